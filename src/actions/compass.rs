@@ -0,0 +1,157 @@
+use constcat::concat;
+use serde_json::{Map, Value};
+
+use streamdeck_lib::prelude::*;
+
+use crate::{
+    PLUGIN_ID,
+    animation::{start_timer_for, stop_timer_for},
+    gw2::{
+        positions::{PositionStore, SavedPosition},
+        shared::SharedMotion,
+    },
+    render::CompassImage,
+    topics::{POSITION_SAVED, TIMER_TICK},
+};
+
+const TIMER_NAME: &str = "compass";
+/// Fast enough for the arrow to follow turning the character.
+const RATE_MS: u64 = 100;
+/// Height difference (units) worth an up/down hint.
+const HEIGHT_HINT_UNITS: f32 = 200.0;
+
+/// Distance and direction to a saved position, relative to the avatar's facing.
+///
+/// `target` names the saved position; empty follows the most recently saved one.
+#[derive(Default)]
+pub struct CompassAction {
+    target_name: Option<String>,
+    target: Option<SavedPosition>,
+    /// Last image sent, so idle keys don't redraw every tick.
+    last_image: Option<String>,
+}
+
+impl ActionStatic for CompassAction {
+    const ID: &'static str = concat!(PLUGIN_ID, ".compass");
+}
+
+impl Action for CompassAction {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn topics(&self) -> &'static [&'static str] {
+        &[TIMER_TICK.name, POSITION_SAVED.name]
+    }
+
+    fn will_appear(&mut self, cx: &Context, ev: &WillAppear) {
+        debug!(cx.log(), "CompassAction will_appear: {:?}", ev.context);
+        self.apply_settings_json(ev.settings);
+        self.load_target(cx);
+        self.last_image = None;
+        cx.sd()
+            .set_title(ev.context, Some(String::new()), None, None);
+        self.refresh(cx, ev.context);
+        start_timer_for(cx, ev.context, TIMER_NAME, RATE_MS);
+    }
+
+    fn will_disappear(&mut self, cx: &Context, ev: &WillDisappear) {
+        stop_timer_for(cx, ev.context, TIMER_NAME);
+    }
+
+    fn did_receive_settings(&mut self, cx: &Context, ev: &DidReceiveSettings) {
+        self.apply_settings_json(ev.settings);
+        self.load_target(cx);
+        self.refresh(cx, ev.context);
+    }
+
+    fn on_notify(&mut self, cx: &Context, ctx_id: &str, event: &ErasedTopic) {
+        if let Some(name) = event.downcast(TIMER_TICK) {
+            if name == TIMER_NAME {
+                self.refresh(cx, ctx_id);
+            }
+            return;
+        }
+        if let Some(name) = event.downcast(POSITION_SAVED)
+            && self.target_name.as_ref().is_none_or(|t| t == name)
+        {
+            self.load_target(cx);
+            self.refresh(cx, ctx_id);
+        }
+    }
+}
+
+// ── Impl details ─────────────────────────────────────────────────────────────
+
+impl CompassAction {
+    fn apply_settings_json(&mut self, settings: &Map<String, Value>) {
+        self.target_name = settings
+            .get("target")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string);
+    }
+
+    fn load_target(&mut self, cx: &Context) {
+        self.target = match PositionStore::open_default()
+            .and_then(|store| store.find(self.target_name.as_deref()))
+        {
+            Ok(target) => target,
+            Err(e) => {
+                error!(cx.log(), "CompassAction: {}", e);
+                None
+            }
+        };
+    }
+
+    fn image(&self, cx: &Context) -> CompassImage {
+        let Some(target) = &self.target else {
+            return CompassImage {
+                header: Some(self.target_name.clone().unwrap_or_else(|| "Compass".into())),
+                footer: Some("Not saved".into()),
+                ..CompassImage::default()
+            };
+        };
+        let header = Some(target.name.clone());
+
+        let position = cx.try_ext::<SharedMotion>().and_then(|m| m.get().position);
+        let Some(me) = position else {
+            return CompassImage {
+                header,
+                ..CompassImage::default()
+            };
+        };
+        if me.map_id != target.map_id {
+            return CompassImage {
+                header,
+                footer: Some("Other map".into()),
+                ..CompassImage::default()
+            };
+        }
+
+        let height = target.height_from(me.pos);
+        let hint = if height >= HEIGHT_HINT_UNITS {
+            " ▲"
+        } else if height <= -HEIGHT_HINT_UNITS {
+            " ▼"
+        } else {
+            ""
+        };
+        CompassImage {
+            header,
+            bearing: target.bearing_from(me.pos, me.front),
+            footer: Some(format!("{:.0}{hint}", target.distance_from(me.pos))),
+            ..CompassImage::default()
+        }
+    }
+
+    fn refresh(&mut self, cx: &Context, ctx_id: &str) {
+        let image = self.image(cx).to_data_uri();
+        if self.last_image.as_deref() == Some(image.as_str()) {
+            return;
+        }
+        cx.sd().set_image(ctx_id, Some(image.clone()), None, None);
+        self.last_image = Some(image);
+    }
+}
@@ -0,0 +1,182 @@
+use constcat::concat;
+use serde_json::{Map, Value, json};
+
+use streamdeck_lib::prelude::*;
+
+use crate::{
+    PLUGIN_ID,
+    gw2::{
+        enums::{KeyControl, UiState},
+        shared::SharedUiState,
+    },
+    topics::{GW2_EXEC_QUEUE, Gw2ExecQueue, MUMBLE_UI_STATE},
+};
+
+/// A pair of opposite controls driven by one dial (clockwise, counter-clockwise).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DialPair {
+    CameraZoom,
+    MapZoom,
+    MapFloor,
+    Targeting,
+}
+
+impl DialPair {
+    const ALL: [DialPair; 4] = [
+        DialPair::CameraZoom,
+        DialPair::MapZoom,
+        DialPair::MapFloor,
+        DialPair::Targeting,
+    ];
+
+    fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.key() == key)
+    }
+
+    /// Value stored in action settings.
+    fn key(self) -> &'static str {
+        match self {
+            DialPair::CameraZoom => "camera_zoom",
+            DialPair::MapZoom => "map_zoom",
+            DialPair::MapFloor => "map_floor",
+            DialPair::Targeting => "targeting",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            DialPair::CameraZoom => "Camera Zoom",
+            DialPair::MapZoom => "Map Zoom",
+            DialPair::MapFloor => "Map Floor",
+            DialPair::Targeting => "Target Enemy",
+        }
+    }
+
+    fn controls(self) -> (KeyControl, KeyControl) {
+        use KeyControl::*;
+        match self {
+            DialPair::CameraZoom => (CameraZoomIn, CameraZoomOut),
+            DialPair::MapZoom => (MapZoomIn, MapZoomOut),
+            DialPair::MapFloor => (MapFloorUp, MapFloorDown),
+            DialPair::Targeting => (TargetingNextEnemy, TargetingPreviousEnemy),
+        }
+    }
+
+    /// Only does something while the world map is open.
+    fn needs_map(self) -> bool {
+        matches!(self, DialPair::MapZoom | DialPair::MapFloor)
+    }
+}
+
+/// Stream Deck+ dial that sends one control per tick.
+///
+/// Rapid ticks are coalesced by the exec adapter into a single job. The touch strip
+/// shows whether the world map is open; tapping it switches to the next control pair.
+#[derive(Default)]
+pub struct DialControlAction {
+    pair: Option<DialPair>,
+    settings: Map<String, Value>,
+}
+
+impl ActionStatic for DialControlAction {
+    const ID: &'static str = concat!(PLUGIN_ID, ".dial-control");
+}
+
+impl Action for DialControlAction {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn topics(&self) -> &'static [&'static str] {
+        &[MUMBLE_UI_STATE.name]
+    }
+
+    fn will_appear(&mut self, cx: &Context, ev: &WillAppear) {
+        debug!(cx.log(), "DialControlAction will_appear: {:?}", ev.context);
+        self.apply_settings_json(ev.settings);
+        self.refresh(cx, ev.context);
+    }
+
+    fn did_receive_settings(&mut self, cx: &Context, ev: &DidReceiveSettings) {
+        self.apply_settings_json(ev.settings);
+        self.refresh(cx, ev.context);
+    }
+
+    fn on_notify(&mut self, cx: &Context, ctx_id: &str, event: &ErasedTopic) {
+        if event.is(MUMBLE_UI_STATE) {
+            self.refresh(cx, ctx_id);
+        }
+    }
+
+    fn dial_rotate(&mut self, cx: &Context, ev: &DialRotate) {
+        let pair = self.pair();
+        if ev.ticks == 0 {
+            return;
+        }
+        let (cw, ccw) = pair.controls();
+        let control = if ev.ticks > 0 { cw } else { ccw };
+
+        cx.bus().publish_t(
+            GW2_EXEC_QUEUE,
+            Gw2ExecQueue {
+                controls: vec![control; ev.ticks.unsigned_abs() as usize],
+                allow_in_combat: true,
+                allow_out_of_combat: true,
+                allow_gliding_or_falling: true,
+                allow_in_water: true,
+                inter_control_ms: Some(10),
+                ttl_ms: None,
+                coalesce: true,
+                origin_ctx: ev.context.into(),
+            },
+        );
+    }
+
+    fn touch_tap(&mut self, cx: &Context, ev: &TouchTap) {
+        let cur = self.pair();
+        let idx = DialPair::ALL.iter().position(|&p| p == cur).unwrap_or(0);
+        let next = DialPair::ALL[(idx + 1) % DialPair::ALL.len()];
+        self.pair = Some(next);
+
+        self.settings.insert("pair".into(), Value::from(next.key()));
+        cx.sd().set_settings(ev.context, self.settings.clone());
+        self.refresh(cx, ev.context);
+    }
+}
+
+// ── Impl details ─────────────────────────────────────────────────────────────
+
+impl DialControlAction {
+    fn apply_settings_json(&mut self, settings: &Map<String, Value>) {
+        self.settings = settings.clone();
+        self.pair = settings
+            .get("pair")
+            .and_then(|v| v.as_str())
+            .and_then(DialPair::from_key);
+    }
+
+    fn pair(&self) -> DialPair {
+        self.pair.unwrap_or(DialPair::CameraZoom)
+    }
+
+    fn refresh(&self, cx: &Context, ctx_id: &str) {
+        let pair = self.pair();
+        let ui = cx
+            .try_ext::<SharedUiState>()
+            .map(|u| u.get())
+            .unwrap_or(UiState::empty());
+
+        let value = match (ui.is_map_open(), pair.needs_map()) {
+            (true, _) => "Map open",
+            (false, true) => "Map closed",
+            (false, false) => "World",
+        };
+        let payload = json!({
+            "title": pair.label(),
+            "value": value,
+        });
+        if let Value::Object(map) = payload {
+            cx.sd().set_feedback(ctx_id, map);
+        }
+    }
+}
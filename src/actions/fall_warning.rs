@@ -0,0 +1,167 @@
+use constcat::concat;
+use serde_json::{Map, Value};
+
+use streamdeck_lib::prelude::*;
+
+use crate::{
+    PLUGIN_ID,
+    animation::{play_animation, stop_animation},
+    gw2::{
+        enums::{FallRisk, KeyControl},
+        shared::SharedMotion,
+    },
+    render::{KeyImage, Overlay, Palette},
+    topics::{ANIMATION_FRAME, AnimationMode, GW2_EXEC_QUEUE, Gw2ExecQueue, MOTION_FALL},
+};
+
+const FLASH: &str = "fall-flash";
+const FLASH_FRAME_MS: u64 = 250;
+/// A jump that didn't go out within this window is useless; we've landed by then.
+const GLIDE_TTL_MS: u64 = 500;
+
+/// Warns about falls that will hurt; flashes while the fall would be lethal.
+///
+/// With `auto_glide` set, a lethal fall also sends one Jump, which opens the
+/// glider when gliding is unlocked.
+#[derive(Default)]
+pub struct FallWarningAction {
+    auto_glide: bool,
+    risk: FallRisk,
+}
+
+impl ActionStatic for FallWarningAction {
+    const ID: &'static str = concat!(PLUGIN_ID, ".fall-warning");
+}
+
+impl Action for FallWarningAction {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn topics(&self) -> &'static [&'static str] {
+        &[MOTION_FALL.name, ANIMATION_FRAME.name]
+    }
+
+    fn will_appear(&mut self, cx: &Context, ev: &WillAppear) {
+        debug!(cx.log(), "FallWarningAction will_appear: {:?}", ev.context);
+        self.apply_settings_json(ev.settings);
+        cx.sd()
+            .set_title(ev.context, Some(String::new()), None, None);
+        self.risk = cx
+            .try_ext::<SharedMotion>()
+            .map(|m| m.get().fall_risk)
+            .unwrap_or_default();
+        self.show(cx, ev.context, 0.0);
+    }
+
+    fn will_disappear(&mut self, cx: &Context, ev: &WillDisappear) {
+        stop_animation(cx, ev.context, FLASH);
+    }
+
+    fn did_receive_settings(&mut self, _cx: &Context, ev: &DidReceiveSettings) {
+        self.apply_settings_json(ev.settings);
+    }
+
+    fn on_notify(&mut self, cx: &Context, ctx_id: &str, event: &ErasedTopic) {
+        if let Some(f) = event.downcast(ANIMATION_FRAME) {
+            if f.name == FLASH {
+                cx.sd().set_image(ctx_id, Some(f.image.clone()), None, None);
+            }
+            return;
+        }
+
+        let Some(alert) = event.downcast(MOTION_FALL) else {
+            return;
+        };
+        if alert.risk == self.risk {
+            return;
+        }
+        let was = self.risk;
+        self.risk = alert.risk;
+        debug!(
+            cx.log(),
+            "FallWarningAction {:?} -> {:?} ({:.0} units)", was, alert.risk, alert.drop
+        );
+
+        if was == FallRisk::Lethal {
+            stop_animation(cx, ctx_id, FLASH);
+        }
+        self.show(cx, ctx_id, alert.drop);
+
+        if alert.risk == FallRisk::Lethal && self.auto_glide {
+            self.glide(cx, ctx_id);
+        }
+    }
+}
+
+// ── Impl details ─────────────────────────────────────────────────────────────
+
+impl FallWarningAction {
+    fn apply_settings_json(&mut self, settings: &Map<String, Value>) {
+        self.auto_glide = settings
+            .get("auto_glide")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+    }
+
+    fn show(&self, cx: &Context, ctx_id: &str, drop: f32) {
+        let image = |overlay| KeyImage {
+            palette: Palette::WARNING,
+            header: Some("Fall".to_string()),
+            name: Some(match self.risk {
+                FallRisk::Safe => "OK".to_string(),
+                FallRisk::Damage => format!("{drop:.0}"),
+                FallRisk::Lethal => "LETHAL".to_string(),
+            }),
+            overlay,
+            ..KeyImage::default()
+        };
+
+        match self.risk {
+            FallRisk::Safe => {
+                let idle = KeyImage {
+                    palette: Palette::default(),
+                    ..image(Overlay::None)
+                };
+                cx.sd()
+                    .set_image(ctx_id, Some(idle.to_data_uri()), None, None);
+            }
+            FallRisk::Damage => {
+                cx.sd()
+                    .set_image(ctx_id, Some(image(Overlay::None).to_data_uri()), None, None);
+            }
+            FallRisk::Lethal => {
+                let frames = vec![
+                    image(Overlay::Running).to_data_uri(),
+                    image(Overlay::None).to_data_uri(),
+                ];
+                play_animation(
+                    cx,
+                    ctx_id,
+                    FLASH,
+                    frames,
+                    FLASH_FRAME_MS,
+                    AnimationMode::Loop,
+                );
+            }
+        }
+    }
+
+    fn glide(&self, cx: &Context, ctx_id: &str) {
+        cx.bus().publish_t(
+            GW2_EXEC_QUEUE,
+            Gw2ExecQueue {
+                controls: vec![KeyControl::MovementJump],
+                allow_in_combat: true,
+                allow_out_of_combat: true,
+                // we're falling by definition
+                allow_gliding_or_falling: true,
+                allow_in_water: true,
+                inter_control_ms: None,
+                ttl_ms: Some(GLIDE_TTL_MS),
+                coalesce: false,
+                origin_ctx: ctx_id.into(),
+            },
+        );
+    }
+}
@@ -0,0 +1,192 @@
+use constcat::concat;
+use serde_json::{Map, Value, json};
+
+use streamdeck_lib::prelude::*;
+
+use crate::{
+    PLUGIN_ID,
+    gw2::{
+        enums::{KeyControl, Mount},
+        shared::{CurrentMount, SharedIdentity},
+    },
+    render::{KeyImage, Overlay, profession_palette},
+    topics::{
+        ExecState, GW2_EXEC_PROGRESS, GW2_EXEC_QUEUE, Gw2ExecQueue, MUMBLE_IDENTITY, MUMBLE_MOUNT,
+    },
+};
+
+/// Summons that can't run right away (combat) are dropped after this long.
+const SUMMON_TTL_MS: u64 = 3000;
+
+/// Summons the configured mount, or dismounts when already on it.
+///
+/// On a Stream Deck+ dial, rotating scrolls through the mounts and pressing
+/// (or tapping the touch strip) summons the shown one.
+#[derive(Default)]
+pub struct MountAction {
+    mount: Option<Mount>,
+    settings: Map<String, Value>,
+    /// Placed on an encoder rather than a key.
+    dial: bool,
+}
+
+impl ActionStatic for MountAction {
+    const ID: &'static str = concat!(PLUGIN_ID, ".mount");
+}
+
+impl Action for MountAction {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn topics(&self) -> &'static [&'static str] {
+        &[
+            MUMBLE_MOUNT.name,
+            MUMBLE_IDENTITY.name,
+            GW2_EXEC_PROGRESS.name,
+        ]
+    }
+
+    fn will_appear(&mut self, cx: &Context, ev: &WillAppear) {
+        debug!(cx.log(), "MountAction will_appear: {:?}", ev.context);
+        self.dial = ev.controller == "Encoder";
+        self.apply_settings_json(ev.settings);
+        if !self.dial {
+            cx.sd()
+                .set_title(ev.context, Some(String::new()), None, None);
+        }
+        self.refresh(cx, ev.context);
+    }
+
+    fn did_receive_settings(&mut self, cx: &Context, ev: &DidReceiveSettings) {
+        self.apply_settings_json(ev.settings);
+        self.refresh(cx, ev.context);
+    }
+
+    fn on_notify(&mut self, cx: &Context, ctx_id: &str, event: &ErasedTopic) {
+        if event.is(MUMBLE_MOUNT) || event.is(MUMBLE_IDENTITY) {
+            self.refresh(cx, ctx_id);
+            return;
+        }
+
+        if let Some(m) = event.downcast(GW2_EXEC_PROGRESS) {
+            if *m == ExecState::Expired {
+                cx.sd().show_alert(ctx_id);
+            }
+        }
+    }
+
+    fn key_up(&mut self, cx: &Context, ev: &KeyUp) {
+        self.toggle(cx, ev.context);
+    }
+
+    fn dial_down(&mut self, cx: &Context, ev: &DialDown) {
+        self.toggle(cx, ev.context);
+    }
+
+    fn touch_tap(&mut self, cx: &Context, ev: &TouchTap) {
+        self.toggle(cx, ev.context);
+    }
+
+    fn dial_rotate(&mut self, cx: &Context, ev: &DialRotate) {
+        let list = &Mount::SUMMONABLE;
+        let cur = self
+            .mount
+            .and_then(|m| list.iter().position(|&x| x == m))
+            .unwrap_or(0) as i32;
+        let next = (cur + ev.ticks).rem_euclid(list.len() as i32) as usize;
+        self.mount = Some(list[next]);
+
+        self.settings
+            .insert("mount".into(), Value::from(list[next].key()));
+        cx.sd().set_settings(ev.context, self.settings.clone());
+        self.refresh(cx, ev.context);
+    }
+}
+
+// ── Impl details ─────────────────────────────────────────────────────────────
+
+impl MountAction {
+    fn apply_settings_json(&mut self, settings: &Map<String, Value>) {
+        self.settings = settings.clone();
+        self.mount = settings
+            .get("mount")
+            .and_then(|v| v.as_str())
+            .and_then(Mount::from_key);
+    }
+
+    /// Summon the mount, or dismount when already riding it.
+    fn toggle(&mut self, cx: &Context, ctx_id: &str) {
+        let Some(mount) = self.mount else {
+            cx.sd().show_alert(ctx_id);
+            return;
+        };
+        let current = cx.try_ext::<CurrentMount>().and_then(|m| m.get());
+
+        let control = if current == Some(mount) {
+            KeyControl::MountsMountDismount
+        } else if let Some(kc) = mount.control() {
+            kc
+        } else {
+            cx.sd().show_alert(ctx_id);
+            return;
+        };
+        debug!(
+            cx.log(),
+            "MountAction {:?} (current {:?}) -> {:?}", mount, current, control
+        );
+
+        cx.bus().publish_t(
+            GW2_EXEC_QUEUE,
+            Gw2ExecQueue {
+                controls: vec![control],
+                allow_in_combat: false,
+                allow_out_of_combat: true,
+                // mounting mid-air works for the flying mounts; let the game decide
+                allow_gliding_or_falling: true,
+                allow_in_water: true,
+                inter_control_ms: None,
+                ttl_ms: Some(SUMMON_TTL_MS),
+                coalesce: false,
+                origin_ctx: ctx_id.into(),
+            },
+        );
+    }
+
+    fn refresh(&self, cx: &Context, ctx_id: &str) {
+        let current = cx.try_ext::<CurrentMount>().and_then(|m| m.get());
+        let identity = cx.try_ext::<SharedIdentity>().and_then(|i| i.get());
+        let riding = self.mount.is_some() && current == self.mount;
+
+        let image = KeyImage {
+            palette: profession_palette(identity.and_then(|i| i.profession)),
+            header: riding.then(|| "Mounted".to_string()),
+            name: Some(self.mount.map_or("Mount", Mount::name).to_string()),
+            overlay: if riding {
+                Overlay::Active
+            } else {
+                Overlay::None
+            },
+            ..KeyImage::default()
+        };
+
+        if self.dial {
+            let value = match current {
+                Some(m) if riding => format!("On {}", m.name()),
+                Some(m) => format!("Riding {}", m.name()),
+                None => "On foot".to_string(),
+            };
+            let payload = json!({
+                "title": self.mount.map_or("Mount", Mount::name),
+                "icon": image.to_data_uri(),
+                "value": value,
+            });
+            if let Value::Object(map) = payload {
+                cx.sd().set_feedback(ctx_id, map);
+            }
+        } else {
+            cx.sd()
+                .set_image(ctx_id, Some(image.to_data_uri()), None, None);
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use constcat::concat;
+use serde_json::{Map, Value};
+
+use streamdeck_lib::prelude::*;
+
+use crate::{
+    PLUGIN_ID,
+    animation::{start_timer_for, stop_timer_for},
+    gw2::{
+        positions::bearing,
+        shared::{SharedMarkers, SharedMotion},
+    },
+    render::CompassImage,
+    topics::{MARKERS_UPDATED, TIMER_TICK},
+};
+
+const TIMER_NAME: &str = "nearest-poi";
+const RATE_MS: u64 = 250;
+
+/// Nearest marker pack POI on the current map: its category, distance and an
+/// arrow relative to the avatar's facing.
+///
+/// `category` limits the search to one category (and its subcategories);
+/// empty follows the selection of the POI category key.
+#[derive(Default)]
+pub struct NearestPoiAction {
+    category: Option<String>,
+    /// Last image sent, so idle keys don't redraw every tick.
+    last_image: Option<String>,
+}
+
+impl ActionStatic for NearestPoiAction {
+    const ID: &'static str = concat!(PLUGIN_ID, ".nearest-poi");
+}
+
+impl Action for NearestPoiAction {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn topics(&self) -> &'static [&'static str] {
+        &[TIMER_TICK.name, MARKERS_UPDATED.name]
+    }
+
+    fn will_appear(&mut self, cx: &Context, ev: &WillAppear) {
+        debug!(cx.log(), "NearestPoiAction will_appear: {:?}", ev.context);
+        self.apply_settings_json(ev.settings);
+        self.last_image = None;
+        cx.sd()
+            .set_title(ev.context, Some(String::new()), None, None);
+        self.refresh(cx, ev.context);
+        start_timer_for(cx, ev.context, TIMER_NAME, RATE_MS);
+    }
+
+    fn will_disappear(&mut self, cx: &Context, ev: &WillDisappear) {
+        stop_timer_for(cx, ev.context, TIMER_NAME);
+    }
+
+    fn did_receive_settings(&mut self, cx: &Context, ev: &DidReceiveSettings) {
+        self.apply_settings_json(ev.settings);
+        self.refresh(cx, ev.context);
+    }
+
+    fn on_notify(&mut self, cx: &Context, ctx_id: &str, event: &ErasedTopic) {
+        if let Some(name) = event.downcast(TIMER_TICK) {
+            if name == TIMER_NAME {
+                self.refresh(cx, ctx_id);
+            }
+            return;
+        }
+        if event.downcast(MARKERS_UPDATED).is_some() {
+            self.refresh(cx, ctx_id);
+        }
+    }
+}
+
+// ── Impl details ─────────────────────────────────────────────────────────────
+
+impl NearestPoiAction {
+    fn apply_settings_json(&mut self, settings: &Map<String, Value>) {
+        self.category = settings
+            .get("category")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string);
+    }
+
+    fn image(&self, cx: &Context) -> CompassImage {
+        let Some(markers) = cx.try_ext::<SharedMarkers>() else {
+            return CompassImage::default();
+        };
+        let category = self.category.clone().or_else(|| markers.selected());
+        let set = markers.get();
+        let header = Some(
+            category
+                .as_deref()
+                .map_or("POI", |c| set.display_name(c))
+                .to_string(),
+        );
+        if set.pois.is_empty() {
+            return CompassImage {
+                header,
+                footer: Some("No markers".into()),
+                ..CompassImage::default()
+            };
+        }
+
+        let position = cx.try_ext::<SharedMotion>().and_then(|m| m.get().position);
+        let Some(me) = position else {
+            return CompassImage {
+                header,
+                ..CompassImage::default()
+            };
+        };
+        let Some((poi, distance)) = set.nearest(me.map_id, me.pos, category.as_deref()) else {
+            return CompassImage {
+                header,
+                footer: Some("None here".into()),
+                ..CompassImage::default()
+            };
+        };
+
+        CompassImage {
+            header: Some(set.display_name(&poi.category).to_string()),
+            bearing: bearing(me.pos, me.front, poi.pos),
+            footer: Some(format!("{distance:.0}")),
+            ..CompassImage::default()
+        }
+    }
+
+    fn refresh(&mut self, cx: &Context, ctx_id: &str) {
+        let image = self.image(cx).to_data_uri();
+        if self.last_image.as_deref() == Some(image.as_str()) {
+            return;
+        }
+        cx.sd().set_image(ctx_id, Some(image.clone()), None, None);
+        self.last_image = Some(image);
+    }
+}
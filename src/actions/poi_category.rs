@@ -0,0 +1,101 @@
+use constcat::concat;
+
+use streamdeck_lib::prelude::*;
+
+use crate::{
+    PLUGIN_ID,
+    gw2::shared::{SharedMarkers, SharedMotion},
+    render::KeyImage,
+    topics::{MARKERS_CATEGORY_CHANGED, MARKERS_UPDATED},
+};
+
+/// Cycles the marker category that nearest-POI keys point at.
+///
+/// Each press moves to the next category with POIs on the current map; after
+/// the last one it goes back to "All".
+#[derive(Default)]
+pub struct PoiCategoryAction;
+
+impl ActionStatic for PoiCategoryAction {
+    const ID: &'static str = concat!(PLUGIN_ID, ".poi-category");
+}
+
+impl Action for PoiCategoryAction {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn topics(&self) -> &'static [&'static str] {
+        &[MARKERS_CATEGORY_CHANGED.name, MARKERS_UPDATED.name]
+    }
+
+    fn will_appear(&mut self, cx: &Context, ev: &WillAppear) {
+        debug!(cx.log(), "PoiCategoryAction will_appear: {:?}", ev.context);
+        cx.sd()
+            .set_title(ev.context, Some(String::new()), None, None);
+        self.show(cx, ev.context);
+    }
+
+    fn on_notify(&mut self, cx: &Context, ctx_id: &str, event: &ErasedTopic) {
+        if event.downcast(MARKERS_CATEGORY_CHANGED).is_some()
+            || event.downcast(MARKERS_UPDATED).is_some()
+        {
+            self.show(cx, ctx_id);
+        }
+    }
+
+    fn key_up(&mut self, cx: &Context, ev: &KeyUp) {
+        let Some(markers) = cx.try_ext::<SharedMarkers>() else {
+            return;
+        };
+        let Some(map_id) = cx
+            .try_ext::<SharedMotion>()
+            .and_then(|m| m.get().position)
+            .map(|p| p.map_id)
+        else {
+            cx.sd().show_alert(ev.context);
+            return;
+        };
+
+        let set = markers.get();
+        let cats = set.categories_on(map_id);
+        let next = match markers.selected() {
+            None => cats.first(),
+            Some(cur) => match cats.iter().position(|c| *c == cur) {
+                Some(i) => cats.get(i + 1),
+                // selection isn't on this map: start over
+                None => cats.first(),
+            },
+        }
+        .map(|c| c.to_string());
+
+        debug!(cx.log(), "PoiCategoryAction -> {:?}", next);
+        markers.set_selected(next.clone());
+        cx.bus().publish_t(MARKERS_CATEGORY_CHANGED, next);
+    }
+}
+
+// ── Impl details ─────────────────────────────────────────────────────────────
+
+impl PoiCategoryAction {
+    fn show(&self, cx: &Context, ctx_id: &str) {
+        let (name, footer) = match cx.try_ext::<SharedMarkers>() {
+            Some(markers) => {
+                let set = markers.get();
+                match markers.selected() {
+                    Some(cat) => (set.display_name(&cat).to_string(), Some(cat)),
+                    None => ("All".to_string(), None),
+                }
+            }
+            None => ("All".to_string(), None),
+        };
+        let image = KeyImage {
+            header: Some("Markers".to_string()),
+            name: Some(name),
+            footer,
+            ..KeyImage::default()
+        };
+        cx.sd()
+            .set_image(ctx_id, Some(image.to_data_uri()), None, None);
+    }
+}
@@ -0,0 +1,107 @@
+use constcat::concat;
+use serde_json::{Map, Value};
+
+use streamdeck_lib::prelude::*;
+
+use crate::{
+    PLUGIN_ID,
+    gw2::{
+        positions::{PositionStore, SavedPosition},
+        shared::SharedMotion,
+    },
+    render::KeyImage,
+    topics::POSITION_SAVED,
+};
+
+/// Saves the current map and avatar position under a name.
+///
+/// Without a configured `name`, each press saves a new position named after
+/// the time of day. Compass keys point back at saved positions.
+#[derive(Default)]
+pub struct SavePositionAction {
+    name: Option<String>,
+}
+
+impl ActionStatic for SavePositionAction {
+    const ID: &'static str = concat!(PLUGIN_ID, ".save-position");
+}
+
+impl Action for SavePositionAction {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn will_appear(&mut self, cx: &Context, ev: &WillAppear) {
+        debug!(cx.log(), "SavePositionAction will_appear: {:?}", ev.context);
+        self.apply_settings_json(ev.settings);
+        cx.sd()
+            .set_title(ev.context, Some(String::new()), None, None);
+        self.show(cx, ev.context);
+    }
+
+    fn did_receive_settings(&mut self, cx: &Context, ev: &DidReceiveSettings) {
+        self.apply_settings_json(ev.settings);
+        self.show(cx, ev.context);
+    }
+
+    fn key_up(&mut self, cx: &Context, ev: &KeyUp) {
+        let position = cx.try_ext::<SharedMotion>().and_then(|m| m.get().position);
+        let Some(position) = position else {
+            warn!(cx.log(), "SavePositionAction: no position (not in a map?)");
+            cx.sd().show_alert(ev.context);
+            return;
+        };
+
+        let name = self
+            .name
+            .clone()
+            .unwrap_or_else(|| chrono::Local::now().format("%H:%M:%S").to_string());
+        let saved = PositionStore::open_default().and_then(|store| {
+            store.save(SavedPosition {
+                name: name.clone(),
+                map_id: position.map_id,
+                pos: position.pos,
+            })
+        });
+        match saved {
+            Ok(()) => {
+                info!(
+                    cx.log(),
+                    "SavePositionAction saved '{}' on map {} at {:?}",
+                    name,
+                    position.map_id,
+                    position.pos
+                );
+                cx.bus().publish_t(POSITION_SAVED, name);
+                cx.sd().show_ok(ev.context);
+            }
+            Err(e) => {
+                error!(cx.log(), "SavePositionAction failed: {}", e);
+                cx.sd().show_alert(ev.context);
+            }
+        }
+    }
+}
+
+// ── Impl details ─────────────────────────────────────────────────────────────
+
+impl SavePositionAction {
+    fn apply_settings_json(&mut self, settings: &Map<String, Value>) {
+        self.name = settings
+            .get("name")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string);
+    }
+
+    fn show(&self, cx: &Context, ctx_id: &str) {
+        let image = KeyImage {
+            header: Some("Save".to_string()),
+            name: Some(self.name.clone().unwrap_or_else(|| "Here".to_string())),
+            ..KeyImage::default()
+        };
+        cx.sd()
+            .set_image(ctx_id, Some(image.to_data_uri()), None, None);
+    }
+}
@@ -0,0 +1,187 @@
+use constcat::concat;
+use serde_json::{Map, Value};
+
+use streamdeck_lib::prelude::*;
+
+use crate::{
+    PLUGIN_ID,
+    animation::{start_timer_for, stop_timer_for},
+    gw2::{
+        session::{MoveClass, Tally, format_distance, format_duration},
+        shared::{ActiveChar, SharedMotion, SharedSession},
+    },
+    render::KeyImage,
+    topics::{SESSION_EXPORT, TIMER_TICK},
+};
+
+const TIMER_NAME: &str = "session-stats";
+/// The session adapter shares new numbers once a second.
+const RATE_MS: u64 = 1000;
+
+/// Which number the key shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Stat {
+    #[default]
+    Time,
+    Combat,
+    Glide,
+    /// All movement classes when None.
+    Distance(Option<MoveClass>),
+    MapChanges,
+    FallDeaths,
+}
+
+/// Shows one statistic of the current game session for the active character,
+/// over the whole session or only the current map.
+///
+/// Pressing the key writes the session summary (JSON) right away; it is
+/// written on game exit either way.
+#[derive(Default)]
+pub struct SessionStatsAction {
+    stat: Stat,
+    this_map: bool,
+    /// Last image sent, so idle keys don't redraw every tick.
+    last_image: Option<String>,
+}
+
+impl ActionStatic for SessionStatsAction {
+    const ID: &'static str = concat!(PLUGIN_ID, ".session-stats");
+}
+
+impl Action for SessionStatsAction {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn topics(&self) -> &'static [&'static str] {
+        &[TIMER_TICK.name]
+    }
+
+    fn will_appear(&mut self, cx: &Context, ev: &WillAppear) {
+        debug!(cx.log(), "SessionStatsAction will_appear: {:?}", ev.context);
+        self.apply_settings_json(ev.settings);
+        self.last_image = None;
+        cx.sd()
+            .set_title(ev.context, Some(String::new()), None, None);
+        self.refresh(cx, ev.context);
+        start_timer_for(cx, ev.context, TIMER_NAME, RATE_MS);
+    }
+
+    fn will_disappear(&mut self, cx: &Context, ev: &WillDisappear) {
+        stop_timer_for(cx, ev.context, TIMER_NAME);
+    }
+
+    fn did_receive_settings(&mut self, cx: &Context, ev: &DidReceiveSettings) {
+        self.apply_settings_json(ev.settings);
+        self.last_image = None;
+        self.refresh(cx, ev.context);
+    }
+
+    fn on_notify(&mut self, cx: &Context, ctx_id: &str, event: &ErasedTopic) {
+        if let Some(name) = event.downcast(TIMER_TICK)
+            && name == TIMER_NAME
+        {
+            self.refresh(cx, ctx_id);
+        }
+    }
+
+    fn key_up(&mut self, cx: &Context, ev: &KeyUp) {
+        if cx
+            .try_ext::<SharedSession>()
+            .and_then(|s| s.get())
+            .is_none()
+        {
+            // game not running, no session to write
+            cx.sd().show_alert(ev.context);
+            return;
+        }
+        cx.bus().publish_t(SESSION_EXPORT, ());
+        cx.sd().show_ok(ev.context);
+    }
+}
+
+// ── Impl details ─────────────────────────────────────────────────────────────
+
+impl SessionStatsAction {
+    fn apply_settings_json(&mut self, settings: &Map<String, Value>) {
+        let text = |key| settings.get(key).and_then(|v| v.as_str()).unwrap_or("");
+        self.stat = match text("stat") {
+            "combat" => Stat::Combat,
+            "glide" => Stat::Glide,
+            "distance" => Stat::Distance(
+                settings
+                    .get("move_class")
+                    .and_then(|v| serde_json::from_value(v.clone()).ok()),
+            ),
+            "map_changes" => Stat::MapChanges,
+            "fall_deaths" => Stat::FallDeaths,
+            _ => Stat::Time,
+        };
+        self.this_map = text("scope") == "map";
+    }
+
+    fn header(&self) -> &'static str {
+        match self.stat {
+            Stat::Time => "Played",
+            Stat::Combat => "In combat",
+            Stat::Glide => "Gliding",
+            Stat::Distance(None) => "Distance",
+            Stat::Distance(Some(class)) => class.label(),
+            Stat::MapChanges => "Maps",
+            Stat::FallDeaths => "Fall deaths",
+        }
+    }
+
+    fn value(&self, tally: &Tally) -> String {
+        match self.stat {
+            Stat::Time => format_duration(tally.time_ms),
+            Stat::Combat => format_duration(tally.combat_ms),
+            Stat::Glide => format_duration(tally.glide_ms),
+            Stat::Distance(class) => format_distance(tally.distance(class)),
+            Stat::MapChanges => tally.map_changes.to_string(),
+            Stat::FallDeaths => tally.fall_deaths.to_string(),
+        }
+    }
+
+    fn image(&self, cx: &Context) -> KeyImage {
+        let character = cx.try_ext::<ActiveChar>().and_then(|c| c.get());
+        let map_id = if self.this_map {
+            cx.try_ext::<SharedMotion>()
+                .and_then(|m| m.get().position)
+                .map(|p| p.map_id)
+        } else {
+            None
+        };
+        let tally = cx
+            .try_ext::<SharedSession>()
+            .and_then(|s| s.get())
+            .zip(character.as_deref())
+            .and_then(|(stats, character)| {
+                if self.this_map && map_id.is_none() {
+                    return None;
+                }
+                stats.tally(character, map_id).cloned()
+            })
+            .unwrap_or_default();
+
+        KeyImage {
+            header: Some(self.header().to_string()),
+            name: Some(self.value(&tally)),
+            footer: Some(if self.this_map {
+                "This map".to_string()
+            } else {
+                character.unwrap_or_else(|| "Session".to_string())
+            }),
+            ..KeyImage::default()
+        }
+    }
+
+    fn refresh(&mut self, cx: &Context, ctx_id: &str) {
+        let image = self.image(cx).to_data_uri();
+        if self.last_image.as_deref() == Some(image.as_str()) {
+            return;
+        }
+        cx.sd().set_image(ctx_id, Some(image.clone()), None, None);
+        self.last_image = Some(image);
+    }
+}
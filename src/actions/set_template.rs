@@ -1,23 +1,32 @@
 use constcat::concat;
 use serde_json::{Map, Value};
-use std::fmt::Write as _;
 
 use streamdeck_lib::prelude::*;
 
 use crate::{
     PLUGIN_ID,
+    actions::title_template::{TitleDep, TitleTemplate, TitleVars},
+    animation::{play_animation, stop_animation},
     gw2::{
-        enums::{KeyControl, TemplateNames}, // { build: [Option<String>; 9], equipment: [Option<String>; 9] }
-        shared::{ActiveChar, TemplateStore},
+        enums::{ActiveTabs, KeyControl, TemplateNames, elite_spec_name, profession_name}, // { build: [Option<String>; 9], equipment: [Option<String>; 9] }
+        shared::{ActiveChar, SharedIdentity, TemplateStore},
     },
+    render::{KeyImage, Overlay},
     topics::{
-        ExecState, GW2_ANIMATION_TICK, GW2_API_CHARACTER_CHANGED, GW2_API_TEMPLATE_CHANGED,
-        GW2_EXEC_PROGRESS, GW2_EXEC_QUEUE, Gw2ExecQueue, MUMBLE_ACTIVE_CHARACTER,
+        ANIMATION_FRAME, AnimationMode, BlockReason, ExecState, GW2_API_CHARACTER_CHANGED,
+        GW2_API_TEMPLATE_CHANGED, GW2_API_VERIFY_RESULT, GW2_API_VERIFY_TEMPLATE,
+        GW2_EXEC_PROGRESS, GW2_EXEC_QUEUE, GW2_EXEC_WAITING, GW2_TEMPLATE_ACTIVE_CHANGED,
+        Gw2ApiVerifyTemplate, Gw2ExecQueue, Gw2ExecWaiting, Gw2TemplateActiveChanged,
+        MUMBLE_ACTIVE_CHARACTER, MUMBLE_IDENTITY, VerifyOutcome,
     },
 };
 
 // ── Action ────────────────────────────────────────────────────────────────────
 
+const IDLE_IMAGE: &str = "imgs/set_template/template_idle";
+/// Shown instead of the idle image when the configured slots are the active ones.
+const ACTIVE_IMAGE: &str = "imgs/set_template/template_active";
+
 const QUEUED_SET: &[&str] = &[
     "imgs/set_template/template_pulse_q_0",
     "imgs/set_template/template_pulse_q_1",
@@ -30,6 +39,11 @@ const RUNNING_SET: &[&str] = &[
     "imgs/set_template/template_pulse_r_2",
 ];
 
+/// Name of the queued/running pulse in the animation adapter.
+const EXEC_ANIMATION: &str = "exec";
+const EXEC_FRAME_MS: u64 = 300;
+
+#[derive(Clone, Copy)]
 enum AnimSet {
     Queued,
     Running,
@@ -39,11 +53,18 @@ enum AnimSet {
 pub struct SetTemplateAction {
     selected_build: Option<u8>,     // 1..=9
     selected_equipment: Option<u8>, // 1..=9
+    verify_via_api: bool,
+    /// Let swaps through while underwater; off by default.
+    allow_in_water: bool,
+    /// Give up on a blocked swap after this long; None waits indefinitely.
+    ttl_ms: Option<u64>,
+    /// Render the key image from state instead of using the static SVGs.
+    dynamic_image: bool,
+    /// Compiled `title_template` setting (default when empty or invalid).
+    title: TitleTemplate,
     last_title: Option<String>,
 
     anim_running: bool,
-    anim_phase: u8,            // 0..2
-    anim_set: Option<AnimSet>, // which set to use
 }
 
 impl ActionStatic for SetTemplateAction {
@@ -55,13 +76,19 @@ impl Action for SetTemplateAction {
         Self::ID
     }
 
+    /// Lists every topic a title could read; `on_notify` drops the ones this key's
+    /// template doesn't use (see `TitleDep`) before recomputing the title.
     fn topics(&self) -> &'static [&'static str] {
         &[
             MUMBLE_ACTIVE_CHARACTER.name,
-            MUMBLE_ACTIVE_CHARACTER.name,
+            MUMBLE_IDENTITY.name,
             GW2_API_CHARACTER_CHANGED.name,
+            GW2_API_TEMPLATE_CHANGED.name,
+            GW2_TEMPLATE_ACTIVE_CHANGED.name,
             GW2_EXEC_PROGRESS.name,
-            GW2_ANIMATION_TICK.name,
+            GW2_EXEC_WAITING.name,
+            GW2_API_VERIFY_RESULT.name,
+            ANIMATION_FRAME.name,
         ]
     }
 
@@ -76,27 +103,54 @@ impl Action for SetTemplateAction {
 
     fn did_receive_settings(&mut self, cx: &Context, ev: &DidReceiveSettings) {
         self.apply_settings_json(ev.settings, cx, ev.context);
+        if !self.anim_running {
+            self.refresh_image(cx, ev.context);
+        }
     }
 
     fn will_appear(&mut self, cx: &Context, ev: &WillAppear) {
         debug!(cx.log(), "SetTemplateAction will_appear: {:?}", ev.context);
         self.apply_settings_json(ev.settings, cx, ev.context);
         debug!(cx.log(), "Setting idle icon");
-        cx.sd().set_image(
-            ev.context,
-            Some("imgs/set_template/template_idle".into()),
-            None,
-            None,
-        );
+        self.refresh_image(cx, ev.context);
         debug!(cx.log(), "Refreshing title for context: {}", ev.context);
         self.refresh_title(cx, ev.context);
     }
 
+    fn will_disappear(&mut self, cx: &Context, ev: &WillDisappear) {
+        // a job still waiting in the queue would keep animating a hidden key
+        self.anim_running = false;
+        stop_animation(cx, ev.context, EXEC_ANIMATION);
+    }
+
     fn on_notify(&mut self, cx: &Context, ctx_id: &str, event: &ErasedTopic) {
-        // Recompute title whenever inputs it depends on might change.
+        // Recompute the title only when an input its template reads might change.
         if let Some(m) = event.downcast(MUMBLE_ACTIVE_CHARACTER) {
             debug!(cx.log(), "Received Mumble active character event: {:?}", m);
-            self.refresh_title(cx, ctx_id);
+            if self.title.depends_on(TitleDep::ActiveCharacter) {
+                self.refresh_title(cx, ctx_id);
+            }
+            if !self.anim_running {
+                self.refresh_image(cx, ctx_id);
+            }
+            return;
+        }
+
+        if event.is(MUMBLE_IDENTITY) {
+            if self.title.depends_on(TitleDep::Identity) {
+                self.refresh_title(cx, ctx_id);
+            }
+            if self.dynamic_image && !self.anim_running {
+                self.refresh_image(cx, ctx_id); // profession colors
+            }
+            return;
+        }
+
+        if let Some(m) = event.downcast(GW2_TEMPLATE_ACTIVE_CHANGED) {
+            debug!(cx.log(), "Received active template change: {:?}", m);
+            if m.name == active_character(cx) && !self.anim_running {
+                self.refresh_image(cx, ctx_id);
+            }
             return;
         }
 
@@ -111,7 +165,7 @@ impl Action for SetTemplateAction {
                 .and_then(|a| a.get())
                 .unwrap_or_default(); // "" allowed
 
-            if m.name == active {
+            if m.name == active && self.title.depends_on(TitleDep::TemplateNames) {
                 debug!(
                     cx.log(),
                     "Refreshing title due to GW2 API change for active character: {}", active
@@ -129,7 +183,7 @@ impl Action for SetTemplateAction {
                 .and_then(|a| a.get())
                 .unwrap_or_default(); // "" allowed
 
-            if m.name == active {
+            if m.name == active && self.title.depends_on(TitleDep::TemplateNames) {
                 debug!(
                     cx.log(),
                     "Refreshing title due to GW2 API template change for active character: {}",
@@ -137,6 +191,9 @@ impl Action for SetTemplateAction {
                 );
                 self.refresh_title(cx, ctx_id);
             }
+            if m.name == active && self.dynamic_image && !self.anim_running {
+                self.refresh_image(cx, ctx_id); // template names
+            }
             return;
         }
 
@@ -145,63 +202,61 @@ impl Action for SetTemplateAction {
             match m {
                 ExecState::Queued => {
                     self.anim_running = true;
-                    self.anim_phase = 0;
-                    self.anim_set = Some(AnimSet::Queued);
-                    cx.sd()
-                        .set_image(ctx_id, Some(QUEUED_SET[0].into()), None, None);
+                    self.start_animation(cx, ctx_id, AnimSet::Queued);
                 }
                 ExecState::Started => {
                     self.anim_running = true;
-                    self.anim_phase = 0;
-                    self.anim_set = Some(AnimSet::Running);
-                    cx.sd()
-                        .set_image(ctx_id, Some(RUNNING_SET[0].into()), None, None);
+                    self.start_animation(cx, ctx_id, AnimSet::Running);
+                    self.refresh_title(cx, ctx_id); // clear the waiting countdown
+                }
+                ExecState::Expired => {
+                    self.anim_running = false;
+                    stop_animation(cx, ctx_id, EXEC_ANIMATION);
+                    self.refresh_image(cx, ctx_id);
+                    self.refresh_title(cx, ctx_id);
+                    cx.sd().show_alert(ctx_id);
                 }
                 ExecState::Done => {
                     self.anim_running = false;
-                    self.anim_phase = 0;
-                    self.anim_set = None;
-                    cx.sd().set_image(
-                        ctx_id,
-                        Some("imgs/set_template/template_idle".into()),
-                        None,
-                        None,
-                    );
+                    stop_animation(cx, ctx_id, EXEC_ANIMATION);
+                    self.refresh_title(cx, ctx_id);
+                    mark_applied(cx, self.selected_build, self.selected_equipment);
+                    self.refresh_image(cx, ctx_id);
+                    if self.verify_via_api {
+                        request_verification(
+                            cx,
+                            ctx_id,
+                            self.selected_build,
+                            self.selected_equipment,
+                        );
+                    }
                 }
             }
             return;
         }
 
-        if event.is(GW2_ANIMATION_TICK) {
+        if let Some(m) = event.downcast(GW2_EXEC_WAITING) {
             if self.anim_running {
-                self.anim_phase = (self.anim_phase + 1) % 3;
-                match self.anim_set {
-                    Some(AnimSet::Queued) => {
-                        cx.sd().set_image(
-                            ctx_id,
-                            Some(QUEUED_SET[self.anim_phase as usize].into()),
-                            None,
-                            None,
-                        );
-                    }
-                    Some(AnimSet::Running) => {
-                        cx.sd().set_image(
-                            ctx_id,
-                            Some(RUNNING_SET[self.anim_phase as usize].into()),
-                            None,
-                            None,
-                        );
-                    }
-                    None => {
-                        // shouldn't happen
-                        cx.sd().set_image(
-                            ctx_id,
-                            Some("imgs/set_template/template_idle".into()),
-                            None,
-                            None,
-                        );
-                    }
-                }
+                let title = waiting_title(m);
+                self.last_title = Some(title.clone());
+                cx.sd().set_title(ctx_id, Some(title), None, None);
+            }
+            return;
+        }
+
+        if let Some(m) = event.downcast(GW2_API_VERIFY_RESULT) {
+            debug!(cx.log(), "Received template verification result: {:?}", m);
+            match m {
+                VerifyOutcome::Verified => cx.sd().show_ok(ctx_id),
+                VerifyOutcome::Mismatch { .. } => cx.sd().show_alert(ctx_id),
+            }
+            return;
+        }
+
+        if let Some(m) = event.downcast(ANIMATION_FRAME) {
+            // Late frames can still arrive after Done stopped the animation.
+            if self.anim_running && m.name == EXEC_ANIMATION {
+                cx.sd().set_image(ctx_id, Some(m.image.clone()), None, None);
             }
             return;
         }
@@ -233,7 +288,10 @@ impl Action for SetTemplateAction {
                 allow_in_combat: false,
                 allow_out_of_combat: true,
                 allow_gliding_or_falling: false,
+                allow_in_water: self.allow_in_water,
                 inter_control_ms: None, // optional pacing between controls
+                ttl_ms: self.ttl_ms,
+                coalesce: false,
                 origin_ctx: ev.context.into(),
             },
         );
@@ -252,9 +310,7 @@ impl Action for SetTemplateAction {
 
 impl SetTemplateAction {
     fn refresh_title(&mut self, cx: &Context, cx_id: &str) {
-        let title = self
-            .compute_title(cx)
-            .unwrap_or_else(|| self.compute_fallback_title());
+        let title = self.compute_title(cx);
         if self.last_title.as_deref() != Some(title.as_str()) {
             self.last_title = Some(title.clone());
             cx.sd().set_title(cx_id, Some(title), None, None);
@@ -274,122 +330,285 @@ impl SetTemplateAction {
             .and_then(|n| u8::try_from(n).ok())
             .filter(|&n| (1..=9).contains(&n));
 
+        self.verify_via_api = settings
+            .get("verify_via_api")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        self.allow_in_water = settings
+            .get("allow_in_water")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        self.ttl_ms = ttl_ms_setting(settings);
+
+        self.dynamic_image = settings
+            .get("dynamic_image")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        self.title = match settings
+            .get("title_template")
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.trim().is_empty())
+        {
+            Some(src) => TitleTemplate::parse(src).unwrap_or_else(|e| {
+                warn!(cx.log(), "{e}; using the default title");
+                TitleTemplate::default()
+            }),
+            None => TitleTemplate::default(),
+        };
+
         debug!(
             cx.log(),
-            "settings -> build={:?} equipment={:?}", self.selected_build, self.selected_equipment
+            "settings -> build={:?} equipment={:?} verify={}",
+            self.selected_build,
+            self.selected_equipment,
+            self.verify_via_api
         );
 
-        // Try to build a pretty title using TemplateStore + active character ("" allowed)
-        let title = self
-            .compute_title(cx)
-            .unwrap_or_else(|| self.compute_fallback_title());
-
-        cx.sd().set_title(cx_id, Some(title), None, None);
+        self.last_title = None;
+        self.refresh_title(cx, cx_id);
     }
 
-    fn compute_title(&self, cx: &Context) -> Option<String> {
-        let Some(active_ext) = cx.try_ext::<ActiveChar>() else {
-            return None;
-        };
-
-        let active_name_opt = active_ext.get();
-        let active_name = match active_name_opt.as_deref() {
-            Some(name) if !name.is_empty() => name,
-            _ => {
-                return None;
-            } // No active character or empty name
+    fn compute_title(&self, cx: &Context) -> String {
+        let character = active_character(cx);
+        let names: Option<TemplateNames> = if character.is_empty() {
+            None
+        } else {
+            cx.try_ext::<TemplateStore>()
+                .and_then(|s| s.get(&character))
         };
-
-        let store = cx.try_ext::<TemplateStore>()?;
-        let names: TemplateNames = match store.get(active_name) {
-            Some(n) => n.clone(),
-            None => {
-                return None;
-            }
+        let identity = cx.try_ext::<SharedIdentity>().and_then(|i| i.get());
+
+        let vars = TitleVars {
+            build_name: self.selected_build.and_then(|b| {
+                names
+                    .as_ref()
+                    .and_then(|n| n.build.get(b as usize - 1).cloned().flatten())
+            }),
+            equip_name: self.selected_equipment.and_then(|e| {
+                names
+                    .as_ref()
+                    .and_then(|n| n.equipment.get(e as usize - 1).cloned().flatten())
+            }),
+            profession: identity
+                .as_ref()
+                .and_then(|i| i.profession)
+                .and_then(profession_name)
+                .map(str::to_string),
+            spec: identity
+                .as_ref()
+                .and_then(|i| i.spec)
+                .and_then(elite_spec_name)
+                .map(str::to_string),
+            map: identity
+                .as_ref()
+                .and_then(|i| i.map_id)
+                .map(|id| id.to_string()),
+            character: Some(character),
+            build_slot: self.selected_build,
+            equip_slot: self.selected_equipment,
         };
 
-        let mut parts: Vec<String> = Vec::new();
-
-        if let Some(b) = self.selected_build {
-            let idx = (b as usize).saturating_sub(1);
-            let label = names
-                .build
-                .get(idx)
-                .and_then(|o| o.as_ref())
-                .map(|s| wrap_title_or_fallback(s, &format!("Build {b}"), 10))
-                .unwrap_or_else(|| format!("Build {b}"));
-            parts.push(label);
+        let title = self.title.render(&vars);
+        if title.trim().is_empty() {
+            "Set Template".to_string()
+        } else {
+            title
         }
+    }
 
-        if let Some(e) = self.selected_equipment {
-            let idx = (e as usize).saturating_sub(1);
-            let label = names
-                .equipment
-                .get(idx)
-                .and_then(|o| o.as_ref())
-                .map(|s| wrap_title_or_fallback(s, &format!("Equip {e}"), 10))
-                .unwrap_or_else(|| format!("Equip {e}"));
-            parts.push(label);
-        }
+    /// Idle image, or the "active" variant when the configured slots match the
+    /// active character's current tabs.
+    fn refresh_image(&self, cx: &Context, cx_id: &str) {
+        let active = self.is_active(cx);
+        let image = if self.dynamic_image {
+            let overlay = if active {
+                Overlay::Active
+            } else {
+                Overlay::None
+            };
+            self.render_image(cx, overlay, None)
+        } else if active {
+            ACTIVE_IMAGE.to_string()
+        } else {
+            IDLE_IMAGE.to_string()
+        };
+        cx.sd().set_image(cx_id, Some(image), None, None);
+    }
 
-        if parts.is_empty() {
+    /// Loops the queued/running pulse until exec reports Done.
+    fn start_animation(&self, cx: &Context, cx_id: &str, set: AnimSet) {
+        let frames: Vec<String> = match (set, self.dynamic_image) {
+            (AnimSet::Queued, false) => QUEUED_SET.iter().map(|s| s.to_string()).collect(),
+            (AnimSet::Running, false) => RUNNING_SET.iter().map(|s| s.to_string()).collect(),
+            (AnimSet::Queued, true) => vec![self.render_image(cx, Overlay::Queued, None)],
+            (AnimSet::Running, true) => (1..=3)
+                .map(|i| self.render_image(cx, Overlay::Running, Some(i as f32 / 3.0)))
+                .collect(),
+        };
+        play_animation(
+            cx,
+            cx_id,
+            EXEC_ANIMATION,
+            frames,
+            EXEC_FRAME_MS,
+            AnimationMode::Loop,
+        );
+    }
+
+    fn render_image(&self, cx: &Context, overlay: Overlay, progress: Option<f32>) -> String {
+        let character = active_character(cx);
+        let names: Option<TemplateNames> = if character.is_empty() {
             None
         } else {
-            Some(parts.join("\n"))
+            cx.try_ext::<TemplateStore>()
+                .and_then(|s| s.get(&character))
+        };
+        let identity = cx.try_ext::<SharedIdentity>().and_then(|i| i.get());
+
+        let header = match (self.selected_build, self.selected_equipment) {
+            (Some(b), Some(e)) => Some(format!("B{b} · E{e}")),
+            (Some(b), None) => Some(format!("B{b}")),
+            (None, Some(e)) => Some(format!("E{e}")),
+            (None, None) => None,
+        };
+        // Prefer the build name; equipment-only keys show the equipment name.
+        let name = self
+            .selected_build
+            .and_then(|b| {
+                names
+                    .as_ref()
+                    .and_then(|n| n.build.get(b as usize - 1).cloned().flatten())
+            })
+            .or_else(|| {
+                self.selected_equipment.and_then(|e| {
+                    names
+                        .as_ref()
+                        .and_then(|n| n.equipment.get(e as usize - 1).cloned().flatten())
+                })
+            });
+
+        KeyImage {
+            header,
+            name,
+            overlay,
+            progress,
+            ..KeyImage::default()
         }
+        .with_identity(identity.as_ref())
+        .to_data_uri()
     }
 
-    fn compute_fallback_title(&self) -> String {
-        match (self.selected_build, self.selected_equipment) {
-            (Some(b), Some(e)) => format!("B{b} / E{e}"),
-            (Some(b), None) => format!("B{b}"),
-            (None, Some(e)) => format!("E{e}"),
-            _ => "Set Template".to_string(),
+    fn is_active(&self, cx: &Context) -> bool {
+        if self.selected_build.is_none() && self.selected_equipment.is_none() {
+            return false;
         }
+        let character = active_character(cx);
+        if character.is_empty() {
+            return false;
+        }
+        let Some(active) = cx
+            .try_ext::<TemplateStore>()
+            .and_then(|s| s.get_active(&character))
+        else {
+            return false;
+        };
+
+        self.selected_build.is_none_or(|b| active.build == Some(b))
+            && self
+                .selected_equipment
+                .is_none_or(|e| active.equipment == Some(e))
     }
 }
 
 // ── helpers ──────────────────────────────────────────────────────────────────
 
-fn wrap_title_or_fallback(name: &str, fallback: &str, max_len: usize) -> String {
-    if name.is_empty() {
-        return fallback.to_string();
+/// After a swap finished, record the slots as active right away instead of waiting
+/// for the API (which lags behind by minutes) and tell the other keys.
+pub(crate) fn mark_applied(cx: &Context, build: Option<u8>, equipment: Option<u8>) {
+    let character = active_character(cx);
+    if character.is_empty() {
+        return;
+    }
+    let Some(store) = cx.try_ext::<TemplateStore>() else {
+        return;
+    };
+
+    let prev = store.get_active(&character).unwrap_or_default();
+    let active = ActiveTabs {
+        build: build.or(prev.build),
+        equipment: equipment.or(prev.equipment),
+    };
+    if store.set_active(character.clone(), active) {
+        cx.bus().publish_t(
+            GW2_TEMPLATE_ACTIVE_CHANGED,
+            Gw2TemplateActiveChanged {
+                name: character,
+                active,
+            },
+        );
     }
-    wrap_title(name, max_len)
 }
 
-fn wrap_title(title: &str, max_len: usize) -> String {
-    let mut out = String::new();
-    let mut cur_len = 0usize;
-
-    for (i, word) in title.split_whitespace().enumerate() {
-        let need = if i == 0 { word.len() } else { 1 + word.len() };
-        if cur_len > 0 && cur_len + need > max_len {
-            out.push('\n');
-            out.push_str(word);
-            cur_len = word.len();
-        } else {
-            if i != 0 && cur_len > 0 {
-                out.push(' ');
-                cur_len += 1;
-            }
-            out.push_str(word);
-            cur_len += word.len();
-        }
+/// Ask the API adapter to confirm the swap; the outcome arrives on
+/// `GW2_API_VERIFY_RESULT` for `ctx_id`.
+pub(crate) fn request_verification(
+    cx: &Context,
+    ctx_id: &str,
+    build: Option<u8>,
+    equipment: Option<u8>,
+) {
+    let character = active_character(cx);
+    if character.is_empty() {
+        return;
     }
+    cx.bus().publish_t(
+        GW2_API_VERIFY_TEMPLATE,
+        Gw2ApiVerifyTemplate {
+            name: character,
+            expected: ActiveTabs { build, equipment },
+            origin_ctx: ctx_id.into(),
+        },
+    );
+}
 
-    if out.is_empty() {
-        // title had no whitespace or was empty — still clamp hard to avoid long single-line strings
-        let mut s = String::new();
-        let _ = write!(&mut s, "{title}");
-        return s;
+/// "Combat / 12s / 18s left" style title while a swap is held back by the exec gates.
+pub(crate) fn waiting_title(w: &Gw2ExecWaiting) -> String {
+    let reason = match w.reason {
+        BlockReason::Combat => "Combat",
+        BlockReason::OutOfCombat => "No combat",
+        BlockReason::Airborne => "Airborne",
+        BlockReason::LandingGrace => "Landing",
+        BlockReason::InWater => "In water",
+        BlockReason::Queue => "Queued",
+    };
+    let mut title = format!("{reason}\n{}s", w.waited_ms / 1000);
+    if let Some(left) = w.remaining_ms {
+        title.push_str(&format!("\n{}s left", left.div_ceil(1000)));
     }
+    title
+}
+
+/// The `ttl_s` setting in milliseconds; None (wait forever) when unset or not positive.
+pub(crate) fn ttl_ms_setting(settings: &Map<String, Value>) -> Option<u64> {
+    settings
+        .get("ttl_s")
+        // text fields may hand us a string
+        .and_then(|v| v.as_f64().or_else(|| v.as_str()?.trim().parse().ok()))
+        .filter(|s| *s > 0.0)
+        .map(|s| (s * 1000.0) as u64)
+}
 
-    out
+pub(crate) fn active_character(cx: &Context) -> String {
+    cx.try_ext::<ActiveChar>()
+        .and_then(|a| a.get())
+        .unwrap_or_default() // "" allowed
 }
 
 // Same mapping you had before
-fn build_slot_to_control(n: u8) -> Option<KeyControl> {
+pub(crate) fn build_slot_to_control(n: u8) -> Option<KeyControl> {
     use KeyControl::*;
     Some(match n {
         1 => TemplatesBuildTemplate1,
@@ -406,7 +625,7 @@ fn build_slot_to_control(n: u8) -> Option<KeyControl> {
         }
     })
 }
-fn equipment_slot_to_control(n: u8) -> Option<KeyControl> {
+pub(crate) fn equipment_slot_to_control(n: u8) -> Option<KeyControl> {
     use KeyControl::*;
     Some(match n {
         1 => TemplatesEquipmentTemplate1,
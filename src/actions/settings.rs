@@ -2,7 +2,9 @@ use std::sync::atomic::Ordering;
 use std::sync::{Arc, atomic::AtomicBool};
 
 use crate::PLUGIN_ID;
-use crate::topics::GW2_BINDINGS_PATH_SET;
+use crate::api::config_from_globals;
+use crate::obs;
+use crate::topics::{API_CONFIG_SET, GW2_BINDINGS_PATH_SET, MARKERS_DIR_SET, OBS_CONFIG_SET};
 use constcat::concat;
 use streamdeck_lib::prelude::*;
 
@@ -42,6 +44,14 @@ impl Action for SettingsAction {
             "Received settings for context {}: {:?}", ev.context, ev.settings
         );
 
+        // The PI sends every setting on each save; compare with what was stored to
+        // only react to the ones that changed.
+        let before = cx.globals().snapshot();
+        let changed = |key: &str| {
+            ev.settings.contains_key(key)
+                && ev.settings.get(key).filter(|v| !v.is_null()) != before.get(key)
+        };
+
         cx.globals().with_mut(|globals| {
             // Update globals with the new settings
             for (k, v) in ev.settings.iter() {
@@ -59,6 +69,27 @@ impl Action for SettingsAction {
             // Notify adapters in the OnAppLaunch group (where the watcher lives)
             bus.publish_t(GW2_BINDINGS_PATH_SET, path.into());
         }
+        if changed("markers_dir")
+            && let Some(dir) = ev.settings.get("markers_dir").and_then(|v| v.as_str())
+        {
+            bus.publish_t(MARKERS_DIR_SET, dir.into());
+        }
+        if changed("api_port") || changed("api_token") {
+            bus.publish_t(
+                API_CONFIG_SET,
+                config_from_globals(&cx.globals().snapshot()),
+            );
+        }
+        if ["obs_url", "obs_password", "obs_rules"]
+            .iter()
+            .any(|k| changed(k))
+        {
+            match obs::config_from_globals(&cx.globals().snapshot()) {
+                Ok(config) => bus.publish_t(OBS_CONFIG_SET, config),
+                // keep the old rules running until the new ones parse
+                Err(e) => warn!(cx.log(), "{e}"),
+            }
+        }
     }
 
     fn key_down(&mut self, cx: &Context, ev: &KeyDown) {
@@ -76,7 +107,18 @@ impl Action for SettingsAction {
         std::thread::spawn(move || {
             std::thread::sleep(LONG_PRESS);
             if !cancel.load(Ordering::SeqCst) {
-                cx.globals().delete_many(&["api_key", "bindings_file"]);
+                cx.globals().delete_many(&[
+                    "api_key",
+                    "bindings_file",
+                    "markers_dir",
+                    "api_port",
+                    "api_token",
+                    "obs_url",
+                    "obs_password",
+                    "obs_rules",
+                ]);
+                cx.bus().publish_t(API_CONFIG_SET, Default::default());
+                cx.bus().publish_t(OBS_CONFIG_SET, Default::default());
             }
 
             cx.sd().show_ok(&ctx_id);
@@ -0,0 +1,157 @@
+use constcat::concat;
+use serde_json::{Map, Value};
+
+use streamdeck_lib::prelude::*;
+
+use crate::{
+    PLUGIN_ID,
+    animation::{start_timer_for, stop_timer_for},
+    gw2::shared::{ActiveChar, MotionSnapshot, SharedIdentity, SharedMotion, SharedSession},
+    render::{KeyImage, Overlay, profession_palette},
+    topics::TIMER_TICK,
+};
+
+const TIMER_NAME: &str = "speedometer";
+const DEFAULT_RATE_MS: u64 = 250;
+/// Below this the key redraws faster than Stream Deck can display it.
+const MIN_RATE_MS: u64 = 50;
+
+/// Which speed the readout shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Metric {
+    /// Ground speed, ignores climbing/falling.
+    #[default]
+    Horizontal,
+    /// Including the vertical component.
+    Total,
+}
+
+/// Shows current speed (game units/s), movement class and the session max.
+///
+/// Reads the motion adapter's snapshot on a timer. The max is the active
+/// character's, from the session adapter, so it starts over with each game session.
+#[derive(Default)]
+pub struct SpeedometerAction {
+    rate_ms: u64,
+    metric: Metric,
+    /// Last image sent, so idle keys don't redraw every tick.
+    last_image: Option<String>,
+}
+
+impl ActionStatic for SpeedometerAction {
+    const ID: &'static str = concat!(PLUGIN_ID, ".speedometer");
+}
+
+impl Action for SpeedometerAction {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn topics(&self) -> &'static [&'static str] {
+        &[TIMER_TICK.name]
+    }
+
+    fn will_appear(&mut self, cx: &Context, ev: &WillAppear) {
+        debug!(cx.log(), "SpeedometerAction will_appear: {:?}", ev.context);
+        self.apply_settings_json(ev.settings);
+        self.last_image = None;
+        cx.sd()
+            .set_title(ev.context, Some(String::new()), None, None);
+        self.refresh(cx, ev.context);
+        start_timer_for(cx, ev.context, TIMER_NAME, self.rate_ms);
+    }
+
+    fn will_disappear(&mut self, cx: &Context, ev: &WillDisappear) {
+        stop_timer_for(cx, ev.context, TIMER_NAME);
+    }
+
+    fn did_receive_settings(&mut self, cx: &Context, ev: &DidReceiveSettings) {
+        self.apply_settings_json(ev.settings);
+        self.last_image = None;
+        self.refresh(cx, ev.context);
+        // replaces the running timer with the new rate
+        start_timer_for(cx, ev.context, TIMER_NAME, self.rate_ms);
+    }
+
+    fn on_notify(&mut self, cx: &Context, ctx_id: &str, event: &ErasedTopic) {
+        if let Some(name) = event.downcast(TIMER_TICK) {
+            if name == TIMER_NAME {
+                self.refresh(cx, ctx_id);
+            }
+        }
+    }
+}
+
+// ── Impl details ─────────────────────────────────────────────────────────────
+
+impl SpeedometerAction {
+    fn apply_settings_json(&mut self, settings: &Map<String, Value>) {
+        self.rate_ms = settings
+            .get("rate_ms")
+            // text fields may hand us a string
+            .and_then(|v| v.as_u64().or_else(|| v.as_str()?.trim().parse().ok()))
+            .unwrap_or(DEFAULT_RATE_MS)
+            .max(MIN_RATE_MS);
+
+        self.metric = match settings.get("metric").and_then(|v| v.as_str()) {
+            Some("total") => Metric::Total,
+            _ => Metric::Horizontal,
+        };
+    }
+
+    fn current_speed(&self, snap: &MotionSnapshot) -> f32 {
+        snap.speed.map_or(0.0, |s| match self.metric {
+            Metric::Horizontal => s.horizontal,
+            Metric::Total => s.magnitude,
+        })
+    }
+
+    /// The active character's fastest speed this session.
+    fn session_max(&self, cx: &Context) -> f32 {
+        let character = cx.try_ext::<ActiveChar>().and_then(|c| c.get());
+        let stats = cx.try_ext::<SharedSession>().and_then(|s| s.get());
+        let tally = stats
+            .as_ref()
+            .zip(character.as_deref())
+            .and_then(|(stats, character)| stats.tally(character, None));
+        tally.map_or(0.0, |t| match self.metric {
+            Metric::Horizontal => t.max_speed_horizontal,
+            Metric::Total => t.max_speed_total,
+        })
+    }
+
+    fn refresh(&mut self, cx: &Context, ctx_id: &str) {
+        let snap = cx
+            .try_ext::<SharedMotion>()
+            .map(|m| m.get())
+            .unwrap_or_default();
+        let speed = self.current_speed(&snap);
+        // the session samples less often than this key; never show a max below now
+        let max = self.session_max(cx).max(speed);
+
+        let profession = cx
+            .try_ext::<SharedIdentity>()
+            .and_then(|i| i.get())
+            .and_then(|id| id.profession);
+
+        let image = KeyImage {
+            palette: profession_palette(profession),
+            header: Some(snap.state.label().to_string()),
+            name: Some(format!("{:.0}", speed)),
+            footer: Some(format!("max {:.0}", max)),
+            overlay: if snap.in_air {
+                Overlay::Active
+            } else {
+                Overlay::None
+            },
+            ..KeyImage::default()
+        }
+        .to_data_uri();
+
+        if self.last_image.as_deref() == Some(image.as_str()) {
+            return;
+        }
+        cx.sd().set_image(ctx_id, Some(image.clone()), None, None);
+        self.last_image = Some(image);
+    }
+}
@@ -0,0 +1,236 @@
+use std::path::Path;
+
+use constcat::concat;
+use serde_json::{Map, Value};
+
+use streamdeck_lib::prelude::*;
+
+use crate::{
+    PLUGIN_ID,
+    animation::{start_timer_for, stop_timer_for},
+    gw2::{
+        course::{CourseStore, format_delta, format_elapsed},
+        shared::{CourseStatus, SharedCourse},
+    },
+    render::{KeyImage, Overlay, Palette},
+    topics::{COURSE_EVENT, COURSE_RESET, COURSES_RELOAD, TIMER_TICK},
+};
+
+const TIMER_NAME: &str = "split-timer";
+/// Tenths of a second are shown while running.
+const RATE_MS: u64 = 100;
+
+/// Shows the running time and split delta of checkpoint courses.
+///
+/// Runs start, split and finish on their own from the player's position (see
+/// the course adapter); pressing the key abandons the current run. With
+/// `course` set the key only follows that course, otherwise whichever ran last.
+///
+/// The property inspector imports course files into the plugin's data
+/// directory and, from its Export button, writes the selected course to a file.
+#[derive(Default)]
+pub struct SplitTimerAction {
+    course: Option<String>,
+    import_file: Option<String>,
+    export_file: Option<String>,
+    /// Last image sent, so idle keys don't redraw every tick.
+    last_image: Option<String>,
+}
+
+impl ActionStatic for SplitTimerAction {
+    const ID: &'static str = concat!(PLUGIN_ID, ".split-timer");
+}
+
+impl Action for SplitTimerAction {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn topics(&self) -> &'static [&'static str] {
+        &[COURSE_EVENT.name, TIMER_TICK.name]
+    }
+
+    fn will_appear(&mut self, cx: &Context, ev: &WillAppear) {
+        debug!(cx.log(), "SplitTimerAction will_appear: {:?}", ev.context);
+        let settings = ev.settings;
+        self.course = text_setting(settings, "course");
+        // files picked earlier were already handled
+        self.import_file = text_setting(settings, "import_file");
+        self.export_file = text_setting(settings, "export_file");
+        self.last_image = None;
+        cx.sd()
+            .set_title(ev.context, Some(String::new()), None, None);
+        self.refresh(cx, ev.context);
+        start_timer_for(cx, ev.context, TIMER_NAME, RATE_MS);
+    }
+
+    fn will_disappear(&mut self, cx: &Context, ev: &WillDisappear) {
+        stop_timer_for(cx, ev.context, TIMER_NAME);
+    }
+
+    fn did_receive_settings(&mut self, cx: &Context, ev: &DidReceiveSettings) {
+        let settings = ev.settings;
+        self.course = text_setting(settings, "course");
+
+        let import = text_setting(settings, "import_file");
+        if import.is_some() && import != self.import_file {
+            self.import(cx, ev.context, import.as_deref().unwrap_or_default());
+        }
+        self.import_file = import;
+
+        // written only when the PI's Export button is pressed (see send_to_plugin)
+        self.export_file = text_setting(settings, "export_file");
+
+        self.last_image = None;
+        self.refresh(cx, ev.context);
+    }
+
+    fn on_notify(&mut self, cx: &Context, ctx_id: &str, event: &ErasedTopic) {
+        if let Some(name) = event.downcast(TIMER_TICK) {
+            if name == TIMER_NAME {
+                self.refresh(cx, ctx_id);
+            }
+            return;
+        }
+        if let Some(ev) = event.downcast(COURSE_EVENT)
+            && self.follows(&ev.course)
+        {
+            self.refresh(cx, ctx_id);
+        }
+    }
+
+    fn key_up(&mut self, cx: &Context, _ev: &KeyUp) {
+        cx.bus().publish_t(COURSE_RESET, ());
+    }
+
+    fn send_to_plugin(&mut self, cx: &Context, ev: &SendToPlugin) {
+        if ev.payload.get("event").and_then(|v| v.as_str()) != Some("export") {
+            return;
+        }
+        match self.export_file.as_deref() {
+            Some(path) => self.export(cx, ev.context, path),
+            None => {
+                warn!(cx.log(), "SplitTimerAction export: no file given");
+                cx.sd().show_alert(ev.context);
+            }
+        }
+    }
+}
+
+// ── Impl details ─────────────────────────────────────────────────────────────
+
+fn text_setting(settings: &Map<String, Value>, key: &str) -> Option<String> {
+    settings
+        .get(key)
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+impl SplitTimerAction {
+    fn follows(&self, course: &str) -> bool {
+        self.course.as_deref().is_none_or(|c| c == course)
+    }
+
+    fn import(&self, cx: &Context, ctx_id: &str, path: &str) {
+        match CourseStore::open_default().and_then(|s| s.import(Path::new(path))) {
+            Ok(course) => {
+                info!(
+                    cx.log(),
+                    "SplitTimerAction imported course '{}'", course.name
+                );
+                cx.bus().publish_t(COURSES_RELOAD, ());
+                cx.sd().show_ok(ctx_id);
+            }
+            Err(e) => {
+                error!(cx.log(), "SplitTimerAction import failed: {}", e);
+                cx.sd().show_alert(ctx_id);
+            }
+        }
+    }
+
+    fn export(&self, cx: &Context, ctx_id: &str, path: &str) {
+        let Some(course) = self.course.as_deref() else {
+            warn!(cx.log(), "SplitTimerAction export: no course selected");
+            cx.sd().show_alert(ctx_id);
+            return;
+        };
+        match CourseStore::open_default().and_then(|s| s.export(course, Path::new(path))) {
+            Ok(()) => {
+                info!(
+                    cx.log(),
+                    "SplitTimerAction exported '{}' to {}", course, path
+                );
+                cx.sd().show_ok(ctx_id);
+            }
+            Err(e) => {
+                error!(cx.log(), "SplitTimerAction export failed: {}", e);
+                cx.sd().show_alert(ctx_id);
+            }
+        }
+    }
+
+    fn image(&self, status: &CourseStatus) -> KeyImage {
+        let shown = status.course.as_deref().filter(|c| self.follows(c));
+        let Some(course) = shown else {
+            return KeyImage {
+                header: Some(self.course.clone().unwrap_or_else(|| "Splits".into())),
+                name: Some("Ready".into()),
+                ..KeyImage::default()
+            };
+        };
+
+        let palette = match status.last_delta_ms {
+            Some(d) if d < 0 => Palette::AHEAD,
+            Some(d) if d > 0 => Palette::WARNING,
+            _ => Palette::default(),
+        };
+        let delta = status.last_delta_ms.map(format_delta);
+
+        if let Some(run) = &status.run {
+            let elapsed = run.started_at.elapsed().as_millis() as u64;
+            let total = status.checkpoints.saturating_sub(1).max(1);
+            return KeyImage {
+                palette,
+                header: Some(course.to_string()),
+                name: Some(format_elapsed(elapsed)),
+                footer: Some(delta.unwrap_or_else(|| format!("CP {}/{}", run.next, total))),
+                overlay: Overlay::Running,
+                progress: Some((run.next - 1) as f32 / total as f32),
+            };
+        }
+
+        match status.finished {
+            Some((ms, pb)) => KeyImage {
+                palette,
+                header: Some(course.to_string()),
+                name: Some(format_elapsed(ms)),
+                footer: if pb { Some("PB!".into()) } else { delta },
+                overlay: if pb { Overlay::Active } else { Overlay::None },
+                ..KeyImage::default()
+            },
+            // abandoned
+            None => KeyImage {
+                header: Some(course.to_string()),
+                name: Some("Ready".into()),
+                footer: status.best_ms.map(|b| format!("PB {}", format_elapsed(b))),
+                ..KeyImage::default()
+            },
+        }
+    }
+
+    fn refresh(&mut self, cx: &Context, ctx_id: &str) {
+        let status = cx
+            .try_ext::<SharedCourse>()
+            .map(|c| c.get())
+            .unwrap_or_default();
+        let image = self.image(&status).to_data_uri();
+
+        if self.last_image.as_deref() == Some(image.as_str()) {
+            return;
+        }
+        cx.sd().set_image(ctx_id, Some(image.clone()), None, None);
+        self.last_image = Some(image);
+    }
+}
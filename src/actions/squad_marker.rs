@@ -0,0 +1,219 @@
+use constcat::concat;
+use serde_json::{Map, Value, json};
+use std::time::{Duration, Instant};
+
+use streamdeck_lib::prelude::*;
+
+use crate::{
+    PLUGIN_ID,
+    gw2::{
+        enums::{KeyControl, SquadMarker},
+        shared::SharedIdentity,
+    },
+    render::MarkerImage,
+    topics::{GW2_EXEC_QUEUE, Gw2ExecQueue, MUMBLE_IDENTITY},
+};
+
+/// Holding the key (or dial) at least this long clears all markers of the kind.
+const LONG_PRESS: Duration = Duration::from_millis(500);
+
+/// Manifest states: 0 = usable, 1 = greyed out because we're not commanding.
+const STATE_ENABLED: u8 = 0;
+const STATE_DISABLED: u8 = 1;
+
+/// Places a squad location or object marker; long press clears all of that kind.
+///
+/// On a dial, rotating picks the marker and pressing places it. The key is
+/// disabled unless the Mumble identity reports us as commander.
+#[derive(Default)]
+pub struct SquadMarkerAction {
+    marker: Option<SquadMarker>,
+    /// Object markers instead of location (ground) markers.
+    object: bool,
+    settings: Map<String, Value>,
+    dial: bool,
+    pressed_at: Option<Instant>,
+    last_enabled: Option<bool>,
+}
+
+impl ActionStatic for SquadMarkerAction {
+    const ID: &'static str = concat!(PLUGIN_ID, ".squad-marker");
+}
+
+impl Action for SquadMarkerAction {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn topics(&self) -> &'static [&'static str] {
+        &[MUMBLE_IDENTITY.name]
+    }
+
+    fn will_appear(&mut self, cx: &Context, ev: &WillAppear) {
+        debug!(cx.log(), "SquadMarkerAction will_appear: {:?}", ev.context);
+        self.dial = ev.controller == "Encoder";
+        self.apply_settings_json(ev.settings);
+        self.last_enabled = None;
+        self.refresh(cx, ev.context);
+    }
+
+    fn did_receive_settings(&mut self, cx: &Context, ev: &DidReceiveSettings) {
+        self.apply_settings_json(ev.settings);
+        self.last_enabled = None;
+        self.refresh(cx, ev.context);
+    }
+
+    fn on_notify(&mut self, cx: &Context, ctx_id: &str, event: &ErasedTopic) {
+        if event.is(MUMBLE_IDENTITY) {
+            self.refresh(cx, ctx_id);
+        }
+    }
+
+    fn key_down(&mut self, _cx: &Context, _ev: &KeyDown) {
+        self.pressed_at = Some(Instant::now());
+    }
+
+    fn key_up(&mut self, cx: &Context, ev: &KeyUp) {
+        self.release(cx, ev.context);
+    }
+
+    fn dial_down(&mut self, _cx: &Context, _ev: &DialDown) {
+        self.pressed_at = Some(Instant::now());
+    }
+
+    fn dial_up(&mut self, cx: &Context, ev: &DialUp) {
+        self.release(cx, ev.context);
+    }
+
+    fn touch_tap(&mut self, cx: &Context, ev: &TouchTap) {
+        if ev.hold {
+            self.clear_all(cx, ev.context);
+        } else {
+            self.place(cx, ev.context);
+        }
+    }
+
+    fn dial_rotate(&mut self, cx: &Context, ev: &DialRotate) {
+        let list = &SquadMarker::ALL;
+        let cur = self
+            .marker
+            .and_then(|m| list.iter().position(|&x| x == m))
+            .unwrap_or(0) as i32;
+        let next = list[(cur + ev.ticks).rem_euclid(list.len() as i32) as usize];
+        self.marker = Some(next);
+
+        self.settings
+            .insert("marker".into(), Value::from(next.key()));
+        cx.sd().set_settings(ev.context, self.settings.clone());
+        self.last_enabled = None;
+        self.refresh(cx, ev.context);
+    }
+}
+
+// ── Impl details ─────────────────────────────────────────────────────────────
+
+impl SquadMarkerAction {
+    fn apply_settings_json(&mut self, settings: &Map<String, Value>) {
+        self.settings = settings.clone();
+        self.marker = settings
+            .get("marker")
+            .and_then(|v| v.as_str())
+            .and_then(SquadMarker::from_key);
+        self.object = settings.get("kind").and_then(|v| v.as_str()) == Some("object");
+    }
+
+    fn release(&mut self, cx: &Context, ctx_id: &str) {
+        let long = self
+            .pressed_at
+            .take()
+            .is_some_and(|t| t.elapsed() >= LONG_PRESS);
+        if long {
+            self.clear_all(cx, ctx_id);
+        } else {
+            self.place(cx, ctx_id);
+        }
+    }
+
+    fn place(&self, cx: &Context, ctx_id: &str) {
+        let Some(marker) = self.marker else {
+            cx.sd().show_alert(ctx_id);
+            return;
+        };
+        self.send(cx, ctx_id, marker.control(self.object));
+    }
+
+    fn clear_all(&self, cx: &Context, ctx_id: &str) {
+        let control = if self.object {
+            KeyControl::SquadClearAllObjectMarkers
+        } else {
+            KeyControl::SquadClearAllLocationMarkers
+        };
+        self.send(cx, ctx_id, control);
+    }
+
+    fn send(&self, cx: &Context, ctx_id: &str, control: KeyControl) {
+        if !is_commander(cx) {
+            cx.sd().show_alert(ctx_id);
+            return;
+        }
+        cx.bus().publish_t(
+            GW2_EXEC_QUEUE,
+            Gw2ExecQueue {
+                controls: vec![control],
+                // markers matter most mid-fight
+                allow_in_combat: true,
+                allow_out_of_combat: true,
+                allow_gliding_or_falling: true,
+                allow_in_water: true,
+                inter_control_ms: None,
+                ttl_ms: None,
+                coalesce: false,
+                origin_ctx: ctx_id.into(),
+            },
+        );
+    }
+
+    fn refresh(&mut self, cx: &Context, ctx_id: &str) {
+        let enabled = is_commander(cx);
+        if self.last_enabled == Some(enabled) {
+            return;
+        }
+        self.last_enabled = Some(enabled);
+
+        let marker = self.marker.unwrap_or(SquadMarker::Arrow);
+        let image = MarkerImage {
+            marker,
+            object: self.object,
+            enabled,
+        }
+        .to_data_uri();
+
+        if self.dial {
+            let payload = json!({
+                "title": if self.object { "Object Marker" } else { "Location Marker" },
+                "icon": image,
+                "value": if enabled { marker.name() } else { "Not commander" },
+            });
+            if let Value::Object(map) = payload {
+                cx.sd().set_feedback(ctx_id, map);
+            }
+        } else {
+            let state = if enabled {
+                STATE_ENABLED
+            } else {
+                STATE_DISABLED
+            };
+            cx.sd().set_state(ctx_id, state);
+            cx.sd().set_image(ctx_id, Some(image), None, None);
+        }
+    }
+}
+
+// ── helpers ──────────────────────────────────────────────────────────────────
+
+fn is_commander(cx: &Context) -> bool {
+    cx.try_ext::<SharedIdentity>()
+        .and_then(|i| i.get())
+        .and_then(|id| id.commander)
+        .unwrap_or(false)
+}
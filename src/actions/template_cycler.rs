@@ -0,0 +1,368 @@
+use constcat::concat;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use streamdeck_lib::prelude::*;
+
+use crate::{
+    PLUGIN_ID,
+    actions::set_template::{
+        active_character, build_slot_to_control, equipment_slot_to_control, mark_applied,
+        request_verification, ttl_ms_setting, waiting_title,
+    },
+    gw2::{
+        enums::{KeyControl, TemplateNames},
+        shared::TemplateStore,
+    },
+    render::wrap_title_or_fallback,
+    topics::{
+        ExecState, GW2_API_CHARACTER_CHANGED, GW2_API_TEMPLATE_CHANGED, GW2_API_VERIFY_RESULT,
+        GW2_EXEC_PROGRESS, GW2_EXEC_QUEUE, GW2_EXEC_WAITING, Gw2ExecQueue, MUMBLE_ACTIVE_CHARACTER,
+        VerifyOutcome,
+    },
+};
+
+/// Number of build/equipment pairs the property inspector exposes.
+const MAX_STEPS: usize = 5;
+
+/// Holding the key at least this long cycles backwards.
+const LONG_PRESS: Duration = Duration::from_millis(500);
+
+const IDLE_IMAGE: &str = "imgs/set_template/template_idle";
+const QUEUED_IMAGE: &str = "imgs/set_template/template_pulse_q_0";
+const RUNNING_IMAGE: &str = "imgs/set_template/template_pulse_r_0";
+
+/// One configured build/equipment pair; either side may be empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Step {
+    build: Option<u8>,     // 1..=9
+    equipment: Option<u8>, // 1..=9
+}
+
+/// A press waiting for the exec adapter.
+#[derive(Debug, Clone)]
+struct Pending {
+    character: String,
+    index: usize,
+    step: Step,
+}
+
+/// Cycles through a list of build/equipment pairs on each press.
+///
+/// Short press applies the next pair, long press the previous one. The position is
+/// remembered per character (persisted in the action settings under `positions`),
+/// and the title shows the pair the next short press will apply.
+#[derive(Default)]
+pub struct TemplateCyclerAction {
+    steps: Vec<Step>,
+    /// character name ("" when unknown) -> index of the last applied step
+    positions: HashMap<String, usize>,
+    settings: Map<String, Value>,
+    verify_via_api: bool,
+    allow_in_water: bool,
+    ttl_ms: Option<u64>,
+    pressed_at: Option<Instant>,
+    /// step queued for execution; marked active and made the cycle position once
+    /// the exec adapter reports Done, dropped if it expires
+    pending: Option<Pending>,
+    last_title: Option<String>,
+}
+
+impl ActionStatic for TemplateCyclerAction {
+    const ID: &'static str = concat!(PLUGIN_ID, ".template-cycler");
+}
+
+impl Action for TemplateCyclerAction {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn topics(&self) -> &'static [&'static str] {
+        &[
+            MUMBLE_ACTIVE_CHARACTER.name,
+            GW2_API_CHARACTER_CHANGED.name,
+            GW2_API_TEMPLATE_CHANGED.name,
+            GW2_EXEC_PROGRESS.name,
+            GW2_EXEC_WAITING.name,
+            GW2_API_VERIFY_RESULT.name,
+        ]
+    }
+
+    fn will_appear(&mut self, cx: &Context, ev: &WillAppear) {
+        debug!(
+            cx.log(),
+            "TemplateCyclerAction will_appear: {:?}", ev.context
+        );
+        self.apply_settings_json(ev.settings, cx);
+        cx.sd()
+            .set_image(ev.context, Some(IDLE_IMAGE.into()), None, None);
+        self.last_title = None;
+        self.refresh_title(cx, ev.context);
+    }
+
+    fn did_receive_settings(&mut self, cx: &Context, ev: &DidReceiveSettings) {
+        self.apply_settings_json(ev.settings, cx);
+        self.refresh_title(cx, ev.context);
+    }
+
+    fn on_notify(&mut self, cx: &Context, ctx_id: &str, event: &ErasedTopic) {
+        if event.is(MUMBLE_ACTIVE_CHARACTER) {
+            self.refresh_title(cx, ctx_id);
+            return;
+        }
+
+        if let Some(m) = event.downcast(GW2_API_CHARACTER_CHANGED) {
+            if m.name == active_character(cx) {
+                self.refresh_title(cx, ctx_id);
+            }
+            return;
+        }
+
+        if let Some(m) = event.downcast(GW2_API_TEMPLATE_CHANGED) {
+            if m.name == active_character(cx) {
+                self.refresh_title(cx, ctx_id);
+            }
+            return;
+        }
+
+        if let Some(m) = event.downcast(GW2_EXEC_PROGRESS) {
+            if *m == ExecState::Done {
+                if let Some(Pending {
+                    character,
+                    index,
+                    step,
+                }) = self.pending.take()
+                {
+                    self.positions.insert(character, index);
+                    self.persist_positions(cx, ctx_id);
+                    mark_applied(cx, step.build, step.equipment);
+                    if self.verify_via_api {
+                        request_verification(cx, ctx_id, step.build, step.equipment);
+                    }
+                }
+            }
+            if *m == ExecState::Expired {
+                self.pending = None;
+                cx.sd().show_alert(ctx_id);
+            }
+            let image = match m {
+                ExecState::Queued => QUEUED_IMAGE,
+                ExecState::Started => RUNNING_IMAGE,
+                ExecState::Done | ExecState::Expired => IDLE_IMAGE,
+            };
+            cx.sd().set_image(ctx_id, Some(image.into()), None, None);
+            if *m != ExecState::Queued {
+                self.last_title = None; // drop the waiting countdown
+                self.refresh_title(cx, ctx_id);
+            }
+            return;
+        }
+
+        if let Some(m) = event.downcast(GW2_EXEC_WAITING) {
+            if self.pending.is_some() {
+                let title = waiting_title(m);
+                self.last_title = Some(title.clone());
+                cx.sd().set_title(ctx_id, Some(title), None, None);
+            }
+            return;
+        }
+
+        if let Some(m) = event.downcast(GW2_API_VERIFY_RESULT) {
+            match m {
+                VerifyOutcome::Verified => cx.sd().show_ok(ctx_id),
+                VerifyOutcome::Mismatch { .. } => cx.sd().show_alert(ctx_id),
+            }
+        }
+    }
+
+    fn key_down(&mut self, _cx: &Context, _ev: &KeyDown) {
+        self.pressed_at = Some(Instant::now());
+    }
+
+    fn key_up(&mut self, cx: &Context, ev: &KeyUp) {
+        let backwards = self
+            .pressed_at
+            .take()
+            .is_some_and(|t| t.elapsed() >= LONG_PRESS);
+
+        if self.steps.is_empty() {
+            cx.sd().show_alert(ev.context);
+            return;
+        }
+
+        let character = active_character(cx);
+        let idx = if backwards {
+            self.prev_index(&character)
+        } else {
+            self.next_index(&character)
+        };
+        let step = self.steps[idx];
+
+        let mut controls: Vec<KeyControl> = Vec::new();
+        if let Some(kc) = step.build.and_then(build_slot_to_control) {
+            controls.push(kc);
+        }
+        if let Some(kc) = step.equipment.and_then(equipment_slot_to_control) {
+            controls.push(kc);
+        }
+
+        debug!(
+            cx.log(),
+            "TemplateCyclerAction {} -> step {} ({:?}) for {:?}",
+            if backwards { "back" } else { "forward" },
+            idx + 1,
+            step,
+            character
+        );
+
+        cx.bus().publish_t(
+            GW2_EXEC_QUEUE,
+            Gw2ExecQueue {
+                controls,
+                allow_in_combat: false,
+                allow_out_of_combat: true,
+                allow_gliding_or_falling: false,
+                allow_in_water: self.allow_in_water,
+                inter_control_ms: None,
+                ttl_ms: self.ttl_ms,
+                coalesce: false,
+                origin_ctx: ev.context.into(),
+            },
+        );
+
+        self.pending = Some(Pending {
+            character,
+            index: idx,
+            step,
+        });
+    }
+}
+
+// ── Impl details ─────────────────────────────────────────────────────────────
+
+impl TemplateCyclerAction {
+    fn apply_settings_json(&mut self, settings: &Map<String, Value>, cx: &Context) {
+        self.settings = settings.clone();
+
+        self.steps = (1..=MAX_STEPS)
+            .map(|i| Step {
+                build: slot_setting(settings, &format!("step{i}_build")),
+                equipment: slot_setting(settings, &format!("step{i}_equipment")),
+            })
+            .filter(|s| s.build.is_some() || s.equipment.is_some())
+            .collect();
+
+        self.verify_via_api = settings
+            .get("verify_via_api")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        self.allow_in_water = settings
+            .get("allow_in_water")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        self.ttl_ms = ttl_ms_setting(settings);
+
+        self.positions = settings
+            .get("positions")
+            .and_then(|v| v.as_object())
+            .map(|o| {
+                o.iter()
+                    .filter_map(|(k, v)| Some((k.clone(), v.as_u64()? as usize)))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        debug!(
+            cx.log(),
+            "TemplateCyclerAction settings -> {} step(s), {} remembered position(s)",
+            self.steps.len(),
+            self.positions.len()
+        );
+    }
+
+    fn persist_positions(&mut self, cx: &Context, ctx_id: &str) {
+        let positions: Map<String, Value> = self
+            .positions
+            .iter()
+            .map(|(k, v)| (k.clone(), Value::from(*v)))
+            .collect();
+        self.settings
+            .insert("positions".into(), Value::Object(positions));
+        cx.sd().set_settings(ctx_id, self.settings.clone());
+    }
+
+    /// Index applied by a short press for `character`.
+    fn next_index(&self, character: &str) -> usize {
+        match self.positions.get(character) {
+            Some(&i) => (i + 1) % self.steps.len(),
+            None => 0,
+        }
+    }
+
+    /// Index applied by a long press for `character`.
+    fn prev_index(&self, character: &str) -> usize {
+        let len = self.steps.len();
+        match self.positions.get(character) {
+            Some(&i) => (i % len + len - 1) % len,
+            None => len - 1,
+        }
+    }
+
+    fn refresh_title(&mut self, cx: &Context, ctx_id: &str) {
+        let title = self.compute_title(cx);
+        if self.last_title.as_deref() != Some(title.as_str()) {
+            self.last_title = Some(title.clone());
+            cx.sd().set_title(ctx_id, Some(title), None, None);
+        }
+    }
+
+    fn compute_title(&self, cx: &Context) -> String {
+        if self.steps.is_empty() {
+            return "Cycle".to_string();
+        }
+
+        let character = active_character(cx);
+        let step = self.steps[self.next_index(&character)];
+        let names: Option<TemplateNames> = if character.is_empty() {
+            None
+        } else {
+            cx.try_ext::<TemplateStore>()
+                .and_then(|s| s.get(&character))
+        };
+
+        let mut parts: Vec<String> = Vec::new();
+        if let Some(b) = step.build {
+            let name = names
+                .as_ref()
+                .and_then(|n| n.build.get(b as usize - 1).cloned().flatten());
+            parts.push(match name {
+                Some(n) => wrap_title_or_fallback(&n, &format!("B{b}"), 10),
+                None => format!("B{b}"),
+            });
+        }
+        if let Some(e) = step.equipment {
+            let name = names
+                .as_ref()
+                .and_then(|n| n.equipment.get(e as usize - 1).cloned().flatten());
+            parts.push(match name {
+                Some(n) => wrap_title_or_fallback(&n, &format!("E{e}"), 10),
+                None => format!("E{e}"),
+            });
+        }
+
+        parts.join("\n")
+    }
+}
+
+// ── helpers ──────────────────────────────────────────────────────────────────
+
+fn slot_setting(settings: &Map<String, Value>, key: &str) -> Option<u8> {
+    settings
+        .get(key)
+        .and_then(|v| v.as_u64())
+        .and_then(|n| u8::try_from(n).ok())
+        .filter(|n| (1..=9).contains(n))
+}
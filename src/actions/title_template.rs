@@ -0,0 +1,570 @@
+//! Tiny template language for key titles.
+//!
+//! Syntax:
+//! - `{char}`, `{profession}`, `{spec}`, `{map}`, `{build.slot}`, `{build.name}`,
+//!   `{equip.slot}`, `{equip.name}` insert a value (empty when unknown).
+//! - `{build.name|Build {build.slot}}` falls back to the text after `|` when empty.
+//! - `{build.name:wrap=10,lines=2,max=20,upper}` formats the value:
+//!   `wrap` word-wraps at N chars, `lines` keeps N lines (ellipsis on the last),
+//!   `max` clamps to N chars with an ellipsis, `upper`/`lower` change case.
+//! - `{?char}…{:}…{/}` renders the first branch when the value is non-empty, else the
+//!   second (`{:}` is optional); `{!char}…{/}` is the inverse.
+//! - `\n` is a line break, `{{` and `}}` are literal braces.
+//!
+//! Each compiled template records which inputs it reads ([`TitleDep`]). Keys can't
+//! subscribe to just those topics: `Action::topics()` is one static list for every key
+//! of an action. So actions stay subscribed to every input topic and filter their
+//! notifications with it; a key's title is only recomputed when something it shows
+//! actually changed, but every key still receives every notification.
+
+use crate::render::wrap_title;
+
+/// Default title for Set Template keys: template names with "Build N"/"Equip N"
+/// fallbacks, or "B1 / E2" while no character is known.
+pub const DEFAULT_SET_TEMPLATE_TITLE: &str = concat!(
+    "{?char}",
+    "{?build.slot}{build.name:wrap=10|Build {build.slot}}{?equip.slot}\\n{/}{/}",
+    "{?equip.slot}{equip.name:wrap=10|Equip {equip.slot}}{/}",
+    "{:}",
+    "{?build.slot}B{build.slot}{?equip.slot} / {/}{/}",
+    "{?equip.slot}E{equip.slot}{/}",
+    "{/}",
+);
+
+/// Inputs a title can depend on; maps 1:1 onto the bus topics that change them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TitleDep {
+    /// `MUMBLE_ACTIVE_CHARACTER`
+    ActiveCharacter,
+    /// `MUMBLE_IDENTITY`
+    Identity,
+    /// `GW2_API_TEMPLATE_CHANGED` / `GW2_API_CHARACTER_CHANGED`
+    TemplateNames,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Var {
+    Char,
+    Profession,
+    Spec,
+    Map,
+    BuildSlot,
+    BuildName,
+    EquipSlot,
+    EquipName,
+}
+
+impl Var {
+    fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "char" => Var::Char,
+            "profession" => Var::Profession,
+            "spec" => Var::Spec,
+            "map" => Var::Map,
+            "build.slot" => Var::BuildSlot,
+            "build.name" => Var::BuildName,
+            "equip.slot" => Var::EquipSlot,
+            "equip.name" => Var::EquipName,
+            _ => return None,
+        })
+    }
+
+    fn deps(self) -> &'static [TitleDep] {
+        match self {
+            Var::Char => &[TitleDep::ActiveCharacter],
+            Var::Profession | Var::Spec | Var::Map => &[TitleDep::Identity],
+            Var::BuildName | Var::EquipName => {
+                &[TitleDep::ActiveCharacter, TitleDep::TemplateNames]
+            }
+            Var::BuildSlot | Var::EquipSlot => &[], // come from the key's own settings
+        }
+    }
+}
+
+/// Values a template is rendered against. `None` and `""` both count as empty.
+#[derive(Debug, Clone, Default)]
+pub struct TitleVars {
+    pub character: Option<String>,
+    pub profession: Option<String>,
+    pub spec: Option<String>,
+    pub map: Option<String>,
+    pub build_slot: Option<u8>,
+    pub build_name: Option<String>,
+    pub equip_slot: Option<u8>,
+    pub equip_name: Option<String>,
+}
+
+impl TitleVars {
+    fn get(&self, var: Var) -> Option<String> {
+        let v = match var {
+            Var::Char => self.character.clone(),
+            Var::Profession => self.profession.clone(),
+            Var::Spec => self.spec.clone(),
+            Var::Map => self.map.clone(),
+            Var::BuildSlot => self.build_slot.map(|n| n.to_string()),
+            Var::BuildName => self.build_name.clone(),
+            Var::EquipSlot => self.equip_slot.map(|n| n.to_string()),
+            Var::EquipName => self.equip_name.clone(),
+        };
+        v.filter(|s| !s.trim().is_empty())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Case {
+    Upper,
+    Lower,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Format {
+    wrap: Option<usize>,
+    lines: Option<usize>,
+    max: Option<usize>,
+    case: Option<Case>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Value {
+        var: Var,
+        fmt: Format,
+        fallback: Vec<Node>,
+    },
+    Cond {
+        var: Var,
+        negate: bool,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+}
+
+/// A parsed title template plus the inputs it depends on.
+#[derive(Debug, Clone)]
+pub struct TitleTemplate {
+    nodes: Vec<Node>,
+    deps: Vec<TitleDep>,
+}
+
+impl Default for TitleTemplate {
+    fn default() -> Self {
+        Self::parse(DEFAULT_SET_TEMPLATE_TITLE).expect("default title template must parse")
+    }
+}
+
+impl TitleTemplate {
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let mut p = Parser {
+            chars: src.chars().collect(),
+            pos: 0,
+        };
+        let (nodes, end) = p.parse_seq(false)?;
+        match end {
+            End::Eof => {}
+            End::Else => return Err(p.error("`{:}` outside of a conditional")),
+            End::Close => return Err(p.error("`{/}` without an open conditional")),
+            End::Brace => unreachable!("only returned inside a fallback"),
+        }
+
+        let mut deps = Vec::new();
+        collect_deps(&nodes, &mut deps);
+        Ok(Self { nodes, deps })
+    }
+
+    /// Whether a change of `dep` can change the rendered title.
+    pub fn depends_on(&self, dep: TitleDep) -> bool {
+        self.deps.contains(&dep)
+    }
+
+    pub fn render(&self, vars: &TitleVars) -> String {
+        let mut out = String::new();
+        render_nodes(&self.nodes, vars, &mut out);
+        out
+    }
+}
+
+fn collect_deps(nodes: &[Node], deps: &mut Vec<TitleDep>) {
+    let add = |var: Var, deps: &mut Vec<TitleDep>| {
+        for d in var.deps() {
+            if !deps.contains(d) {
+                deps.push(*d);
+            }
+        }
+    };
+    for n in nodes {
+        match n {
+            Node::Text(_) => {}
+            Node::Value { var, fallback, .. } => {
+                add(*var, deps);
+                collect_deps(fallback, deps);
+            }
+            Node::Cond {
+                var,
+                then,
+                otherwise,
+                ..
+            } => {
+                add(*var, deps);
+                collect_deps(then, deps);
+                collect_deps(otherwise, deps);
+            }
+        }
+    }
+}
+
+fn render_nodes(nodes: &[Node], vars: &TitleVars, out: &mut String) {
+    for n in nodes {
+        match n {
+            Node::Text(t) => out.push_str(t),
+            Node::Value { var, fmt, fallback } => match vars.get(*var) {
+                Some(v) => out.push_str(&apply_format(&v, fmt)),
+                None => render_nodes(fallback, vars, out),
+            },
+            Node::Cond {
+                var,
+                negate,
+                then,
+                otherwise,
+            } => {
+                if vars.get(*var).is_some() != *negate {
+                    render_nodes(then, vars, out);
+                } else {
+                    render_nodes(otherwise, vars, out);
+                }
+            }
+        }
+    }
+}
+
+fn apply_format(value: &str, fmt: &Format) -> String {
+    let mut s = match fmt.case {
+        Some(Case::Upper) => value.to_uppercase(),
+        Some(Case::Lower) => value.to_lowercase(),
+        None => value.to_string(),
+    };
+    if let Some(w) = fmt.wrap {
+        s = wrap_title(&s, w);
+    }
+    if let Some(n) = fmt.lines {
+        let lines: Vec<&str> = s.lines().collect();
+        if lines.len() > n {
+            let mut kept = lines[..n].join("\n");
+            kept.push('…');
+            s = kept;
+        }
+    }
+    if let Some(n) = fmt.max
+        && s.chars().count() > n
+    {
+        s = s.chars().take(n.saturating_sub(1)).collect();
+        s.push('…');
+    }
+    s
+}
+
+// ── Parser ───────────────────────────────────────────────────────────────────
+
+/// What stopped a sequence.
+enum End {
+    Eof,
+    /// `{:}`
+    Else,
+    /// `{/}`
+    Close,
+    /// `}` closing a fallback
+    Brace,
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn error(&self, msg: &str) -> String {
+        format!("title template: {msg} (at char {})", self.pos)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, off: usize) -> Option<char> {
+        self.chars.get(self.pos + off).copied()
+    }
+
+    fn parse_seq(&mut self, in_fallback: bool) -> Result<(Vec<Node>, End), String> {
+        let mut nodes = Vec::new();
+        let mut text = String::new();
+
+        let end = loop {
+            match (self.peek(), self.peek_at(1)) {
+                (None, _) => {
+                    if in_fallback {
+                        return Err(self.error("unterminated `|` fallback"));
+                    }
+                    break End::Eof;
+                }
+                (Some('{'), Some('{')) => {
+                    text.push('{');
+                    self.pos += 2;
+                }
+                (Some('}'), _) if in_fallback => {
+                    self.pos += 1;
+                    break End::Brace;
+                }
+                (Some('}'), Some('}')) => {
+                    text.push('}');
+                    self.pos += 2;
+                }
+                (Some('\\'), Some('n')) => {
+                    text.push('\n');
+                    self.pos += 2;
+                }
+                (Some('{'), _) => {
+                    if !text.is_empty() {
+                        nodes.push(Node::Text(std::mem::take(&mut text)));
+                    }
+                    match self.parse_tag()? {
+                        Tag::Node(n) => nodes.push(n),
+                        Tag::Else => break End::Else,
+                        Tag::Close => break End::Close,
+                    }
+                }
+                (Some(c), _) => {
+                    text.push(c);
+                    self.pos += 1;
+                }
+            }
+        };
+
+        if !text.is_empty() {
+            nodes.push(Node::Text(text));
+        }
+        Ok((nodes, end))
+    }
+
+    /// Parses from `{` up to and including the tag's closing `}`.
+    fn parse_tag(&mut self) -> Result<Tag, String> {
+        self.pos += 1; // '{'
+        match (self.peek(), self.peek_at(1)) {
+            (Some(':'), Some('}')) => {
+                self.pos += 2;
+                Ok(Tag::Else)
+            }
+            (Some('/'), Some('}')) => {
+                self.pos += 2;
+                Ok(Tag::Close)
+            }
+            (Some(c @ ('?' | '!')), _) => {
+                self.pos += 1;
+                let var = self.parse_var()?;
+                if self.peek() != Some('}') {
+                    return Err(self.error("expected `}` after conditional variable"));
+                }
+                self.pos += 1;
+
+                let (then, end) = self.parse_seq(false)?;
+                let otherwise = match end {
+                    End::Close => Vec::new(),
+                    End::Else => match self.parse_seq(false)? {
+                        (nodes, End::Close) => nodes,
+                        _ => return Err(self.error("conditional is missing `{/}`")),
+                    },
+                    _ => return Err(self.error("conditional is missing `{/}`")),
+                };
+                Ok(Tag::Node(Node::Cond {
+                    var,
+                    negate: c == '!',
+                    then,
+                    otherwise,
+                }))
+            }
+            _ => {
+                let var = self.parse_var()?;
+                let mut fmt = Format::default();
+                if self.peek() == Some(':') {
+                    self.pos += 1;
+                    fmt = self.parse_format()?;
+                }
+                let fallback = match self.peek() {
+                    Some('}') => {
+                        self.pos += 1;
+                        Vec::new()
+                    }
+                    Some('|') => {
+                        self.pos += 1;
+                        match self.parse_seq(true)? {
+                            (nodes, End::Brace) => nodes,
+                            (_, End::Else) => {
+                                return Err(self.error("`{:}` inside a `|` fallback"));
+                            }
+                            _ => return Err(self.error("`{/}` inside a `|` fallback")),
+                        }
+                    }
+                    _ => return Err(self.error("expected `}`, `:` or `|` after variable")),
+                };
+                Ok(Tag::Node(Node::Value { var, fmt, fallback }))
+            }
+        }
+    }
+
+    fn parse_var(&mut self) -> Result<Var, String> {
+        let start = self.pos;
+        while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || c == '.' || c == '_') {
+            self.pos += 1;
+        }
+        let name: String = self.chars[start..self.pos].iter().collect();
+        Var::parse(&name).ok_or_else(|| self.error(&format!("unknown variable `{name}`")))
+    }
+
+    /// `wrap=10,max=20,upper` up to (not including) `|` or `}`.
+    fn parse_format(&mut self) -> Result<Format, String> {
+        let start = self.pos;
+        while !matches!(self.peek(), None | Some('|') | Some('}')) {
+            self.pos += 1;
+        }
+        let spec: String = self.chars[start..self.pos].iter().collect();
+
+        let mut fmt = Format::default();
+        for item in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (key, val) = match item.split_once('=') {
+                Some((k, v)) => (k.trim(), Some(v.trim())),
+                None => (item, None),
+            };
+            let num = || {
+                val.and_then(|v| v.parse::<usize>().ok())
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| self.error(&format!("`{key}` needs a positive number")))
+            };
+            match key {
+                "wrap" => fmt.wrap = Some(num()?),
+                "lines" => fmt.lines = Some(num()?),
+                "max" => fmt.max = Some(num()?),
+                "upper" => fmt.case = Some(Case::Upper),
+                "lower" => fmt.case = Some(Case::Lower),
+                _ => return Err(self.error(&format!("unknown format `{key}`"))),
+            }
+        }
+        Ok(fmt)
+    }
+}
+
+enum Tag {
+    Node(Node),
+    Else,
+    Close,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars() -> TitleVars {
+        TitleVars {
+            character: Some("Rytlock Brimstone".into()),
+            profession: Some("Revenant".into()),
+            build_slot: Some(2),
+            build_name: Some("Power Herald".into()),
+            equip_slot: Some(3),
+            ..TitleVars::default()
+        }
+    }
+
+    fn render(src: &str, vars: &TitleVars) -> String {
+        TitleTemplate::parse(src).unwrap().render(vars)
+    }
+
+    #[test]
+    fn escapes() {
+        let v = TitleVars::default();
+        assert_eq!(render("{{char}}", &v), "{char}");
+        assert_eq!(render("a\\nb", &v), "a\nb");
+        assert_eq!(render("50% } done", &v), "50% } done");
+    }
+
+    #[test]
+    fn values_and_formats() {
+        let v = vars();
+        assert_eq!(render("{char}", &v), "Rytlock Brimstone");
+        assert_eq!(render("{map}", &v), "");
+        assert_eq!(render("{char:wrap=8}", &v), "Rytlock\nBrimstone");
+        assert_eq!(render("{char:max=5,upper}", &v), "RYTL…");
+        assert_eq!(render("{char:wrap=8,lines=1}", &v), "Rytlock…");
+        assert_eq!(render("{profession:lower}", &v), "revenant");
+    }
+
+    #[test]
+    fn fallbacks() {
+        let v = vars();
+        assert_eq!(
+            render("{build.name|Build {build.slot}}", &v),
+            "Power Herald"
+        );
+        assert_eq!(render("{equip.name|Equip {equip.slot}}", &v), "Equip 3");
+        assert_eq!(render("{map|{spec|none}}!", &v), "none!");
+        assert_eq!(render("{map:upper|}x", &v), "x");
+    }
+
+    #[test]
+    fn conditionals() {
+        let v = vars();
+        assert_eq!(render("{?char}yes{:}no{/}", &v), "yes");
+        assert_eq!(render("{?map}yes{:}no{/}", &v), "no");
+        assert_eq!(render("{!map}no map{/}", &v), "no map");
+        assert_eq!(render("{?map}only{/}", &v), "");
+        assert_eq!(
+            render(
+                "{?build.slot}B{build.slot}{?equip.slot} / E{equip.slot}{/}{/}",
+                &v
+            ),
+            "B2 / E3"
+        );
+    }
+
+    #[test]
+    fn default_template() {
+        let t = TitleTemplate::default();
+        assert_eq!(t.render(&vars()), "Power\nHerald\nEquip 3");
+        let no_char = TitleVars {
+            character: None,
+            ..vars()
+        };
+        assert_eq!(t.render(&no_char), "B2 / E3");
+    }
+
+    #[test]
+    fn deps() {
+        let t = TitleTemplate::parse("{build.slot} {?map}{/}").unwrap();
+        assert!(t.depends_on(TitleDep::Identity));
+        assert!(!t.depends_on(TitleDep::ActiveCharacter));
+        assert!(!t.depends_on(TitleDep::TemplateNames));
+
+        let t = TitleTemplate::parse("{spec|{equip.name}}").unwrap();
+        assert!(t.depends_on(TitleDep::TemplateNames));
+        assert!(t.depends_on(TitleDep::ActiveCharacter));
+    }
+
+    #[test]
+    fn errors() {
+        for src in [
+            "{nope}",
+            "{char",
+            "{char:wrap=0}",
+            "{char:bold}",
+            "{char|fallback",
+            "{?char}open",
+            "{?char",
+            "text{:}",
+            "text{/}",
+            "{char|a{/}b}",
+            "{char|a{:}b}",
+        ] {
+            assert!(
+                TitleTemplate::parse(src).is_err(),
+                "{src:?} should not parse"
+            );
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use std::path::PathBuf;
+
+use constcat::concat;
+use serde_json::{Map, Value};
+
+use streamdeck_lib::prelude::*;
+
+use crate::{
+    PLUGIN_ID,
+    animation::{start_timer_for, stop_timer_for},
+    gw2::{
+        shared::SharedMotion,
+        storage::data_dir,
+        trail::{TrailRecorder, write_pack},
+    },
+    render::{KeyImage, Overlay},
+    topics::TIMER_TICK,
+};
+
+const TIMER_NAME: &str = "trail-recorder";
+const SAMPLE_MS: u64 = 100;
+const DEFAULT_MIN_STEP_M: f32 = 2.0;
+const DEFAULT_NAME: &str = "Recorded trail";
+
+/// Records the avatar's path while toggled on and writes it as a TacO trail
+/// pack (`.xml` + `.trl` files) when toggled off.
+///
+/// Points are kept every `min_step` meters; map changes and teleports start a
+/// new trail file. Packs go to `output_dir`, or `<data dir>/trails`.
+#[derive(Default)]
+pub struct TrailRecorderAction {
+    name: String,
+    min_step_m: f32,
+    output_dir: Option<PathBuf>,
+    recording: Option<TrailRecorder>,
+    /// Last image sent, so the key isn't redrawn every sample.
+    last_image: Option<String>,
+}
+
+impl ActionStatic for TrailRecorderAction {
+    const ID: &'static str = concat!(PLUGIN_ID, ".trail-recorder");
+}
+
+impl Action for TrailRecorderAction {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn topics(&self) -> &'static [&'static str] {
+        &[TIMER_TICK.name]
+    }
+
+    fn will_appear(&mut self, cx: &Context, ev: &WillAppear) {
+        debug!(
+            cx.log(),
+            "TrailRecorderAction will_appear: {:?}", ev.context
+        );
+        self.apply_settings_json(ev.settings);
+        self.last_image = None;
+        cx.sd()
+            .set_title(ev.context, Some(String::new()), None, None);
+        self.show(cx, ev.context);
+    }
+
+    fn will_disappear(&mut self, cx: &Context, ev: &WillDisappear) {
+        // don't lose a recording because the page changed
+        if self.recording.is_some() {
+            self.stop(cx, ev.context);
+        }
+    }
+
+    fn did_receive_settings(&mut self, cx: &Context, ev: &DidReceiveSettings) {
+        self.apply_settings_json(ev.settings);
+        self.last_image = None;
+        self.show(cx, ev.context);
+    }
+
+    fn on_notify(&mut self, cx: &Context, ctx_id: &str, event: &ErasedTopic) {
+        if let Some(name) = event.downcast(TIMER_TICK)
+            && name == TIMER_NAME
+        {
+            self.sample(cx);
+            self.show(cx, ctx_id);
+        }
+    }
+
+    fn key_up(&mut self, cx: &Context, ev: &KeyUp) {
+        if self.recording.is_some() {
+            self.stop(cx, ev.context);
+        } else {
+            info!(cx.log(), "TrailRecorderAction: recording '{}'", self.name);
+            self.recording = Some(TrailRecorder::new(self.min_step_m));
+            self.sample(cx);
+            start_timer_for(cx, ev.context, TIMER_NAME, SAMPLE_MS);
+        }
+        self.show(cx, ev.context);
+    }
+}
+
+// ── Impl details ─────────────────────────────────────────────────────────────
+
+impl TrailRecorderAction {
+    fn apply_settings_json(&mut self, settings: &Map<String, Value>) {
+        let text = |key: &str| {
+            settings
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+        };
+        self.name = text("name").unwrap_or(DEFAULT_NAME).to_string();
+        self.output_dir = text("output_dir").map(PathBuf::from);
+        self.min_step_m = settings
+            .get("min_step")
+            // text fields may hand us a string
+            .and_then(|v| v.as_f64().or_else(|| v.as_str()?.trim().parse().ok()))
+            .map(|m| m as f32)
+            .filter(|m| *m > 0.0)
+            .unwrap_or(DEFAULT_MIN_STEP_M);
+    }
+
+    fn sample(&mut self, cx: &Context) {
+        let Some(rec) = self.recording.as_mut() else {
+            return;
+        };
+        if let Some(p) = cx.try_ext::<SharedMotion>().and_then(|m| m.get().position) {
+            rec.push(p.map_id, p.pos);
+        }
+    }
+
+    fn stop(&mut self, cx: &Context, ctx_id: &str) {
+        stop_timer_for(cx, ctx_id, TIMER_NAME);
+        let Some(rec) = self.recording.take() else {
+            return;
+        };
+        let segments = rec.finish();
+        if segments.is_empty() {
+            warn!(cx.log(), "TrailRecorderAction: nothing recorded");
+            cx.sd().show_alert(ctx_id);
+            return;
+        }
+
+        let written = self
+            .output_dir
+            .clone()
+            .map(Ok)
+            .unwrap_or_else(|| data_dir().map(|d| d.join("trails")))
+            .and_then(|dir| write_pack(&dir, &self.name, &segments));
+        match written {
+            Ok(path) => {
+                info!(
+                    cx.log(),
+                    "TrailRecorderAction: {} trail(s) written to {}",
+                    segments.len(),
+                    path.display()
+                );
+                cx.sd().show_ok(ctx_id);
+            }
+            Err(e) => {
+                error!(
+                    cx.log(),
+                    "TrailRecorderAction: writing trails failed: {}", e
+                );
+                cx.sd().show_alert(ctx_id);
+            }
+        }
+    }
+
+    fn show(&mut self, cx: &Context, ctx_id: &str) {
+        let image = match &self.recording {
+            Some(rec) => KeyImage {
+                header: Some("Trail".to_string()),
+                name: Some("REC".to_string()),
+                footer: Some(format!("{} pts", rec.point_count())),
+                overlay: Overlay::Running,
+                ..KeyImage::default()
+            },
+            None => KeyImage {
+                header: Some("Trail".to_string()),
+                name: Some(self.name.clone()),
+                footer: Some("Press to record".to_string()),
+                ..KeyImage::default()
+            },
+        }
+        .to_data_uri();
+
+        if self.last_image.as_deref() == Some(image.as_str()) {
+            return;
+        }
+        cx.sd().set_image(ctx_id, Some(image.clone()), None, None);
+        self.last_image = Some(image);
+    }
+}
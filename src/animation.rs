@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::{Receiver as CbReceiver, bounded, select};
+
+use streamdeck_lib::prelude::*;
+
+use crate::topics::{
+    ANIMATION_DONE, ANIMATION_FRAME, ANIMATION_PLAY, ANIMATION_STOP, AnimationFrame, AnimationMode,
+    AnimationPlay, AnimationStop, TIMER_START, TIMER_STOP, TIMER_TICK, TimerStart, TimerStop,
+};
+
+/// Upper bound on how long the loop sleeps while nothing is playing.
+const IDLE_WAIT: Duration = Duration::from_secs(1);
+/// Floor for `frame_ms` so a bad request can't spin the thread.
+const MIN_FRAME: Duration = Duration::from_millis(16);
+
+/// Drives per-context key animations and repeating timers on its own thread.
+///
+/// Listens:
+/// - "animation.play" -> start or replace the named animation on a context
+/// - "animation.stop" -> stop one (or every) animation on a context
+/// - "timer.start"    -> start or replace a repeating timer on a context
+/// - "timer.stop"     -> stop one (or every) timer on a context
+///
+/// Sends to the animated context:
+/// - "animation.frame" -> image for the next frame (the first one immediately)
+/// - "animation.done"  -> name of a `Once` animation that finished
+/// - "timer.tick"      -> name of a timer that fired
+pub struct AnimationAdapter;
+
+impl AnimationAdapter {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+struct Playing {
+    frames: Vec<String>,
+    frame_every: Duration,
+    mode: AnimationMode,
+    index: usize,
+    next_at: Instant,
+}
+
+struct Timer {
+    every: Duration,
+    next_at: Instant,
+}
+
+impl Adapter for AnimationAdapter {
+    fn name(&self) -> &'static str {
+        "animation_adapter"
+    }
+
+    fn policy(&self) -> StartPolicy {
+        StartPolicy::OnAppLaunch
+    }
+
+    fn topics(&self) -> &'static [&'static str] {
+        &[
+            ANIMATION_PLAY.name,
+            ANIMATION_STOP.name,
+            TIMER_START.name,
+            TIMER_STOP.name,
+        ]
+    }
+
+    fn start(
+        &self,
+        cx: &Context,
+        bus: Arc<dyn Bus>,
+        inbox: CbReceiver<Arc<ErasedTopic>>,
+    ) -> AdapterResult {
+        let (stop_tx, stop_rx) = bounded::<()>(1);
+        let logger = cx.log().clone();
+
+        let join = thread::spawn(move || {
+            // (context, animation name) -> state
+            let mut playing: HashMap<(String, String), Playing> = HashMap::new();
+            // (context, timer name) -> state
+            let mut timers: HashMap<(String, String), Timer> = HashMap::new();
+
+            info!(logger, "animation adapter started");
+
+            loop {
+                let now = Instant::now();
+                let wait = playing
+                    .values()
+                    .map(|p| p.next_at)
+                    .chain(timers.values().map(|t| t.next_at))
+                    .map(|at| at.saturating_duration_since(now))
+                    .min()
+                    .unwrap_or(IDLE_WAIT);
+
+                select! {
+                    recv(inbox) -> msg => {
+                        let Ok(note) = msg else {
+                            error!(logger, "animation: inbox closed");
+                            break;
+                        };
+                        if let Some(p) = note.downcast(ANIMATION_PLAY) {
+                            play(&bus, &mut playing, p.clone());
+                        } else if let Some(s) = note.downcast(ANIMATION_STOP) {
+                            stop(&mut playing, s);
+                        } else if let Some(t) = note.downcast(TIMER_START) {
+                            start_timer(&mut timers, t);
+                        } else if let Some(t) = note.downcast(TIMER_STOP) {
+                            stop_timer(&mut timers, t);
+                        }
+                    }
+
+                    recv(stop_rx) -> _ => {
+                        debug!(logger, "Stopping animation adapter...");
+                        break;
+                    }
+
+                    default(wait) => {
+                        let now = Instant::now();
+                        advance(&bus, &mut playing, now);
+                        fire_timers(&bus, &mut timers, now);
+                    }
+                }
+            }
+
+            info!(logger, "animation adapter stopped");
+        });
+
+        Ok(AdapterHandle::from_crossbeam(join, stop_tx))
+    }
+}
+
+fn play(bus: &Arc<dyn Bus>, playing: &mut HashMap<(String, String), Playing>, p: AnimationPlay) {
+    if p.frames.is_empty() {
+        return;
+    }
+    let frame_every = Duration::from_millis(p.frame_ms).max(MIN_FRAME);
+    bus.action_notify_context_t(
+        p.ctx.clone(),
+        ANIMATION_FRAME,
+        AnimationFrame {
+            name: p.name.clone(),
+            index: 0,
+            image: p.frames[0].clone(),
+        },
+    );
+    playing.insert(
+        (p.ctx, p.name),
+        Playing {
+            frames: p.frames,
+            frame_every,
+            mode: p.mode,
+            index: 0,
+            next_at: Instant::now() + frame_every,
+        },
+    );
+}
+
+fn stop(playing: &mut HashMap<(String, String), Playing>, s: &AnimationStop) {
+    match &s.name {
+        Some(name) => {
+            playing.remove(&(s.ctx.clone(), name.clone()));
+        }
+        None => playing.retain(|(ctx, _), _| *ctx != s.ctx),
+    }
+}
+
+/// Steps every animation whose frame is due; finished `Once` animations are dropped.
+fn advance(bus: &Arc<dyn Bus>, playing: &mut HashMap<(String, String), Playing>, now: Instant) {
+    let mut finished: Vec<(String, String)> = Vec::new();
+
+    for ((ctx, name), p) in playing.iter_mut() {
+        if p.next_at > now {
+            continue;
+        }
+        let next = p.index + 1;
+        if next >= p.frames.len() && p.mode == AnimationMode::Once {
+            finished.push((ctx.clone(), name.clone()));
+            continue;
+        }
+        p.index = next % p.frames.len();
+        p.next_at += p.frame_every;
+        if p.next_at < now {
+            p.next_at = now + p.frame_every; // fell behind; don't burst to catch up
+        }
+        bus.action_notify_context_t(
+            ctx.clone(),
+            ANIMATION_FRAME,
+            AnimationFrame {
+                name: name.clone(),
+                index: p.index,
+                image: p.frames[p.index].clone(),
+            },
+        );
+    }
+
+    for key in finished {
+        playing.remove(&key);
+        bus.action_notify_context_t(key.0, ANIMATION_DONE, key.1);
+    }
+}
+
+fn start_timer(timers: &mut HashMap<(String, String), Timer>, t: &TimerStart) {
+    let every = Duration::from_millis(t.every_ms).max(MIN_FRAME);
+    timers.insert(
+        (t.ctx.clone(), t.name.clone()),
+        Timer {
+            every,
+            next_at: Instant::now() + every,
+        },
+    );
+}
+
+fn stop_timer(timers: &mut HashMap<(String, String), Timer>, t: &TimerStop) {
+    match &t.name {
+        Some(name) => {
+            timers.remove(&(t.ctx.clone(), name.clone()));
+        }
+        None => timers.retain(|(ctx, _), _| *ctx != t.ctx),
+    }
+}
+
+fn fire_timers(bus: &Arc<dyn Bus>, timers: &mut HashMap<(String, String), Timer>, now: Instant) {
+    for ((ctx, name), t) in timers.iter_mut() {
+        if t.next_at > now {
+            continue;
+        }
+        t.next_at += t.every;
+        if t.next_at < now {
+            t.next_at = now + t.every;
+        }
+        bus.action_notify_context_t(ctx.clone(), TIMER_TICK, name.clone());
+    }
+}
+
+// ── action-side helpers ──────────────────────────────────────────────────────
+
+pub fn play_animation(
+    cx: &Context,
+    ctx_id: &str,
+    name: &str,
+    frames: Vec<String>,
+    frame_ms: u64,
+    mode: AnimationMode,
+) {
+    cx.bus().publish_t(
+        ANIMATION_PLAY,
+        AnimationPlay {
+            ctx: ctx_id.into(),
+            name: name.into(),
+            frames,
+            frame_ms,
+            mode,
+        },
+    );
+}
+
+pub fn stop_animation(cx: &Context, ctx_id: &str, name: &str) {
+    cx.bus().publish_t(
+        ANIMATION_STOP,
+        AnimationStop {
+            ctx: ctx_id.into(),
+            name: Some(name.into()),
+        },
+    );
+}
+
+pub fn start_timer_for(cx: &Context, ctx_id: &str, name: &str, every_ms: u64) {
+    cx.bus().publish_t(
+        TIMER_START,
+        TimerStart {
+            ctx: ctx_id.into(),
+            name: name.into(),
+            every_ms,
+        },
+    );
+}
+
+pub fn stop_timer_for(cx: &Context, ctx_id: &str, name: &str) {
+    cx.bus().publish_t(
+        TIMER_STOP,
+        TimerStop {
+            ctx: ctx_id.into(),
+            name: Some(name.into()),
+        },
+    );
+}
@@ -0,0 +1,688 @@
+// src/api.rs
+//! Optional local server so stream overlays and scripts can use the plugin's
+//! state without reading Mumble themselves.
+//!
+//! Off unless `api_port` is set in the global settings; listens on 127.0.0.1
+//! only.
+//!
+//! - `GET /state`  -> JSON snapshot: character, identity, mount, UI state,
+//!   motion and session statistics
+//! - `GET /events` -> WebSocket; the snapshot first (`"topic": "state"`), then
+//!   one `{"topic": …, "data": …}` message per bus event
+//! - `POST /exec`  -> queue controls like a key does; the body holds the
+//!   `Gw2ExecQueue` fields, the request `Authorization: Bearer <api_token>`
+//!
+//! Requests must name the server in `Host` (`127.0.0.1:<port>` or
+//! `localhost:<port>`), so other sites can't reach it through DNS rebinding.
+//! Browser pages from any other origin need the token too, as
+//! `Authorization: Bearer <api_token>` or `?token=<api_token>`.
+
+use std::{
+    io::{self, Read, Write},
+    net::{Ipv4Addr, TcpListener, TcpStream},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::{
+    Receiver as CbReceiver, Sender, TryRecvError, TrySendError, bounded, select,
+};
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+use streamdeck_lib::prelude::*;
+use tungstenite::{Message, WebSocket, handshake::derive_accept_key, protocol::Role};
+
+use crate::{
+    gw2::{
+        enums::{KeyControl, UiState},
+        shared::{
+            ActiveChar, CurrentMount, SharedIdentity, SharedMotion, SharedSession, SharedUiState,
+        },
+    },
+    topics::{
+        API_CONFIG_SET, ApiConfig, COURSE_EVENT, GW2_EXEC_ACTIVITY, GW2_EXEC_QUEUE, Gw2ExecQueue,
+        MOTION_EVENT, MOTION_FALL, MUMBLE_ACTIVE_CHARACTER, MUMBLE_IDENTITY, MUMBLE_MOUNT,
+        MUMBLE_UI_STATE,
+    },
+};
+
+/// `origin_ctx` of jobs queued through the API; no key listens for their progress.
+const API_ORIGIN: &str = "api";
+
+const MAX_HEAD: usize = 16 * 1024;
+const MAX_BODY: usize = 64 * 1024;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long a WebSocket connection waits for client frames before sending queued events.
+const WS_POLL: Duration = Duration::from_millis(50);
+/// Events buffered per WebSocket client; a client that falls this far behind is dropped.
+const CLIENT_BACKLOG: usize = 256;
+const ACCEPT_POLL: Duration = Duration::from_millis(100);
+
+/// Read the API settings out of the global settings.
+pub fn config_from_globals(globals: &Map<String, Value>) -> ApiConfig {
+    let port = globals
+        .get("api_port")
+        .and_then(|v| v.as_u64().or_else(|| v.as_str()?.trim().parse().ok()))
+        .and_then(|p| u16::try_from(p).ok())
+        .filter(|p| *p != 0);
+    let token = globals
+        .get("api_token")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string);
+    ApiConfig { port, token }
+}
+
+/// Runs the local API server and feeds bus events to its WebSocket clients.
+///
+/// Listens:
+/// - "api.config-set" -> restart the server with the new port/token
+/// - everything streamed to clients (character, identity, mount, UI state,
+///   motion events, fall alerts, exec activity, course events)
+pub struct ApiAdapter;
+
+impl ApiAdapter {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Adapter for ApiAdapter {
+    fn name(&self) -> &'static str {
+        "api_server"
+    }
+
+    fn policy(&self) -> StartPolicy {
+        StartPolicy::OnAppLaunch
+    }
+
+    fn topics(&self) -> &'static [&'static str] {
+        &[
+            API_CONFIG_SET.name,
+            MUMBLE_ACTIVE_CHARACTER.name,
+            MUMBLE_IDENTITY.name,
+            MUMBLE_MOUNT.name,
+            MUMBLE_UI_STATE.name,
+            MOTION_EVENT.name,
+            MOTION_FALL.name,
+            GW2_EXEC_ACTIVITY.name,
+            COURSE_EVENT.name,
+        ]
+    }
+
+    fn start(
+        &self,
+        cx: &Context,
+        bus: Arc<dyn Bus>,
+        inbox: CbReceiver<Arc<ErasedTopic>>,
+    ) -> AdapterResult {
+        let (stop_tx, stop_rx) = bounded::<()>(1);
+        let logger = cx.log().clone();
+        let initial = config_from_globals(&cx.globals().snapshot());
+        let sources = StateSources::from_cx(cx);
+
+        let join = thread::spawn(move || {
+            let mut config = ApiConfig::default();
+            let mut server: Option<Server> = None;
+            let mut apply = |new: ApiConfig, server: &mut Option<Server>| {
+                if new == config && (server.is_some() || new.port.is_none()) {
+                    return;
+                }
+                if let Some(old) = server.take() {
+                    old.stop();
+                    info!(logger, "api: server stopped");
+                }
+                if let Some(port) = new.port {
+                    match Server::start(port, new.token.clone(), sources.clone(), bus.clone()) {
+                        Ok(s) => {
+                            info!(logger, "api: listening on 127.0.0.1:{port}");
+                            *server = Some(s);
+                        }
+                        Err(e) => error!(logger, "api: can't listen on port {port}: {e}"),
+                    }
+                }
+                config = new;
+            };
+            apply(initial, &mut server);
+
+            loop {
+                select! {
+                    recv(inbox) -> msg => {
+                        let Ok(note) = msg else {
+                            break; // inbox closed
+                        };
+                        if let Some(new) = note.downcast(API_CONFIG_SET) {
+                            apply(new.clone(), &mut server);
+                        } else if let Some(server) = &server
+                            && let Some(event) = event_json(&note)
+                        {
+                            server.broadcast(event.to_string());
+                        }
+                    }
+
+                    recv(stop_rx) -> _ => {
+                        debug!(logger, "Stopping API adapter...");
+                        break;
+                    }
+                }
+            }
+
+            if let Some(server) = server.take() {
+                server.stop();
+            }
+        });
+
+        Ok(AdapterHandle::from_crossbeam(join, stop_tx))
+    }
+}
+
+/// `{"topic": …, "data": …}` for the streamed topics.
+fn event_json(note: &ErasedTopic) -> Option<Value> {
+    let data = if let Some(v) = note.downcast(MUMBLE_ACTIVE_CHARACTER) {
+        json!(v)
+    } else if let Some(v) = note.downcast(MUMBLE_IDENTITY) {
+        json!(v)
+    } else if let Some(v) = note.downcast(MUMBLE_MOUNT) {
+        json!(v)
+    } else if let Some(v) = note.downcast(MUMBLE_UI_STATE) {
+        ui_state_json(*v)
+    } else if let Some(v) = note.downcast(MOTION_EVENT) {
+        json!(v)
+    } else if let Some(v) = note.downcast(MOTION_FALL) {
+        json!(v)
+    } else if let Some(v) = note.downcast(GW2_EXEC_ACTIVITY) {
+        json!(v)
+    } else if let Some(v) = note.downcast(COURSE_EVENT) {
+        json!(v)
+    } else {
+        return None;
+    };
+    Some(json!({ "topic": note.name(), "data": data }))
+}
+
+fn ui_state_json(ui: UiState) -> Value {
+    json!({
+        "flags": ui.iter_names().map(|(name, _)| name).collect::<Vec<_>>(),
+        "in_combat": ui.is_in_combat(),
+    })
+}
+
+/// Extensions the `/state` snapshot is read from.
+#[derive(Clone)]
+struct StateSources {
+    character: Option<Arc<ActiveChar>>,
+    identity: Option<Arc<SharedIdentity>>,
+    mount: Option<Arc<CurrentMount>>,
+    ui_state: Option<Arc<SharedUiState>>,
+    motion: Option<Arc<SharedMotion>>,
+    session: Option<Arc<SharedSession>>,
+}
+
+impl StateSources {
+    fn from_cx(cx: &Context) -> Self {
+        Self {
+            character: cx.try_ext::<ActiveChar>(),
+            identity: cx.try_ext::<SharedIdentity>(),
+            mount: cx.try_ext::<CurrentMount>(),
+            ui_state: cx.try_ext::<SharedUiState>(),
+            motion: cx.try_ext::<SharedMotion>(),
+            session: cx.try_ext::<SharedSession>(),
+        }
+    }
+
+    fn snapshot(&self) -> Value {
+        let motion = self.motion.as_ref().map(|m| {
+            let m = m.get();
+            json!({
+                "state": m.state,
+                "in_air": m.in_air,
+                "position": m.position.map(|p| json!({
+                    "map_id": p.map_id,
+                    "pos": p.pos,
+                    "front": p.front,
+                })),
+                "speed": m.speed.map(|s| json!({
+                    "horizontal": s.horizontal,
+                    "vertical": s.vz,
+                    "total": s.magnitude,
+                })),
+                "water": m.water,
+                "fall_risk": m.fall_risk,
+                "fall_drop": m.fall_drop,
+            })
+        });
+        json!({
+            "character": self.character.as_ref().and_then(|c| c.get()),
+            "identity": self.identity.as_ref().and_then(|i| i.get()),
+            "mount": self.mount.as_ref().and_then(|m| m.get()),
+            "ui_state": self.ui_state.as_ref().map(|u| ui_state_json(u.get())),
+            "motion": motion,
+            "session": self.session.as_ref().and_then(|s| s.get()).map(|s| json!(*s)),
+        })
+    }
+}
+
+struct Server {
+    stop: Arc<AtomicBool>,
+    clients: Arc<Mutex<Vec<Sender<String>>>>,
+    join: JoinHandle<()>,
+}
+
+/// Shared by the accept loop and every connection thread.
+struct Shared {
+    port: u16,
+    token: Option<String>,
+    sources: StateSources,
+    bus: Arc<dyn Bus>,
+    stop: Arc<AtomicBool>,
+    clients: Arc<Mutex<Vec<Sender<String>>>>,
+}
+
+impl Server {
+    fn start(
+        port: u16,
+        token: Option<String>,
+        sources: StateSources,
+        bus: Arc<dyn Bus>,
+    ) -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        // polled, so the loop notices `stop`
+        listener.set_nonblocking(true)?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let clients = Arc::new(Mutex::new(Vec::new()));
+        let shared = Arc::new(Shared {
+            port,
+            token,
+            sources,
+            bus,
+            stop: stop.clone(),
+            clients: clients.clone(),
+        });
+
+        let join = thread::spawn(move || {
+            while !shared.stop.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        let shared = shared.clone();
+                        thread::spawn(move || {
+                            if let Err(e) = handle_connection(stream, &shared) {
+                                shared.bus.log(&format!("api: {e}"), Level::Debug);
+                            }
+                        });
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                        thread::sleep(ACCEPT_POLL);
+                    }
+                    Err(e) => {
+                        shared
+                            .bus
+                            .log(&format!("api: accept failed: {e}"), Level::Warn);
+                        thread::sleep(ACCEPT_POLL);
+                    }
+                }
+            }
+        });
+
+        Ok(Self {
+            stop,
+            clients,
+            join,
+        })
+    }
+
+    /// Queue `msg` for every WebSocket client; slow or closed clients are dropped.
+    fn broadcast(&self, msg: String) {
+        let Ok(mut clients) = self.clients.lock() else {
+            return;
+        };
+        clients.retain(|tx| match tx.try_send(msg.clone()) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_) | TrySendError::Disconnected(_)) => false,
+        });
+    }
+
+    fn stop(self) {
+        self.stop.store(true, Ordering::Relaxed);
+        // connection threads see the flag (or their dropped channel) on their own
+        if let Ok(mut clients) = self.clients.lock() {
+            clients.clear();
+        }
+        let _ = self.join.join();
+    }
+}
+
+struct Request {
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, shared: &Shared) -> Result<(), String> {
+    stream.set_nonblocking(false).map_err(|e| e.to_string())?;
+    stream
+        .set_read_timeout(Some(REQUEST_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+    let req = match read_request(&mut stream) {
+        Ok(req) => req,
+        Err(e) => {
+            respond(&mut stream, "400 Bad Request", &json!({ "error": e }));
+            return Err(e);
+        }
+    };
+
+    if let Err(e) = check_caller(&req, shared) {
+        respond(&mut stream, "403 Forbidden", &json!({ "error": e }));
+        return Err(e.into());
+    }
+
+    // path without the query string
+    let path = req.path.split('?').next().unwrap_or_default();
+    match (req.method.as_str(), path) {
+        ("GET", "/state") => {
+            respond(&mut stream, "200 OK", &shared.sources.snapshot());
+            Ok(())
+        }
+        ("GET", "/events") => {
+            let is_upgrade = req
+                .header("upgrade")
+                .is_some_and(|v| v.eq_ignore_ascii_case("websocket"));
+            match req.header("sec-websocket-key") {
+                Some(key) if is_upgrade => serve_events(stream, key, shared),
+                _ => {
+                    respond(
+                        &mut stream,
+                        "426 Upgrade Required",
+                        &json!({ "error": "WebSocket only" }),
+                    );
+                    Ok(())
+                }
+            }
+        }
+        ("POST", "/exec") => {
+            let (status, body) = exec(&req, shared);
+            respond(&mut stream, status, &body);
+            Ok(())
+        }
+        (_, "/state" | "/events" | "/exec") => {
+            respond(
+                &mut stream,
+                "405 Method Not Allowed",
+                &json!({ "error": "method not allowed" }),
+            );
+            Ok(())
+        }
+        _ => {
+            respond(
+                &mut stream,
+                "404 Not Found",
+                &json!({ "error": "not found" }),
+            );
+            Ok(())
+        }
+    }
+}
+
+fn read_request(stream: &mut TcpStream) -> Result<Request, String> {
+    let mut buf = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 1024];
+    loop {
+        let n = stream.read(&mut chunk).map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Err("connection closed".into());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+
+        let mut headers = [httparse::EMPTY_HEADER; 32];
+        let mut parsed = httparse::Request::new(&mut headers);
+        let head_len = match parsed.parse(&buf).map_err(|e| e.to_string())? {
+            httparse::Status::Complete(len) => len,
+            httparse::Status::Partial if buf.len() < MAX_HEAD => continue,
+            httparse::Status::Partial => return Err("request head too large".into()),
+        };
+
+        let mut req = Request {
+            method: parsed.method.unwrap_or_default().to_string(),
+            path: parsed.path.unwrap_or_default().to_string(),
+            headers: parsed
+                .headers
+                .iter()
+                .map(|h| {
+                    (
+                        h.name.to_string(),
+                        String::from_utf8_lossy(h.value).into_owned(),
+                    )
+                })
+                .collect(),
+            body: buf[head_len..].to_vec(),
+        };
+        let len: usize = req
+            .header("content-length")
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(0);
+        if len > MAX_BODY {
+            return Err("request body too large".into());
+        }
+        while req.body.len() < len {
+            let n = stream.read(&mut chunk).map_err(|e| e.to_string())?;
+            if n == 0 {
+                return Err("connection closed".into());
+            }
+            req.body.extend_from_slice(&chunk[..n]);
+        }
+        req.body.truncate(len);
+        return Ok(req);
+    }
+}
+
+fn respond(stream: &mut TcpStream, status: &str, body: &Value) {
+    let body = body.to_string();
+    let head = format!(
+        "HTTP/1.1 {status}\r\n\
+         Connection: close\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\r\n",
+        body.len()
+    );
+    let _ = stream.write_all(head.as_bytes());
+    let _ = stream.write_all(body.as_bytes());
+    let _ = stream.flush();
+}
+
+/// `Gw2ExecQueue` fields a script may set; by default no gate holds the job back.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+struct ExecRequest {
+    controls: Vec<KeyControl>,
+    allow_in_combat: bool,
+    allow_out_of_combat: bool,
+    allow_gliding_or_falling: bool,
+    allow_in_water: bool,
+    inter_control_ms: Option<u64>,
+    ttl_ms: Option<u64>,
+}
+
+impl Default for ExecRequest {
+    fn default() -> Self {
+        Self {
+            controls: Vec::new(),
+            allow_in_combat: true,
+            allow_out_of_combat: true,
+            allow_gliding_or_falling: true,
+            allow_in_water: true,
+            inter_control_ms: None,
+            ttl_ms: None,
+        }
+    }
+}
+
+fn exec(req: &Request, shared: &Shared) -> (&'static str, Value) {
+    let Some(token) = shared.token.as_deref() else {
+        return (
+            "403 Forbidden",
+            json!({ "error": "set an API token to enable /exec" }),
+        );
+    };
+    if !given_token(req).is_some_and(|g| tokens_match(g, token)) {
+        return ("401 Unauthorized", json!({ "error": "bad token" }));
+    }
+
+    let body: ExecRequest = match serde_json::from_slice(&req.body) {
+        Ok(b) => b,
+        Err(e) => return ("400 Bad Request", json!({ "error": e.to_string() })),
+    };
+    if body.controls.is_empty() {
+        return ("400 Bad Request", json!({ "error": "no controls" }));
+    }
+
+    let count = body.controls.len();
+    shared.bus.publish_t(
+        GW2_EXEC_QUEUE,
+        Gw2ExecQueue {
+            controls: body.controls,
+            allow_in_combat: body.allow_in_combat,
+            allow_out_of_combat: body.allow_out_of_combat,
+            allow_gliding_or_falling: body.allow_gliding_or_falling,
+            allow_in_water: body.allow_in_water,
+            inter_control_ms: body.inter_control_ms,
+            ttl_ms: body.ttl_ms,
+            coalesce: false,
+            origin_ctx: API_ORIGIN.to_string(),
+        },
+    );
+    ("202 Accepted", json!({ "queued": count }))
+}
+
+/// Rejects requests addressed to another host name (DNS rebinding) and browser
+/// pages from other origins that don't carry the token.
+fn check_caller(req: &Request, shared: &Shared) -> Result<(), &'static str> {
+    let port = shared.port;
+    let own = |name: &str| {
+        [format!("127.0.0.1:{port}"), format!("localhost:{port}")]
+            .iter()
+            .any(|own| name.eq_ignore_ascii_case(own))
+    };
+    if !req.header("host").is_some_and(|h| own(h.trim())) {
+        return Err("bad Host");
+    }
+    let Some(origin) = req.header("origin") else {
+        // not a browser page; scripts and OBS-style clients
+        return Ok(());
+    };
+    let same_origin = origin.trim().strip_prefix("http://").is_some_and(own);
+    let has_token = shared
+        .token
+        .as_deref()
+        .is_some_and(|t| given_token(req).is_some_and(|g| tokens_match(g, t)));
+    if same_origin || has_token {
+        Ok(())
+    } else {
+        Err("origin not allowed; pass the API token")
+    }
+}
+
+/// `Authorization: Bearer …`, else the `token` query parameter.
+fn given_token(req: &Request) -> Option<&str> {
+    req.header("authorization")
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim)
+        .or_else(|| {
+            let (_, query) = req.path.split_once('?')?;
+            query.split('&').find_map(|kv| kv.strip_prefix("token="))
+        })
+}
+
+/// Compares every byte, so response time doesn't tell how much of a guess was right.
+fn tokens_match(given: &str, token: &str) -> bool {
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+fn serve_events(mut stream: TcpStream, key: &str, shared: &Shared) -> Result<(), String> {
+    let accept = derive_accept_key(key.trim().as_bytes());
+    let head = format!(
+        "HTTP/1.1 101 Switching Protocols\r\n\
+         Upgrade: websocket\r\n\
+         Connection: Upgrade\r\n\
+         Sec-WebSocket-Accept: {accept}\r\n\r\n"
+    );
+    stream
+        .write_all(head.as_bytes())
+        .map_err(|e| e.to_string())?;
+    stream
+        .set_read_timeout(Some(WS_POLL))
+        .map_err(|e| e.to_string())?;
+
+    let (tx, rx) = bounded::<String>(CLIENT_BACKLOG);
+    if let Ok(mut clients) = shared.clients.lock() {
+        clients.push(tx);
+    }
+
+    let mut ws = WebSocket::from_raw_socket(stream, Role::Server, None);
+    let hello = json!({ "topic": "state", "data": shared.sources.snapshot() });
+    ws.send(Message::text(hello.to_string()))
+        .map_err(|e| e.to_string())?;
+
+    let mut last_ping = Instant::now();
+    loop {
+        if shared.stop.load(Ordering::Relaxed) {
+            let _ = ws.close(None);
+            let _ = ws.flush();
+            return Ok(());
+        }
+        match ws.read() {
+            Ok(Message::Close(_)) => {
+                let _ = ws.flush();
+                return Ok(());
+            }
+            // pings are answered by the next write; clients have nothing else to say
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) => {}
+            Err(tungstenite::Error::ConnectionClosed) => return Ok(()),
+            Err(e) => return Err(e.to_string()),
+        }
+
+        loop {
+            match rx.try_recv() {
+                Ok(msg) => ws.send(Message::text(msg)).map_err(|e| e.to_string())?,
+                Err(TryRecvError::Empty) => break,
+                // dropped for falling behind, or the server stopped
+                Err(TryRecvError::Disconnected) => {
+                    let _ = ws.close(None);
+                    let _ = ws.flush();
+                    return Ok(());
+                }
+            }
+        }
+
+        // keeps idle proxies from closing the connection, and finds dead clients
+        if last_ping.elapsed() >= Duration::from_secs(30) {
+            last_ping = Instant::now();
+            ws.send(Message::Ping(Default::default()))
+                .map_err(|e| e.to_string())?;
+        }
+    }
+}
@@ -3,6 +3,8 @@ use std::{
     time::{Duration, Instant},
 };
 
+use crate::gw2::enums::FallRisk;
+
 /// A single motion sample: (position (x,z,y) in meters, front (x,z,y), ui_tick)
 pub type MotionSample = ([f32; 3], [f32; 3]);
 
@@ -27,6 +29,21 @@ pub struct Speed {
     pub dt_s: f32,
 }
 
+/// Outcome of feeding one position sample to the `SpeedCalculator`.
+#[derive(Debug, Clone, Copy)]
+pub enum StepResult {
+    Speed(Speed),
+    /// First sample (or first after a reset); nothing to compare against yet.
+    Warmup,
+    /// Samples too close together or too far apart in time to derive a speed.
+    Gap,
+    /// Moved farther than anything can travel between two samples: waypoint,
+    /// portal, respawn, … Smoothing starts over from the new position.
+    Teleport {
+        distance_m: f32,
+    },
+}
+
 // ---------- Speed calculator (ported) ----------
 pub struct SpeedCalculator {
     prev_pos_m: Option<[f32; 3]>, // (x,y,z)
@@ -48,8 +65,15 @@ impl SpeedCalculator {
         }
     }
 
+    /// Forget the previous sample and the smoothed velocity.
+    pub fn reset(&mut self) {
+        self.prev_pos_m = None;
+        self.prev_t = None;
+        self.v_smooth = [0.0; 3];
+    }
+
     /// Feed one sample (meters in **(x,z,y)** order).
-    pub fn step(&mut self, pos_xzy_m: [f32; 3], now: Instant) -> Option<Speed> {
+    pub fn step(&mut self, pos_xzy_m: [f32; 3], now: Instant) -> StepResult {
         let pos_m = [pos_xzy_m[0], pos_xzy_m[2], pos_xzy_m[1]]; // -> (x,y,z)
 
         let (prev_pos, prev_t) = match (self.prev_pos_m, self.prev_t) {
@@ -57,7 +81,7 @@ impl SpeedCalculator {
             _ => {
                 self.prev_pos_m = Some(pos_m);
                 self.prev_t = Some(now);
-                return None;
+                return StepResult::Warmup;
             }
         };
 
@@ -65,7 +89,7 @@ impl SpeedCalculator {
         if dt < 1e-3 || dt > self.max_reasonable_dt {
             self.prev_pos_m = Some(pos_m);
             self.prev_t = Some(now);
-            return None;
+            return StepResult::Gap;
         }
 
         let dx = pos_m[0] - prev_pos[0];
@@ -76,7 +100,10 @@ impl SpeedCalculator {
         if step_len_m > self.max_reasonable_step_m {
             self.prev_pos_m = Some(pos_m);
             self.prev_t = Some(now);
-            return None;
+            self.v_smooth = [0.0; 3];
+            return StepResult::Teleport {
+                distance_m: step_len_m,
+            };
         }
 
         let vx_m = dx / dt;
@@ -98,7 +125,7 @@ impl SpeedCalculator {
         self.prev_pos_m = Some(pos_m);
         self.prev_t = Some(now);
 
-        Some(Speed {
+        StepResult::Speed(Speed {
             vx: if vx.abs() < EPS { 0.0 } else { vx },
             vy: if vy.abs() < EPS { 0.0 } else { vy },
             vz: if vz.abs() < EPS { 0.0 } else { vz },
@@ -111,9 +138,14 @@ impl SpeedCalculator {
 
 // ---------- Instant classifier (ported + trimmed) ----------
 pub mod classify {
+    use std::collections::HashMap;
+
+    use serde::{Deserialize, Serialize};
+
     use super::Speed;
+    use crate::gw2::enums::Mount;
 
-    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
     pub enum Movement {
         Idle,
         Walk,
@@ -125,36 +157,222 @@ pub mod classify {
         GlideBack,
         Falling,
         FallingTerminal,
+        /// On a mount, moving on the ground (or water surface).
+        MountedRun,
+        /// On a flying mount, climbing or holding height.
+        Flying,
+        /// On a flying mount, steep fast descent.
+        Diving,
+        /// In water, on the surface or below it (see `WaterState`).
+        Swimming,
         Other,
     }
 
-    pub const GLIDE_VZ_MIN: f32 = 80.0;
-    const GLIDE_VZ_MAX: f32 = 150.0;
-    const GLIDE_BAND_TOL: f32 = 18.0;
+    impl Movement {
+        pub const ALL: [Movement; 15] = [
+            Movement::Idle,
+            Movement::Walk,
+            Movement::RunForward,
+            Movement::Strafe,
+            Movement::Backpedal,
+            Movement::GlideForward,
+            Movement::GlideNeutral,
+            Movement::GlideBack,
+            Movement::Falling,
+            Movement::FallingTerminal,
+            Movement::MountedRun,
+            Movement::Flying,
+            Movement::Diving,
+            Movement::Swimming,
+            Movement::Other,
+        ];
+
+        /// Parse the `Debug` name (as written by `glide_logger`), ignoring case.
+        pub fn from_name(name: &str) -> Option<Self> {
+            Self::ALL
+                .into_iter()
+                .find(|m| format!("{m:?}").eq_ignore_ascii_case(name.trim()))
+        }
+
+        /// Short label for key titles.
+        pub fn label(self) -> &'static str {
+            match self {
+                Movement::Idle => "Idle",
+                Movement::Walk => "Walk",
+                Movement::RunForward => "Run",
+                Movement::Strafe => "Strafe",
+                Movement::Backpedal => "Back",
+                Movement::GlideForward | Movement::GlideNeutral | Movement::GlideBack => "Glide",
+                Movement::Falling => "Fall",
+                Movement::FallingTerminal => "Terminal",
+                Movement::MountedRun => "Mounted",
+                Movement::Flying => "Flying",
+                Movement::Diving => "Diving",
+                Movement::Swimming => "Swim",
+                Movement::Other => "Moving",
+            }
+        }
+    }
+
+    /// Thresholds (game units/s) the classifier compares speeds against.
+    ///
+    /// Defaults are tuned for on-foot movement; every field can be overridden
+    /// from the `classifier` global setting.
+    #[derive(Debug, Clone, Deserialize)]
+    #[serde(default)]
+    pub struct ClassifierProfile {
+        pub idle_h_max: f32,
+
+        pub walk_h: f32,
+        pub walk_tol_h: f32,
+
+        pub run_fwd_ooc_h: f32,
+        pub run_fwd_ic_h: f32,
+        pub run_strafe_h: f32,
+        pub run_back_h: f32,
+        pub run_fwd_tol_h: f32,
+        pub run_strafe_tol_h: f32,
+        pub run_back_tol_h: f32,
+        pub ground_max_vz_for_run: f32,
+        pub ground_max_glide_ratio: f32,
+
+        pub glide_vz_min: f32,
+        pub glide_vz_max: f32,
+        pub glide_band_tol: f32,
+        pub glide_back_h: f32,
+        pub glide_neutral_h: f32,
+        pub glide_fwd_h: f32,
+
+        pub fall_min_vz: f32,
+        pub fall_vertical_dom_ratio: f32,
+        pub terminal_vz: f32,
+        pub beyond_glide_vz_margin: f32,
+
+        /// Flying mounts: vertical speed above which we count as flying.
+        pub fly_vz_min: f32,
+        /// Flying mounts: descent speed above which we count as diving.
+        pub dive_vz_min: f32,
+
+        /// Water surface height. Sea level is 0 on almost every map; lakes
+        /// above sea level are not detected.
+        pub water_level: f32,
+        /// How far below `water_level` the avatar sits while swimming on the surface.
+        pub surface_depth: f32,
+        /// Vertical speed above which we're falling/jumping through, not swimming.
+        pub swim_max_vz: f32,
+
+        /// Upward speed that, reached suddenly from the ground, counts as a jump.
+        pub jump_vz_min: f32,
+        /// Vertical speed below which we may have landed (or sit at the apex).
+        pub land_vz_max: f32,
+        /// Horizontal speed gained over the recent average that counts as a dodge.
+        pub dodge_burst_h: f32,
+        /// Minimum horizontal speed during a dodge.
+        pub dodge_min_h: f32,
+    }
+
+    /// Where the avatar is relative to the water surface.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+    pub enum WaterState {
+        #[default]
+        Dry,
+        Surface,
+        Underwater,
+    }
 
-    const GLIDE_BACK_H: f32 = 80.0;
-    const GLIDE_NEUTRAL_H: f32 = 294.0;
-    const GLIDE_FWD_H: f32 = 390.0;
+    impl ClassifierProfile {
+        /// Water state from avatar height (units) and speed.
+        ///
+        /// Surface swimmers float at a fixed depth below the water level and
+        /// barely move vertically; anything deeper is underwater, unless it's
+        /// moving faster (vertically) than anyone can swim.
+        pub fn water_state(&self, height: f32, s: &Speed) -> WaterState {
+            let depth = self.water_level - height;
+            if depth <= 0.0 || s.vz.abs() > self.swim_max_vz {
+                WaterState::Dry
+            } else if depth <= self.surface_depth {
+                WaterState::Surface
+            } else {
+                WaterState::Underwater
+            }
+        }
+    }
+
+    impl Default for ClassifierProfile {
+        fn default() -> Self {
+            Self {
+                idle_h_max: 10.0,
+
+                walk_h: 80.0,
+                walk_tol_h: 20.0,
+
+                run_fwd_ooc_h: 294.0,
+                run_fwd_ic_h: 210.0,
+                run_strafe_h: 180.0,
+                run_back_h: 105.0,
+                run_fwd_tol_h: 50.0,
+                run_strafe_tol_h: 28.0,
+                run_back_tol_h: 24.0,
+                ground_max_vz_for_run: 180.0,
+                ground_max_glide_ratio: 0.9,
+
+                glide_vz_min: 80.0,
+                glide_vz_max: 150.0,
+                glide_band_tol: 18.0,
+                glide_back_h: 80.0,
+                glide_neutral_h: 294.0,
+                glide_fwd_h: 390.0,
 
-    const FALL_MIN_VZ: f32 = 220.0;
-    const FALL_VERTICAL_DOM_RATIO: f32 = 1.35;
-    const TERMINAL_VZ: f32 = 900.0;
-    const BEYOND_GLIDE_VZ_MARGIN: f32 = 20.0;
+                fall_min_vz: 220.0,
+                fall_vertical_dom_ratio: 1.35,
+                terminal_vz: 900.0,
+                beyond_glide_vz_margin: 20.0,
+
+                fly_vz_min: 60.0,
+                dive_vz_min: 600.0,
+
+                water_level: 0.0,
+                surface_depth: 80.0,
+                swim_max_vz: 400.0,
+
+                jump_vz_min: 200.0,
+                land_vz_max: 40.0,
+                dodge_burst_h: 150.0,
+                dodge_min_h: 400.0,
+            }
+        }
+    }
 
-    const IDLE_H_MAX: f32 = 10.0;
+    /// On-foot profile plus optional per-mount overrides, keyed by `Mount::key`.
+    ///
+    /// ```json
+    /// { "on_foot": { "run_fwd_ooc_h": 300 }, "mounts": { "raptor": { "idle_h_max": 20 } } }
+    /// ```
+    #[derive(Debug, Clone, Default, Deserialize)]
+    #[serde(default)]
+    pub struct ClassifierConfig {
+        pub on_foot: ClassifierProfile,
+        /// Used for mounts without their own entry.
+        pub mounted: ClassifierProfile,
+        pub mounts: HashMap<String, ClassifierProfile>,
+    }
 
-    const WALK_H: f32 = 80.0;
-    const WALK_TOL_H: f32 = 20.0;
+    impl ClassifierConfig {
+        pub fn profile_for(&self, mount: Option<Mount>) -> &ClassifierProfile {
+            match mount {
+                None => &self.on_foot,
+                Some(m) => self.mounts.get(m.key()).unwrap_or(&self.mounted),
+            }
+        }
+    }
 
-    const RUN_FWD_OOC_H: f32 = 294.0;
-    const RUN_FWD_IC_H: f32 = 210.0;
-    const RUN_STRAFE_H: f32 = 180.0;
-    const RUN_BACK_H: f32 = 105.0;
-    const RUN_FWD_TOL_H: f32 = 50.0;
-    const RUN_STRAFE_TOL_H: f32 = 28.0;
-    const RUN_BACK_TOL_H: f32 = 24.0;
-    const GROUND_MAX_VZ_FOR_RUN: f32 = 180.0;
-    const GROUND_MAX_GLIDE_RATIO: f32 = 0.9;
+    /// What the game tells us besides position: changes which thresholds apply.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub struct Conditions {
+        pub in_combat: bool,
+        pub mount: Option<Mount>,
+        pub water: WaterState,
+    }
 
     #[inline]
     pub fn facing_xy_from_front(front_xzy: [f32; 3]) -> [f32; 2] {
@@ -169,11 +387,11 @@ pub mod classify {
     }
 
     #[inline]
-    pub fn snap_glide(h: f32) -> Movement {
+    pub fn snap_glide(h: f32, p: &ClassifierProfile) -> Movement {
         let diffs = [
-            (Movement::GlideBack, (h - GLIDE_BACK_H).abs()),
-            (Movement::GlideNeutral, (h - GLIDE_NEUTRAL_H).abs()),
-            (Movement::GlideForward, (h - GLIDE_FWD_H).abs()),
+            (Movement::GlideBack, (h - p.glide_back_h).abs()),
+            (Movement::GlideNeutral, (h - p.glide_neutral_h).abs()),
+            (Movement::GlideForward, (h - p.glide_fwd_h).abs()),
         ];
         diffs
             .into_iter()
@@ -182,28 +400,41 @@ pub mod classify {
             .0
     }
 
-    pub fn classify(s: &Speed, facing_xy: Option<[f32; 2]>) -> Movement {
+    pub fn classify(
+        s: &Speed,
+        facing_xy: Option<[f32; 2]>,
+        p: &ClassifierProfile,
+        cond: Conditions,
+    ) -> Movement {
         let h = s.horizontal;
         let vz = s.vz;
         let abs_vz = vz.abs();
         let speed3d = s.magnitude;
 
-        if h < IDLE_H_MAX && abs_vz < 5.0 {
+        if h < p.idle_h_max && abs_vz < 5.0 {
             return Movement::Idle;
         }
 
-        if vz < -(GLIDE_VZ_MAX + BEYOND_GLIDE_VZ_MARGIN)
-            && abs_vz >= FALL_VERTICAL_DOM_RATIO * (h + 1.0)
+        if cond.water != WaterState::Dry {
+            return Movement::Swimming;
+        }
+
+        if let Some(mount) = cond.mount {
+            return classify_mounted(s, p, mount);
+        }
+
+        if vz < -(p.glide_vz_max + p.beyond_glide_vz_margin)
+            && abs_vz >= p.fall_vertical_dom_ratio * (h + 1.0)
         {
-            return if abs_vz >= TERMINAL_VZ {
+            return if abs_vz >= p.terminal_vz {
                 Movement::FallingTerminal
             } else {
                 Movement::Falling
             };
         }
 
-        if vz <= -FALL_MIN_VZ && abs_vz >= FALL_VERTICAL_DOM_RATIO * (h + 1.0) {
-            return if abs_vz >= TERMINAL_VZ {
+        if vz <= -p.fall_min_vz && abs_vz >= p.fall_vertical_dom_ratio * (h + 1.0) {
+            return if abs_vz >= p.terminal_vz {
                 Movement::FallingTerminal
             } else {
                 Movement::Falling
@@ -227,12 +458,12 @@ pub mod classify {
             }
         }
 
-        if vz < 0.0 && abs_vz >= GLIDE_VZ_MIN && abs_vz <= GLIDE_VZ_MAX {
-            let m = snap_glide(h);
+        if vz < 0.0 && abs_vz >= p.glide_vz_min && abs_vz <= p.glide_vz_max {
+            let m = snap_glide(h, p);
             let ok = match m {
-                Movement::GlideBack => (h - GLIDE_BACK_H).abs() <= GLIDE_BAND_TOL,
-                Movement::GlideNeutral => (h - GLIDE_NEUTRAL_H).abs() <= GLIDE_BAND_TOL,
-                Movement::GlideForward => (h - GLIDE_FWD_H).abs() <= GLIDE_BAND_TOL,
+                Movement::GlideBack => (h - p.glide_back_h).abs() <= p.glide_band_tol,
+                Movement::GlideNeutral => (h - p.glide_neutral_h).abs() <= p.glide_band_tol,
+                Movement::GlideForward => (h - p.glide_fwd_h).abs() <= p.glide_band_tol,
                 _ => false,
             };
             if ok {
@@ -241,33 +472,28 @@ pub mod classify {
         }
 
         let ground_ok_by_vz =
-            abs_vz <= GROUND_MAX_VZ_FOR_RUN || abs_vz / (h + 1.0) < GROUND_MAX_GLIDE_RATIO;
+            abs_vz <= p.ground_max_vz_for_run || abs_vz / (h + 1.0) < p.ground_max_glide_ratio;
 
         if ground_ok_by_vz {
-            if (speed3d - WALK_H).abs() <= WALK_TOL_H {
+            if (speed3d - p.walk_h).abs() <= p.walk_tol_h {
                 return Movement::Walk;
             }
-            if back_like || (h - RUN_BACK_H).abs() <= RUN_BACK_TOL_H {
+            if back_like || (h - p.run_back_h).abs() <= p.run_back_tol_h {
                 return Movement::Backpedal;
             }
-            if strafe_like || (h - RUN_STRAFE_H).abs() <= RUN_STRAFE_TOL_H {
+            if strafe_like || (h - p.run_strafe_h).abs() <= p.run_strafe_tol_h {
                 return Movement::Strafe;
             }
-            for (t, tol) in [
-                (RUN_FWD_OOC_H, RUN_FWD_TOL_H),
-                (RUN_FWD_IC_H, RUN_FWD_TOL_H),
-            ] {
-                if (forward_comp - t).abs() <= tol {
-                    return Movement::RunForward;
-                }
+            let run_h = if cond.in_combat {
+                p.run_fwd_ic_h
+            } else {
+                p.run_fwd_ooc_h
+            };
+            if (forward_comp - run_h).abs() <= p.run_fwd_tol_h {
+                return Movement::RunForward;
             }
-            for (t, tol) in [
-                (RUN_FWD_OOC_H, RUN_FWD_TOL_H),
-                (RUN_FWD_IC_H, RUN_FWD_TOL_H),
-            ] {
-                if (h - t).abs() <= tol {
-                    return Movement::RunForward;
-                }
+            if (h - run_h).abs() <= p.run_fwd_tol_h {
+                return Movement::RunForward;
             }
             if fwd_like && h > 150.0 {
                 return Movement::RunForward;
@@ -276,18 +502,60 @@ pub mod classify {
 
         Movement::Other
     }
+
+    /// Mount speeds vary too much per mount (and mastery) to pin down run bands,
+    /// so mounted movement only separates ground, air and falls.
+    fn classify_mounted(s: &Speed, p: &ClassifierProfile, mount: Mount) -> Movement {
+        let h = s.horizontal;
+        let vz = s.vz;
+        let abs_vz = vz.abs();
+
+        if mount.can_fly() {
+            if vz <= -p.dive_vz_min {
+                return Movement::Diving;
+            }
+            if abs_vz >= p.fly_vz_min {
+                return Movement::Flying;
+            }
+        } else if vz <= -p.fall_min_vz && abs_vz >= p.fall_vertical_dom_ratio * (h + 1.0) {
+            return if abs_vz >= p.terminal_vz {
+                Movement::FallingTerminal
+            } else {
+                Movement::Falling
+            };
+        }
+
+        if abs_vz <= p.ground_max_vz_for_run || abs_vz / (h + 1.0) < p.ground_max_glide_ratio {
+            Movement::MountedRun
+        } else {
+            Movement::Other
+        }
+    }
 }
 
 // ---------- Temporal layer (ported + trimmed) ----------
-use classify::Movement;
+use classify::{ClassifierProfile, Conditions, Movement, WaterState};
 
 fn same_airborne_family(a: Movement, b: Movement) -> bool {
     use Movement::*;
     matches!(
         (a, b),
         (GlideBack, _) | (GlideNeutral, _) | (GlideForward, _)
-      | (Falling, _) | (FallingTerminal, _)
-        if matches!(b, GlideBack|GlideNeutral|GlideForward|Falling|FallingTerminal)
+      | (Falling, _) | (FallingTerminal, _) | (Flying, _) | (Diving, _)
+        if matches!(b, GlideBack|GlideNeutral|GlideForward|Falling|FallingTerminal|Flying|Diving)
+    )
+}
+
+fn is_airborne(m: Movement) -> bool {
+    matches!(
+        m,
+        Movement::GlideBack
+            | Movement::GlideNeutral
+            | Movement::GlideForward
+            | Movement::Falling
+            | Movement::FallingTerminal
+            | Movement::Flying
+            | Movement::Diving
     )
 }
 
@@ -304,7 +572,6 @@ fn vz_trend(hist: &VecDeque<(Instant, Speed)>) -> f32 {
 const GLIDE_LOCK_VZ_MIN: f32 = 60.0;
 const GLIDE_LOCK_VZ_MAX: f32 = 170.0;
 const GLIDE_LOCK_DWELL_MS: u64 = 180;
-const BEYOND_GLIDE_VZ_MARGIN: f32 = 20.0;
 
 pub struct TemporalClassifier {
     history: VecDeque<(Instant, Speed)>,
@@ -328,7 +595,14 @@ impl TemporalClassifier {
         }
     }
 
-    pub fn update(&mut self, now: Instant, s: Speed, facing_xy: Option<[f32; 2]>) -> Movement {
+    pub fn update(
+        &mut self,
+        now: Instant,
+        s: Speed,
+        facing_xy: Option<[f32; 2]>,
+        p: &ClassifierProfile,
+        cond: Conditions,
+    ) -> Movement {
         self.history.push_back((now, s));
         while let Some((t0, _)) = self.history.front() {
             if now.duration_since(*t0) > self.window {
@@ -360,25 +634,29 @@ impl TemporalClassifier {
         let k = 5usize.min(self.history.len());
         let mut counts = std::collections::HashMap::<Movement, usize>::new();
         for (_, sp) in self.history.iter().rev().take(k) {
-            *counts.entry(classify::classify(sp, facing_xy)).or_default() += 1;
+            *counts
+                .entry(classify::classify(sp, facing_xy, p, cond))
+                .or_default() += 1;
         }
         let (vote, _) = counts
             .into_iter()
             .max_by_key(|(_, c)| *c)
             .unwrap_or((Movement::Other, 0));
-        let avg_label = classify::classify(&avg, facing_xy);
+        let avg_label = classify::classify(&avg, facing_xy, p, cond);
+        // gliding and free fall only happen on foot and out of water
+        let can_glide = cond.mount.is_none() && cond.water == WaterState::Dry;
 
         const FALL_ACCEL_GATE: f32 = -350.0;
         let accel_suggests_fall =
-            vz_trend(&self.history) <= FALL_ACCEL_GATE && avg.vz < -classify::GLIDE_VZ_MIN;
+            can_glide && vz_trend(&self.history) <= FALL_ACCEL_GATE && avg.vz < -p.glide_vz_min;
 
         let abs_vz = avg.vz.abs();
         let looks_glidey_vz =
-            avg.vz < 0.0 && abs_vz >= GLIDE_LOCK_VZ_MIN && abs_vz <= GLIDE_LOCK_VZ_MAX;
+            can_glide && avg.vz < 0.0 && abs_vz >= GLIDE_LOCK_VZ_MIN && abs_vz <= GLIDE_LOCK_VZ_MAX;
         if looks_glidey_vz {
             self.glide_locked_until = now + Duration::from_millis(GLIDE_LOCK_DWELL_MS);
         }
-        let beyond_glide_vz = avg.vz < -(150.0 + BEYOND_GLIDE_VZ_MARGIN);
+        let beyond_glide_vz = avg.vz < -(p.glide_vz_max + p.beyond_glide_vz_margin);
         if accel_suggests_fall || beyond_glide_vz {
             self.glide_locked_until = now;
         }
@@ -391,15 +669,17 @@ impl TemporalClassifier {
             avg_label
         };
 
-        if now <= self.glide_locked_until {
-            proposed = classify::snap_glide(avg.horizontal);
+        // Strictly before: a fall detected above releases the lock with `= now`,
+        // and must not be snapped back to a glide on that same tick.
+        if now < self.glide_locked_until {
+            proposed = classify::snap_glide(avg.horizontal, p);
         } else {
             if matches!(
                 self.state,
                 Movement::GlideBack | Movement::GlideNeutral | Movement::GlideForward
             ) && matches!(proposed, Movement::Other)
             {
-                proposed = classify::snap_glide(avg.horizontal);
+                proposed = classify::snap_glide(avg.horizontal, p);
             }
         }
 
@@ -413,7 +693,7 @@ impl TemporalClassifier {
                 let mut proposed_time = Duration::ZERO;
                 let mut last_t = now;
                 for (t, sp) in self.history.iter().rev() {
-                    if classify::classify(sp, facing_xy) == proposed {
+                    if classify::classify(sp, facing_xy, p, cond) == proposed {
                         proposed_time += last_t.saturating_duration_since(*t);
                         last_t = *t;
                     } else {
@@ -431,15 +711,226 @@ impl TemporalClassifier {
     }
 }
 
+// ---------- Fall tracking ----------
+/// Drop (game units) from which a landing starts to hurt. Approximate.
+pub const FALL_DAMAGE_MIN_UNITS: f32 = 1000.0;
+/// Drop (game units) that a landing is not expected to survive. Approximate.
+pub const FALL_LETHAL_UNITS: f32 = 4500.0;
+
+/// Measures how far we've dropped since we last stood on something (or glided).
+///
+/// The height is remembered on every non-falling sample, so the fall counts
+/// from where it really started rather than from where the classifier first
+/// recognized it. Gliding resets the start, as landing from a glide is safe.
+pub struct FallTracker {
+    /// Height (units) of the last grounded/gliding sample.
+    top: Option<f32>,
+    drop: f32,
+}
+
+impl FallTracker {
+    pub fn new() -> Self {
+        Self {
+            top: None,
+            drop: 0.0,
+        }
+    }
+
+    /// Feed one sample: height in game units and the current movement class.
+    pub fn update(&mut self, height: f32, state: Movement) -> FallRisk {
+        if matches!(state, Movement::Falling | Movement::FallingTerminal) {
+            let top = *self.top.get_or_insert(height);
+            self.drop = (top - height).max(0.0);
+        } else {
+            self.top = Some(height);
+            self.drop = 0.0;
+        }
+        self.risk()
+    }
+
+    /// Units dropped so far in the current fall (0 when not falling).
+    #[inline]
+    pub fn drop(&self) -> f32 {
+        self.drop
+    }
+
+    #[inline]
+    pub fn risk(&self) -> FallRisk {
+        if self.drop >= FALL_LETHAL_UNITS {
+            FallRisk::Lethal
+        } else if self.drop >= FALL_DAMAGE_MIN_UNITS {
+            FallRisk::Damage
+        } else {
+            FallRisk::Safe
+        }
+    }
+}
+
+// ---------- Jumps, landings, dodges ----------
+/// Upward speed must rise at least this much in one sample to count as a jump;
+/// running up a slope climbs steadily instead.
+const JUMP_RISE_VZ: f32 = 100.0;
+/// Settled this long after descending: landed.
+const LAND_SETTLE: Duration = Duration::from_millis(80);
+/// Settled this long without descending: landed on a ledge near the apex.
+const LAND_SETTLE_LEDGE: Duration = Duration::from_millis(200);
+const DODGE_COOLDOWN: Duration = Duration::from_millis(600);
+/// Weight of each sample in the running horizontal average dodges stand out from.
+const DODGE_BASE_ALPHA: f32 = 0.1;
+
+/// Short-lived motion the classifier smooths away.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MoveEvent {
+    Jumped,
+    /// Back on the ground after a jump, fall or glide.
+    Landed {
+        airtime: Duration,
+        /// Units between the highest point of the flight and the landing spot.
+        drop: f32,
+    },
+    Dodged,
+}
+
+struct Flight {
+    since: Instant,
+    top: f32,
+    descended: bool,
+}
+
+/// Picks jumps, landings and dodges out of the raw (smoothed) speed.
+///
+/// Jumps are a sudden climb from the ground; a flight also starts when the
+/// classifier reports an airborne state (walking off a ledge). It ends once the
+/// vertical speed has settled near zero for a moment, which the apex of a jump
+/// only does briefly. Dodges are on-foot bursts of horizontal speed well above
+/// the recent average.
+pub struct JumpTracker {
+    flight: Option<Flight>,
+    settled_since: Option<Instant>,
+    prev_vz: f32,
+    base_h: f32,
+    dodge_ready_at: Option<Instant>,
+}
+
+impl JumpTracker {
+    pub fn new() -> Self {
+        Self {
+            flight: None,
+            settled_since: None,
+            prev_vz: 0.0,
+            base_h: 0.0,
+            dodge_ready_at: None,
+        }
+    }
+
+    /// Feed one sample: height in units, speed, and the classified movement.
+    pub fn update(
+        &mut self,
+        now: Instant,
+        height: f32,
+        s: &Speed,
+        state: Movement,
+        p: &ClassifierProfile,
+        cond: Conditions,
+    ) -> Option<MoveEvent> {
+        let rise = s.vz - self.prev_vz;
+        self.prev_vz = s.vz;
+        let base_h = self.base_h;
+        self.base_h += (s.horizontal - self.base_h) * DODGE_BASE_ALPHA;
+
+        if cond.water != WaterState::Dry {
+            // swimming up and down isn't jumping; entering water ends a flight quietly
+            self.flight = None;
+            self.settled_since = None;
+            return None;
+        }
+
+        let Some(f) = self.flight.as_mut() else {
+            if s.vz >= p.jump_vz_min && rise >= JUMP_RISE_VZ {
+                self.start_flight(now, height);
+                return Some(MoveEvent::Jumped);
+            }
+            if is_airborne(state) {
+                self.start_flight(now, height);
+                return None;
+            }
+            return self.dodge(now, s, base_h, p, cond);
+        };
+
+        f.top = f.top.max(height);
+        f.descended |= s.vz <= -p.land_vz_max;
+
+        // hovering on a flying mount is not a landing
+        if s.vz.abs() > p.land_vz_max || state == Movement::Flying {
+            self.settled_since = None;
+            return None;
+        }
+        let settled = *self.settled_since.get_or_insert(now);
+        let needed = if f.descended {
+            LAND_SETTLE
+        } else {
+            LAND_SETTLE_LEDGE
+        };
+        if now.duration_since(settled) < needed {
+            return None;
+        }
+
+        let event = MoveEvent::Landed {
+            airtime: settled.saturating_duration_since(f.since),
+            drop: (f.top - height).max(0.0),
+        };
+        self.flight = None;
+        self.settled_since = None;
+        Some(event)
+    }
+
+    #[inline]
+    pub fn in_flight(&self) -> bool {
+        self.flight.is_some()
+    }
+
+    fn start_flight(&mut self, now: Instant, height: f32) {
+        self.flight = Some(Flight {
+            since: now,
+            top: height,
+            descended: false,
+        });
+        self.settled_since = None;
+    }
+
+    fn dodge(
+        &mut self,
+        now: Instant,
+        s: &Speed,
+        base_h: f32,
+        p: &ClassifierProfile,
+        cond: Conditions,
+    ) -> Option<MoveEvent> {
+        if cond.mount.is_some() || self.dodge_ready_at.is_some_and(|t| now < t) {
+            return None;
+        }
+        if s.horizontal >= p.dodge_min_h && s.horizontal - base_h >= p.dodge_burst_h {
+            self.dodge_ready_at = Some(now + DODGE_COOLDOWN);
+            return Some(MoveEvent::Dodged);
+        }
+        None
+    }
+}
+
 // ---------- Thin runtime wrapper used by the adapter ----------
 pub struct AirClassifier {
     calc: SpeedCalculator,
     temporal: TemporalClassifier,
     last_state: Movement,
-    last_change: Instant,
-
-    // expose this to the adapter
-    pub landing_grace_ms: u64,
+    last_speed: Option<Speed>,
+    water: WaterState,
+    fall: FallTracker,
+    jumps: JumpTracker,
+    /// Jump/landing/dodge events since the last `take_events`.
+    events: Vec<MoveEvent>,
+    last_landing: Option<Instant>,
+    /// Distance (units) of a teleport seen by the last update, until taken.
+    teleported: Option<f32>,
 }
 
 impl AirClassifier {
@@ -448,52 +939,127 @@ impl AirClassifier {
             calc: SpeedCalculator::new(),
             temporal: TemporalClassifier::new(now),
             last_state: Movement::Idle,
-            last_change: now,
-            landing_grace_ms: 100,
+            last_speed: None,
+            water: WaterState::Dry,
+            fall: FallTracker::new(),
+            jumps: JumpTracker::new(),
+            events: Vec::new(),
+            last_landing: None,
+            teleported: None,
         }
     }
 
-    /// Call once per loop with a motion source.
-    pub fn update_with<S: MotionSource>(&mut self, source: &S) -> Movement {
-        let now = Instant::now();
+    /// Drop all history, e.g. after a map or instance change. The next sample
+    /// starts from scratch instead of being compared to the old location.
+    pub fn reset(&mut self, now: Instant) {
+        self.calc.reset();
+        self.reset_state(now);
+    }
 
-        let (pos_xzy, front_xzy) = match source.read_motion() {
-            Some(v) => v,
-            None => return self.last_state,
-        };
+    fn reset_state(&mut self, now: Instant) {
+        self.temporal = TemporalClassifier::new(now);
+        self.last_state = Movement::Idle;
+        self.last_speed = None;
+        self.water = WaterState::Dry;
+        self.fall = FallTracker::new();
+        self.jumps = JumpTracker::new();
+        self.last_landing = None;
+    }
 
-        if let Some(spd) = self.calc.step(pos_xzy, now) {
-            let facing_xy = classify::facing_xy_from_front(front_xzy);
-            let state = self.temporal.update(now, spd, Some(facing_xy));
-            if state != self.last_state {
-                self.last_state = state;
-                self.last_change = now;
+    /// Call once per loop with a motion source and the profile for the current conditions.
+    pub fn update_with<S: MotionSource>(
+        &mut self,
+        source: &S,
+        profile: &ClassifierProfile,
+        cond: Conditions,
+    ) -> Movement {
+        match source.read_motion() {
+            Some(sample) => self.update_sample(Instant::now(), sample, profile, cond),
+            None => self.last_state,
+        }
+    }
+
+    /// Feed one sample taken at `now`; replays recorded traces with their own clock.
+    pub fn update_sample(
+        &mut self,
+        now: Instant,
+        (pos_xzy, front_xzy): MotionSample,
+        profile: &ClassifierProfile,
+        mut cond: Conditions,
+    ) -> Movement {
+        let height = pos_xzy[1] * UNITS_PER_METER;
+        match self.calc.step(pos_xzy, now) {
+            StepResult::Speed(spd) => {
+                self.last_speed = Some(spd);
+                self.water = profile.water_state(height, &spd);
+                cond.water = self.water;
+                let facing_xy = classify::facing_xy_from_front(front_xzy);
+                self.last_state = self
+                    .temporal
+                    .update(now, spd, Some(facing_xy), profile, cond);
+                if let Some(ev) =
+                    self.jumps
+                        .update(now, height, &spd, self.last_state, profile, cond)
+                {
+                    if let MoveEvent::Landed { .. } = ev {
+                        self.last_landing = Some(now);
+                    }
+                    self.events.push(ev);
+                }
+            }
+            StepResult::Teleport { distance_m } => {
+                // calc already re-seeded itself with the new position
+                self.reset_state(now);
+                self.teleported = Some(distance_m * UNITS_PER_METER);
             }
+            StepResult::Warmup | StepResult::Gap => {}
         }
+        self.fall.update(height, self.last_state);
 
         self.last_state
     }
 
     #[inline]
     pub fn is_airborne(&self) -> bool {
-        matches!(
-            self.last_state,
-            Movement::GlideBack
-                | Movement::GlideNeutral
-                | Movement::GlideForward
-                | Movement::Falling
-                | Movement::FallingTerminal
-        )
+        is_airborne(self.last_state)
     }
 
+    /// When the last flight ended, as reported by a `MoveEvent::Landed`.
     #[inline]
-    pub fn landed_recently(&self) -> bool {
-        !self.is_airborne()
-            && self.last_change.elapsed().as_millis() as u64 <= self.landing_grace_ms
+    pub fn last_landing(&self) -> Option<Instant> {
+        self.last_landing
     }
 
     #[inline]
     pub fn state(&self) -> Movement {
         self.last_state
     }
+
+    /// Smoothed speed from the last usable sample pair.
+    #[inline]
+    pub fn speed(&self) -> Option<Speed> {
+        self.last_speed
+    }
+
+    /// Teleport distance (units) seen since the last call, if any.
+    #[inline]
+    pub fn take_teleport(&mut self) -> Option<f32> {
+        self.teleported.take()
+    }
+
+    /// Jumps, landings and dodges seen since the last call, oldest first.
+    #[inline]
+    pub fn take_events(&mut self) -> Vec<MoveEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    #[inline]
+    pub fn water(&self) -> WaterState {
+        self.water
+    }
+
+    #[inline]
+    pub fn fall(&self) -> &FallTracker {
+        &self.fall
+    }
 }
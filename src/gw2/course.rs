@@ -0,0 +1,524 @@
+// src/gw2/course.rs
+//! Checkpoint courses (jumping puzzles, races) and the split timer that runs them.
+//!
+//! A course is a map id plus an ordered list of checkpoints in Mumble avatar
+//! coordinates (meters, height is the second value, as `glide_logger` records
+//! them). The first checkpoint starts the run, the last one finishes it.
+//!
+//! ```json
+//! {
+//!   "name": "Griffon race",
+//!   "map_id": 1211,
+//!   "checkpoints": [
+//!     { "type": "sphere", "center": [120.5, 34.0, -88.2], "radius": 6.0 },
+//!     { "type": "plane", "point": [300.0, 20.0, -40.0], "normal": [1.0, 0.0, 0.0], "radius": 15.0 },
+//!     { "type": "sphere", "center": [410.0, 18.5, 12.0], "radius": 8.0, "name": "Finish" }
+//!   ]
+//! }
+//! ```
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::gw2::{
+    enums::RunEvent,
+    positions::dist,
+    storage::{data_dir, file_stem, read_json_or_default, write_json},
+};
+
+/// One gate of a course.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Checkpoint {
+    /// Reached by entering the sphere. As the start, the run begins on leaving it,
+    /// so waiting at the start line doesn't count.
+    Sphere {
+        center: [f32; 3],
+        radius: f32,
+        /// Free text for whoever edits the file.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+    },
+    /// Reached by crossing the plane in the direction of `normal`, within
+    /// `radius` of `point`.
+    Plane {
+        point: [f32; 3],
+        normal: [f32; 3],
+        radius: f32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+    },
+}
+
+impl Checkpoint {
+    fn contains(&self, pos: [f32; 3]) -> bool {
+        match self {
+            Checkpoint::Sphere { center, radius, .. } => dist(pos, *center) <= *radius,
+            Checkpoint::Plane { .. } => false,
+        }
+    }
+
+    /// Did moving from `prev` to `pos` pass this checkpoint?
+    fn passed(&self, prev: [f32; 3], pos: [f32; 3], as_start: bool) -> bool {
+        match self {
+            Checkpoint::Sphere { .. } if as_start => self.contains(prev) && !self.contains(pos),
+            Checkpoint::Sphere { .. } => !self.contains(prev) && self.contains(pos),
+            Checkpoint::Plane {
+                point,
+                normal,
+                radius,
+                ..
+            } => {
+                let (a, b) = (
+                    dot(sub(prev, *point), *normal),
+                    dot(sub(pos, *point), *normal),
+                );
+                if !(a < 0.0 && b >= 0.0) {
+                    return false;
+                }
+                // where the step meets the plane
+                let t = a / (a - b);
+                let hit = [
+                    prev[0] + (pos[0] - prev[0]) * t,
+                    prev[1] + (pos[1] - prev[1]) * t,
+                    prev[2] + (pos[2] - prev[2]) * t,
+                ];
+                dist(hit, *point) <= *radius
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Course {
+    pub name: String,
+    pub map_id: u32,
+    pub checkpoints: Vec<Checkpoint>,
+}
+
+impl Course {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("course has no name".into());
+        }
+        if self.checkpoints.len() < 2 {
+            return Err(format!(
+                "course '{}' needs at least a start and a finish",
+                self.name
+            ));
+        }
+        for (i, cp) in self.checkpoints.iter().enumerate() {
+            let (radius, normal) = match cp {
+                Checkpoint::Sphere { radius, .. } => (*radius, None),
+                Checkpoint::Plane { radius, normal, .. } => (*radius, Some(*normal)),
+            };
+            if radius.is_nan() || radius <= 0.0 {
+                return Err(format!(
+                    "course '{}': checkpoint {i} needs a positive radius",
+                    self.name
+                ));
+            }
+            if normal.is_some_and(|n| dot(n, n) < 1e-6) {
+                return Err(format!(
+                    "course '{}': checkpoint {i} has no plane normal",
+                    self.name
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A run in progress.
+#[derive(Debug, Clone)]
+pub struct Run {
+    pub course: String,
+    pub started_at: Instant,
+    /// Index of the checkpoint to reach next.
+    pub next: usize,
+    /// Elapsed ms at each checkpoint reached so far (index 1..).
+    pub splits: Vec<u64>,
+}
+
+/// Watches the player's position against every course on the current map.
+///
+/// Any course whose start is passed begins a run; while running, only that
+/// course is followed. Passing its start again restarts it.
+pub struct SplitTimer {
+    courses: Vec<Course>,
+    /// Best splits per course name; the last entry is the finish time.
+    bests: HashMap<String, Vec<u64>>,
+    run: Option<Run>,
+    prev: Option<(u32, [f32; 3])>,
+}
+
+impl SplitTimer {
+    pub fn new(courses: Vec<Course>, bests: HashMap<String, Vec<u64>>) -> Self {
+        Self {
+            courses,
+            bests,
+            run: None,
+            prev: None,
+        }
+    }
+
+    /// Replace the courses. A run survives only if its course is unchanged;
+    /// otherwise it is abandoned.
+    pub fn set_courses(&mut self, courses: Vec<Course>) -> Option<(String, RunEvent)> {
+        let keep = self.run.as_ref().is_some_and(|run| {
+            let old = self.courses.iter().find(|c| c.name == run.course);
+            courses
+                .iter()
+                .any(|c| c.name == run.course && Some(c) == old)
+        });
+        self.courses = courses;
+        if keep { None } else { self.reset() }
+    }
+
+    pub fn courses(&self) -> &[Course] {
+        &self.courses
+    }
+
+    pub fn run(&self) -> Option<&Run> {
+        self.run.as_ref()
+    }
+
+    pub fn best(&self, course: &str) -> Option<&[u64]> {
+        self.bests.get(course).map(Vec::as_slice)
+    }
+
+    pub fn bests(&self) -> &HashMap<String, Vec<u64>> {
+        &self.bests
+    }
+
+    /// Drop the current run, if any.
+    pub fn reset(&mut self) -> Option<(String, RunEvent)> {
+        self.run.take().map(|run| (run.course, RunEvent::Abandoned))
+    }
+
+    /// Feed one position sample. Returns the course name and what happened to it.
+    pub fn update(
+        &mut self,
+        now: Instant,
+        map_id: u32,
+        pos: [f32; 3],
+    ) -> Option<(String, RunEvent)> {
+        let prev = self.prev.replace((map_id, pos));
+        let prev = match prev {
+            Some((prev_map, prev_pos)) if prev_map == map_id => prev_pos,
+            // first sample, or a new map: nothing to compare against
+            _ => return self.reset(),
+        };
+
+        // (re)start: any course on this map whose start we just passed
+        if let Some(course) = self
+            .courses
+            .iter()
+            .find(|c| c.map_id == map_id && c.checkpoints[0].passed(prev, pos, true))
+        {
+            self.run = Some(Run {
+                course: course.name.clone(),
+                started_at: now,
+                next: 1,
+                splits: Vec::new(),
+            });
+            return Some((course.name.clone(), RunEvent::Started));
+        }
+
+        let run = self.run.as_mut()?;
+        let Some(course) = self.courses.iter().find(|c| c.name == run.course) else {
+            return self.reset();
+        };
+        // `set_courses` drops runs whose course changed; abandon rather than panic anyway
+        let Some(next) = course.checkpoints.get(run.next) else {
+            return self.reset();
+        };
+        if !next.passed(prev, pos, false) {
+            return None;
+        }
+
+        let elapsed_ms = now.duration_since(run.started_at).as_millis() as u64;
+        let index = run.next;
+        run.splits.push(elapsed_ms);
+        run.next += 1;
+        let best = self.bests.get(&run.course);
+        let delta_ms = best
+            .and_then(|b| b.get(index - 1))
+            .map(|b| elapsed_ms as i64 - *b as i64);
+
+        if run.next < course.checkpoints.len() {
+            return Some((
+                run.course.clone(),
+                RunEvent::Split {
+                    index,
+                    elapsed_ms,
+                    delta_ms,
+                },
+            ));
+        }
+
+        let run = self.run.take()?;
+        let personal_best = best.and_then(|b| b.last()).is_none_or(|b| elapsed_ms < *b);
+        if personal_best {
+            self.bests.insert(run.course.clone(), run.splits);
+        }
+        Some((
+            run.course,
+            RunEvent::Finished {
+                elapsed_ms,
+                delta_ms,
+                personal_best,
+            },
+        ))
+    }
+}
+
+// ── storage ──────────────────────────────────────────────────────────────────
+
+const BESTS_FILE: &str = "personal_bests.json";
+
+/// Courses live as one JSON file each in `<data dir>/courses`, so they can be
+/// shared by copying files; personal bests go to `<data dir>/personal_bests.json`.
+#[derive(Debug, Clone)]
+pub struct CourseStore {
+    dir: PathBuf,
+}
+
+impl CourseStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The plugin's per-user data directory.
+    pub fn open_default() -> Result<Self, String> {
+        data_dir().map(Self::new)
+    }
+
+    pub fn courses_dir(&self) -> PathBuf {
+        self.dir.join("courses")
+    }
+
+    /// Every valid course file; broken ones are reported, not fatal.
+    pub fn load_courses(&self) -> (Vec<Course>, Vec<String>) {
+        let (mut courses, mut errors) = (Vec::new(), Vec::new());
+        let Ok(entries) = fs::read_dir(self.courses_dir()) else {
+            return (courses, errors);
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|e| e == "json"))
+            .collect();
+        paths.sort();
+        for path in paths {
+            match read_course(&path) {
+                Ok(c) => courses.push(c),
+                Err(e) => errors.push(e),
+            }
+        }
+        (courses, errors)
+    }
+
+    /// Copy a course file into the store, replacing a course of the same name.
+    pub fn import(&self, path: &Path) -> Result<Course, String> {
+        let course = read_course(path)?;
+        self.save_course(&course)?;
+        Ok(course)
+    }
+
+    /// Write course `name` (as stored) to `path`, an absolute `.json` path in an
+    /// existing folder.
+    pub fn export(&self, name: &str, path: &Path) -> Result<(), String> {
+        let is_json = path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("json"));
+        if !path.is_absolute() || !is_json {
+            return Err(format!(
+                "{}: export needs a full path to a .json file",
+                path.display()
+            ));
+        }
+        if !path.parent().is_some_and(Path::is_dir) {
+            return Err(format!("{}: folder doesn't exist", path.display()));
+        }
+        let (courses, _) = self.load_courses();
+        let course = courses
+            .iter()
+            .find(|c| c.name == name)
+            .ok_or(format!("no course named '{name}'"))?;
+        write_json(path, course)
+    }
+
+    pub fn save_course(&self, course: &Course) -> Result<(), String> {
+        course.validate()?;
+        write_json(
+            &self
+                .courses_dir()
+                .join(format!("{}.json", file_stem(&course.name))),
+            course,
+        )
+    }
+
+    /// Empty when no best was set yet; an error when the file is unreadable.
+    pub fn load_bests(&self) -> Result<HashMap<String, Vec<u64>>, String> {
+        read_json_or_default(&self.dir.join(BESTS_FILE))
+    }
+
+    pub fn save_bests(&self, bests: &HashMap<String, Vec<u64>>) -> Result<(), String> {
+        write_json(&self.dir.join(BESTS_FILE), bests)
+    }
+}
+
+fn read_course(path: &Path) -> Result<Course, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let course: Course =
+        serde_json::from_str(&text).map_err(|e| format!("{}: {e}", path.display()))?;
+    course
+        .validate()
+        .map_err(|e| format!("{}: {e}", path.display()))?;
+    Ok(course)
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// `+1.23` / `-0.45` seconds, for split deltas.
+pub fn format_delta(delta_ms: i64) -> String {
+    let sign = if delta_ms < 0 { '-' } else { '+' };
+    let ms = delta_ms.unsigned_abs();
+    format!("{sign}{}.{:02}", ms / 1000, ms % 1000 / 10)
+}
+
+/// `m:ss.t` for run times.
+pub fn format_elapsed(ms: u64) -> String {
+    format!("{}:{:02}.{}", ms / 60_000, ms / 1000 % 60, ms % 1000 / 100)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn sphere(center: [f32; 3], radius: f32) -> Checkpoint {
+        Checkpoint::Sphere {
+            center,
+            radius,
+            name: None,
+        }
+    }
+
+    fn course(checkpoints: Vec<Checkpoint>) -> Course {
+        Course {
+            name: "Race".into(),
+            map_id: 15,
+            checkpoints,
+        }
+    }
+
+    fn gate(radius: f32) -> Checkpoint {
+        Checkpoint::Plane {
+            point: [0.0; 3],
+            normal: [1.0, 0.0, 0.0],
+            radius,
+            name: None,
+        }
+    }
+
+    #[test]
+    fn sphere_starts_on_exit_and_counts_on_entry() {
+        let cp = sphere([0.0; 3], 5.0);
+        let (inside, outside) = ([1.0, 0.0, 0.0], [8.0, 0.0, 0.0]);
+
+        assert!(cp.passed(inside, outside, true));
+        assert!(!cp.passed(outside, inside, true));
+        assert!(!cp.passed(inside, inside, true));
+
+        assert!(cp.passed(outside, inside, false));
+        assert!(!cp.passed(inside, outside, false));
+        assert!(!cp.passed(inside, inside, false));
+        assert!(!cp.passed(outside, [0.0, 0.0, 8.0], false));
+    }
+
+    #[test]
+    fn plane_counts_crossings_along_the_normal_within_radius() {
+        let cp = gate(10.0);
+        assert!(cp.passed([-1.0, 0.0, 3.0], [1.0, 0.0, 3.0], false));
+        // landing exactly on the plane counts
+        assert!(cp.passed([-1.0, 0.0, 0.0], [0.0, 0.0, 0.0], false));
+        // a start plane behaves the same
+        assert!(cp.passed([-1.0, 0.0, 3.0], [1.0, 0.0, 3.0], true));
+
+        // wrong way, no crossing, or outside the radius
+        assert!(!cp.passed([1.0, 0.0, 3.0], [-1.0, 0.0, 3.0], false));
+        assert!(!cp.passed([-3.0, 0.0, 3.0], [-1.0, 0.0, 3.0], false));
+        assert!(!cp.passed([-1.0, 0.0, 12.0], [1.0, 0.0, 12.0], false));
+        assert!(!cp.passed([-1.0, 11.0, 0.0], [1.0, 11.0, 0.0], false));
+    }
+
+    #[test]
+    fn plane_radius_is_measured_where_the_step_crosses() {
+        // a long diagonal step: both ends are far from the gate, the crossing isn't
+        let cp = gate(10.0);
+        assert!(cp.passed([-20.0, 0.0, -20.0], [20.0, 0.0, 20.0], false));
+        assert!(!cp.passed([-20.0, 0.0, 0.0], [20.0, 0.0, 40.0], false));
+    }
+
+    #[test]
+    fn formats_times() {
+        assert_eq!(format_delta(-450), "-0.45");
+        assert_eq!(format_delta(1234), "+1.23");
+        assert_eq!(format_delta(0), "+0.00");
+        assert_eq!(format_delta(-61_009), "-61.00");
+        assert_eq!(format_elapsed(0), "0:00.0");
+        assert_eq!(format_elapsed(83_456), "1:23.4");
+        assert_eq!(format_elapsed(3_600_999), "60:00.9");
+    }
+
+    /// A run on a three-checkpoint course, waiting for checkpoint 1.
+    fn running(now: Instant) -> SplitTimer {
+        let mut timer = SplitTimer::new(
+            vec![course(vec![
+                sphere([0.0; 3], 5.0),
+                sphere([50.0, 0.0, 0.0], 5.0),
+                sphere([100.0, 0.0, 0.0], 5.0),
+            ])],
+            HashMap::new(),
+        );
+        timer.update(now, 15, [0.0; 3]);
+        let started = timer.update(now, 15, [10.0, 0.0, 0.0]);
+        assert_eq!(started, Some(("Race".into(), RunEvent::Started)));
+        timer
+    }
+
+    #[test]
+    fn changed_course_abandons_the_run() {
+        let now = Instant::now();
+        let mut timer = running(now);
+        let unchanged = timer.courses().to_vec();
+        assert_eq!(timer.set_courses(unchanged), None);
+        assert!(timer.run().is_some());
+
+        // shortened to start and finish while the run waits for checkpoint 2 of 3
+        timer.update(now, 15, [50.0, 0.0, 0.0]);
+        let shorter = course(vec![sphere([0.0; 3], 5.0), sphere([50.0, 0.0, 0.0], 5.0)]);
+        assert_eq!(
+            timer.set_courses(vec![shorter]),
+            Some(("Race".into(), RunEvent::Abandoned))
+        );
+        assert!(timer.run().is_none());
+        let later = now + Duration::from_secs(1);
+        assert_eq!(timer.update(later, 15, [100.0, 0.0, 0.0]), None);
+    }
+}
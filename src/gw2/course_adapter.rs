@@ -0,0 +1,198 @@
+#![cfg(windows)]
+
+use std::{collections::HashMap, sync::Arc};
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::{Receiver as CbReceiver, bounded, select, tick};
+
+use streamdeck_lib::prelude::*;
+
+use crate::gw2::airborne::MotionSource;
+use crate::gw2::course::{CourseStore, SplitTimer};
+use crate::gw2::enums::RunEvent;
+use crate::gw2::mumble::MumbleLink;
+use crate::gw2::shared::{CourseStatus, SharedCourse};
+use crate::topics::{COURSE_EVENT, COURSE_RESET, COURSES_RELOAD, CourseEvent};
+
+/// Mumble updates at ~25 Hz; sampling faster only repeats frames.
+const SAMPLE_EVERY: Duration = Duration::from_millis(40);
+
+/// Runs the checkpoint split timer on Mumble positions.
+///
+/// Courses and personal bests are read from the `CourseStore`; a new
+/// personal best is saved as soon as the run finishes.
+///
+/// Publishes:
+/// - "course.event" -> start / split / finish / abandon of a run
+///
+/// Listens:
+/// - "course.reset"  -> abandon the current run
+/// - "course.reload" -> re-read the course files
+pub struct CourseAdapter;
+
+impl CourseAdapter {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Adapter for CourseAdapter {
+    fn name(&self) -> &'static str {
+        "gw2.course_adapter"
+    }
+
+    fn policy(&self) -> StartPolicy {
+        StartPolicy::OnAppLaunch
+    }
+
+    fn topics(&self) -> &'static [&'static str] {
+        &[COURSE_RESET.name, COURSES_RELOAD.name]
+    }
+
+    fn start(
+        &self,
+        cx: &Context,
+        bus: Arc<dyn Bus>,
+        inbox: CbReceiver<Arc<ErasedTopic>>,
+    ) -> AdapterResult {
+        let (stop_tx, stop_rx) = bounded::<()>(1);
+        let logger = cx.log().clone();
+        let shared = cx.try_ext::<SharedCourse>().ok_or(AdapterError::Init(
+            "SharedCourse extension not found".into(),
+        ))?;
+        let store = CourseStore::open_default().map_err(AdapterError::Init)?;
+
+        let join = thread::spawn(move || {
+            let load = |timer: &mut SplitTimer| {
+                let (courses, errors) = store.load_courses();
+                for e in errors {
+                    warn!(logger, "course: {e}");
+                }
+                info!(logger, "course: {} course(s) loaded", courses.len());
+                timer.set_courses(courses)
+            };
+
+            let ticker = tick(SAMPLE_EVERY);
+            // An unreadable bests file is left alone rather than replaced by this session's.
+            let (bests, bests_writable) = match store.load_bests() {
+                Ok(bests) => (bests, true),
+                Err(e) => {
+                    error!(logger, "course: {e}; new personal bests won't be saved");
+                    (HashMap::new(), false)
+                }
+            };
+            let mut timer = SplitTimer::new(Vec::new(), bests);
+            let _ = load(&mut timer);
+            let mut status = CourseStatus::default();
+            let mut link: Option<MumbleLink> = None;
+            let mut retry_at = Instant::now();
+
+            info!(logger, "course adapter started");
+
+            loop {
+                let outcome = select! {
+                    recv(inbox) -> msg => {
+                        let Ok(note) = msg else {
+                            break; // inbox closed
+                        };
+                        if note.downcast(COURSES_RELOAD).is_some() {
+                            load(&mut timer)
+                        } else if note.downcast(COURSE_RESET).is_some() {
+                            timer.reset()
+                        } else {
+                            None
+                        }
+                    }
+
+                    recv(stop_rx) -> _ => {
+                        debug!(logger, "Stopping course adapter...");
+                        break;
+                    }
+
+                    recv(ticker) -> _ => {
+                        // ensure link
+                        if link.is_none() {
+                            if Instant::now() < retry_at {
+                                continue;
+                            }
+                            match MumbleLink::new() {
+                                Ok(l) => link = Some(l),
+                                Err(e) => {
+                                    warn!(logger, "course: map failed: {e}");
+                                    retry_at = Instant::now() + Duration::from_secs(2);
+                                    continue;
+                                }
+                            }
+                        }
+
+                        let Some(ref l) = link else { continue };
+                        // map_id 0: character select / loading screen
+                        let map_id = l.read_context().map_or(0, |c| c.map_id());
+                        match l.read_motion() {
+                            Some((pos, _)) if map_id != 0 => timer.update(Instant::now(), map_id, pos),
+                            _ => None,
+                        }
+                    }
+                };
+
+                let Some((course, event)) = outcome else {
+                    continue;
+                };
+                debug!(logger, "course '{}': {:?}", course, event);
+
+                if let RunEvent::Finished {
+                    personal_best: true,
+                    ..
+                } = event
+                    && bests_writable
+                    && let Err(e) = store.save_bests(timer.bests())
+                {
+                    error!(logger, "course: saving personal bests failed: {e}");
+                }
+                update_status(&mut status, &timer, &course, &event);
+                shared.set(status.clone());
+                bus.publish_t(COURSE_EVENT, CourseEvent { course, event });
+            }
+
+            info!(logger, "course adapter stopped");
+        });
+
+        Ok(AdapterHandle::from_crossbeam(join, stop_tx))
+    }
+}
+
+fn update_status(status: &mut CourseStatus, timer: &SplitTimer, course: &str, event: &RunEvent) {
+    if status.course.as_deref() != Some(course) {
+        *status = CourseStatus {
+            course: Some(course.to_string()),
+            ..CourseStatus::default()
+        };
+    }
+    status.checkpoints = timer
+        .courses()
+        .iter()
+        .find(|c| c.name == course)
+        .map_or(0, |c| c.checkpoints.len());
+    status.best_ms = timer.best(course).and_then(|b| b.last().copied());
+    status.run = timer.run().cloned();
+
+    match *event {
+        RunEvent::Started => {
+            status.last_delta_ms = None;
+            status.finished = None;
+        }
+        RunEvent::Split { delta_ms, .. } => status.last_delta_ms = delta_ms,
+        RunEvent::Finished {
+            elapsed_ms,
+            delta_ms,
+            personal_best,
+        } => {
+            status.last_delta_ms = delta_ms;
+            status.finished = Some((elapsed_ms, personal_best));
+        }
+        RunEvent::Abandoned => {}
+    }
+}
@@ -245,6 +245,14 @@ pub struct TemplateNames {
     pub equipment: [Option<String>; 9],
 }
 
+/// Currently active build/equipment tab (1-based), as reported by the API
+/// or updated locally after a swap.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ActiveTabs {
+    pub build: Option<u8>,
+    pub equipment: Option<u8>,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CharacterChange {
     /// Character was added
@@ -252,3 +260,342 @@ pub enum CharacterChange {
     /// Character was removed
     Removed,
 }
+
+/// Which copy of a map we're in, from the Mumble context block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct InstanceId {
+    pub shard_id: u32,
+    pub instance: u32,
+    pub server: Option<std::net::SocketAddr>,
+}
+
+/// Parsed Mumble `identity` JSON.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Identity {
+    pub name: String,
+    pub profession: Option<u8>,
+    pub spec: Option<u16>,
+    pub race: Option<u8>,
+    pub map_id: Option<u32>,
+    pub world_id: Option<u32>,
+    pub team_color_id: Option<u8>,
+    pub commander: Option<bool>,
+    pub fov: Option<f32>,
+    pub uisz: Option<u8>,
+}
+
+/// Profession name for the Mumble `profession` id.
+pub fn profession_name(id: u8) -> Option<&'static str> {
+    Some(match id {
+        1 => "Guardian",
+        2 => "Warrior",
+        3 => "Engineer",
+        4 => "Ranger",
+        5 => "Thief",
+        6 => "Elementalist",
+        7 => "Mesmer",
+        8 => "Necromancer",
+        9 => "Revenant",
+        _ => return None,
+    })
+}
+
+/// Elite specialization name for the Mumble `spec` id (core lines return None).
+pub fn elite_spec_name(id: u16) -> Option<&'static str> {
+    Some(match id {
+        5 => "Druid",
+        7 => "Daredevil",
+        18 => "Berserker",
+        27 => "Dragonhunter",
+        34 => "Reaper",
+        40 => "Chronomancer",
+        43 => "Scrapper",
+        48 => "Tempest",
+        52 => "Herald",
+        55 => "Soulbeast",
+        56 => "Weaver",
+        57 => "Holosmith",
+        58 => "Deadeye",
+        59 => "Mirage",
+        60 => "Scourge",
+        61 => "Spellbreaker",
+        62 => "Firebrand",
+        63 => "Renegade",
+        64 => "Harbinger",
+        65 => "Willbender",
+        66 => "Virtuoso",
+        67 => "Catalyst",
+        68 => "Bladesworn",
+        69 => "Vindicator",
+        70 => "Mechanist",
+        71 => "Specter",
+        72 => "Untamed",
+        _ => return None,
+    })
+}
+
+/// Mounts in Mumble `mount_index` order (0 means not mounted).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum Mount {
+    Jackal = 1,
+    Griffon = 2,
+    Springer = 3,
+    Skimmer = 4,
+    Raptor = 5,
+    RollerBeetle = 6,
+    Warclaw = 7,
+    Skyscale = 8,
+    Skiff = 9,
+    SiegeTurtle = 10,
+}
+
+impl Mount {
+    /// Mounts that have a summon keybind, in mount-wheel order.
+    pub const SUMMONABLE: [Mount; 9] = [
+        Mount::Raptor,
+        Mount::Springer,
+        Mount::Skimmer,
+        Mount::Jackal,
+        Mount::Griffon,
+        Mount::RollerBeetle,
+        Mount::Warclaw,
+        Mount::Skyscale,
+        Mount::SiegeTurtle,
+    ];
+
+    pub fn from_mumble(index: u8) -> Option<Self> {
+        use Mount::*;
+        Some(match index {
+            1 => Jackal,
+            2 => Griffon,
+            3 => Springer,
+            4 => Skimmer,
+            5 => Raptor,
+            6 => RollerBeetle,
+            7 => Warclaw,
+            8 => Skyscale,
+            9 => Skiff,
+            10 => SiegeTurtle,
+            _ => return None,
+        })
+    }
+
+    pub fn name(self) -> &'static str {
+        use Mount::*;
+        match self {
+            Jackal => "Jackal",
+            Griffon => "Griffon",
+            Springer => "Springer",
+            Skimmer => "Skimmer",
+            Raptor => "Raptor",
+            RollerBeetle => "Roller Beetle",
+            Warclaw => "Warclaw",
+            Skyscale => "Skyscale",
+            Skiff => "Skiff",
+            SiegeTurtle => "Siege Turtle",
+        }
+    }
+
+    /// Value stored in action settings.
+    pub fn key(self) -> &'static str {
+        use Mount::*;
+        match self {
+            Jackal => "jackal",
+            Griffon => "griffon",
+            Springer => "springer",
+            Skimmer => "skimmer",
+            Raptor => "raptor",
+            RollerBeetle => "roller_beetle",
+            Warclaw => "warclaw",
+            Skyscale => "skyscale",
+            Skiff => "skiff",
+            SiegeTurtle => "siege_turtle",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        (1..=10)
+            .filter_map(Self::from_mumble)
+            .find(|m| m.key() == key)
+    }
+
+    /// Mounts that can gain height in the air (and dive).
+    pub fn can_fly(self) -> bool {
+        matches!(self, Mount::Griffon | Mount::Skyscale)
+    }
+
+    /// Summon keybind; the skiff is only reachable through the fishing/skiff UI.
+    pub fn control(self) -> Option<KeyControl> {
+        use Mount::*;
+        Some(match self {
+            Jackal => KeyControl::MountsJackal,
+            Griffon => KeyControl::MountsGriffon,
+            Springer => KeyControl::MountsSpringer,
+            Skimmer => KeyControl::MountsSkimmer,
+            Raptor => KeyControl::MountsRaptor,
+            RollerBeetle => KeyControl::MountsRollerBeetle,
+            Warclaw => KeyControl::MountsWarclaw,
+            Skyscale => KeyControl::MountsSkyscale,
+            SiegeTurtle => KeyControl::MountsTurtle,
+            Skiff => return None,
+        })
+    }
+}
+
+/// Game mode of the current map, from the Mumble context `map_type`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MapKind {
+    Pve,
+    Pvp,
+    Wvw,
+}
+
+impl MapKind {
+    pub fn from_mumble(map_type: u32) -> Self {
+        match map_type {
+            // PvP, tournament, user tournament
+            2 | 6 | 8 => MapKind::Pvp,
+            // borderlands, Eternal Battlegrounds, Edge of the Mists, Obsidian Sanctum, lounge
+            9..=15 | 18 => MapKind::Wvw,
+            _ => MapKind::Pve,
+        }
+    }
+}
+
+/// How bad landing from the current fall would be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub enum FallRisk {
+    /// Not falling, or the drop is too short to hurt.
+    #[default]
+    Safe,
+    Damage,
+    Lethal,
+}
+
+/// Something that happened to a split timer run; see `gw2::course`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type")]
+pub enum RunEvent {
+    Started,
+    /// Checkpoint `index` reached; `delta_ms` against the personal best, if any.
+    Split {
+        index: usize,
+        elapsed_ms: u64,
+        delta_ms: Option<i64>,
+    },
+    Finished {
+        elapsed_ms: u64,
+        delta_ms: Option<i64>,
+        personal_best: bool,
+    },
+    /// Left the map (or reset) before finishing.
+    Abandoned,
+}
+
+bitflags::bitflags! {
+    /// Mumble context `ui_state` bits.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct UiState: u32 {
+        const MAP_OPEN          = 1 << 0;
+        const COMPASS_TOP_RIGHT = 1 << 1;
+        const COMPASS_ROTATION  = 1 << 2;
+        const GAME_HAS_FOCUS    = 1 << 3;
+        const COMPETITIVE_MODE  = 1 << 4;
+        const TEXTBOX_HAS_FOCUS = 1 << 5;
+        const IN_COMBAT         = 1 << 6;
+    }
+}
+impl UiState {
+    #[inline]
+    pub fn is_in_combat(self) -> bool {
+        self.contains(Self::IN_COMBAT)
+    }
+    #[inline]
+    pub fn is_map_open(self) -> bool {
+        self.contains(Self::MAP_OPEN)
+    }
+}
+
+/// Squad marker shapes; each exists as a location (ground) and an object marker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SquadMarker {
+    Arrow,
+    Circle,
+    Heart,
+    Square,
+    Star,
+    Spiral,
+    Triangle,
+    X,
+}
+
+impl SquadMarker {
+    pub const ALL: [SquadMarker; 8] = [
+        SquadMarker::Arrow,
+        SquadMarker::Circle,
+        SquadMarker::Heart,
+        SquadMarker::Square,
+        SquadMarker::Star,
+        SquadMarker::Spiral,
+        SquadMarker::Triangle,
+        SquadMarker::X,
+    ];
+
+    /// Value stored in action settings.
+    pub fn key(self) -> &'static str {
+        use SquadMarker::*;
+        match self {
+            Arrow => "arrow",
+            Circle => "circle",
+            Heart => "heart",
+            Square => "square",
+            Star => "star",
+            Spiral => "spiral",
+            Triangle => "triangle",
+            X => "x",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|m| m.key() == key)
+    }
+
+    pub fn name(self) -> &'static str {
+        use SquadMarker::*;
+        match self {
+            Arrow => "Arrow",
+            Circle => "Circle",
+            Heart => "Heart",
+            Square => "Square",
+            Star => "Star",
+            Spiral => "Spiral",
+            Triangle => "Triangle",
+            X => "X",
+        }
+    }
+
+    /// Place control for a location (`object == false`) or object marker.
+    pub fn control(self, object: bool) -> KeyControl {
+        use KeyControl::*;
+        use SquadMarker::*;
+        match (self, object) {
+            (Arrow, false) => SquadLocationArrow,
+            (Circle, false) => SquadLocationCircle,
+            (Heart, false) => SquadLocationHeart,
+            (Square, false) => SquadLocationSquare,
+            (Star, false) => SquadLocationStar,
+            (Spiral, false) => SquadLocationSpiral,
+            (Triangle, false) => SquadLocationTriangle,
+            (X, false) => SquadLocationX,
+            (Arrow, true) => SquadObjectArrow,
+            (Circle, true) => SquadObjectCircle,
+            (Heart, true) => SquadObjectHeart,
+            (Square, true) => SquadObjectSquare,
+            (Star, true) => SquadObjectStar,
+            (Spiral, true) => SquadObjectSpiral,
+            (Triangle, true) => SquadObjectTriangle,
+            (X, true) => SquadObjectX,
+        }
+    }
+}
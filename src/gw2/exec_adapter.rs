@@ -2,27 +2,33 @@
 
 use crossbeam_channel::{Receiver as CbReceiver, bounded, select};
 use std::time::Instant;
-use std::{
-    collections::VecDeque,
-    sync::{Arc, Mutex},
-    thread,
-    time::Duration,
-};
+use std::{collections::VecDeque, sync::Arc, thread, time::Duration};
 
 use streamdeck_lib::prelude::*;
 
-use crate::gw2::airborne::{AirClassifier, classify::Movement};
+use crate::gw2::airborne::classify::WaterState;
 use crate::gw2::mumble::MumbleLink;
-use crate::gw2::shared::SharedBindings;
-use crate::topics::{ExecState, GW2_EXEC_PROGRESS, GW2_EXEC_QUEUE, Gw2ExecQueue};
+use crate::gw2::shared::{SharedBindings, SharedMotion};
+use crate::topics::{
+    BlockReason, ExecActivity, ExecState, GW2_EXEC_ACTIVITY, GW2_EXEC_PROGRESS, GW2_EXEC_QUEUE,
+    GW2_EXEC_WAITING, Gw2ExecQueue, Gw2ExecWaiting,
+};
 
 // Use the Windows synth (or swap behind a feature if you want)
 use streamdeck_lib::input::WinSynth;
 
+/// Upper bound on controls merged into one coalesced job; further ticks queue anew.
+const MAX_COALESCED: usize = 16;
+/// The game ignores some inputs for a moment after landing.
+const LANDING_GRACE: Duration = Duration::from_millis(100);
+
 struct Job {
     req: Gw2ExecQueue,
     /// Pre-expanded steps; built when the job is enqueued so we can log errors early.
     steps: Vec<streamdeck_lib::prelude::InputStep>,
+    queued_at: Instant,
+    /// Last waiting notice sent (reason, waited s, remaining s); re-sent only on change.
+    last_waiting: Option<(BlockReason, u64, Option<u64>)>,
 }
 
 struct CombatSensor {
@@ -119,83 +125,17 @@ impl Adapter for Gw2ExecAdapter {
             "SharedBindings extension not found".into(),
         ))?;
 
+        let motion = cx.try_ext::<SharedMotion>().ok_or(AdapterError::Init(
+            "SharedMotion extension not found".into(),
+        ))?;
+
         let logger = cx.log().clone();
 
         let join = thread::spawn(move || {
             let synth = WinSynth::new();
             let mut queue: VecDeque<Job> = VecDeque::new();
-            let mut last_tick = std::time::Instant::now();
-            let tick_every = Duration::from_millis(300);
             let mut combat = CombatSensor::new();
 
-            // --- Airborne worker: shared snapshot + thread ---
-            #[derive(Copy, Clone)]
-            struct AirSnapshot {
-                state: Movement,
-                in_air: bool,
-                landed_recently: bool,
-            }
-            impl Default for AirSnapshot {
-                fn default() -> Self {
-                    Self {
-                        state: Movement::Idle,
-                        in_air: false,
-                        landed_recently: false,
-                    }
-                }
-            }
-
-            let air_snapshot: Arc<Mutex<AirSnapshot>> =
-                Arc::new(Mutex::new(AirSnapshot::default()));
-
-            // clone handles for the worker
-            let air_snapshot_worker = Arc::clone(&air_snapshot);
-            let stop_rx_air = stop_rx.clone();
-            let logger_air = logger.clone();
-
-            thread::spawn(move || {
-                let mut air = AirClassifier::new(Instant::now());
-                let mut link: Option<MumbleLink> = None;
-
-                loop {
-                    if stop_rx_air.try_recv().is_ok() {
-                        debug!(logger_air, "airborne worker: stop received");
-                        break;
-                    }
-
-                    // ensure link
-                    if link.is_none() {
-                        match MumbleLink::new() {
-                            Ok(l) => {
-                                info!(logger_air, "airborne worker: MumbleLink mapped");
-                                link = Some(l);
-                            }
-                            Err(e) => {
-                                // back off a bit if mapping fails
-                                warn!(logger_air, "airborne worker: map failed: {e}");
-                                thread::sleep(Duration::from_millis(200));
-                                continue;
-                            }
-                        }
-                    }
-
-                    if let Some(ref l) = link {
-                        let state = air.update_with(l); // runs classifier
-                        let snap = AirSnapshot {
-                            state,
-                            in_air: air.is_airborne(),
-                            landed_recently: air.landed_recently(),
-                        };
-                        if let Ok(mut guard) = air_snapshot_worker.lock() {
-                            *guard = snap;
-                        }
-                    }
-
-                    // ~25 Hz (Mumble updates ~25 Hz)
-                    thread::sleep(Duration::from_millis(40));
-                }
-            });
-
             info!(logger, "GW2 exec adapter started");
 
             // Expand an ExecRequest to a Job (steps baked)
@@ -228,7 +168,12 @@ impl Adapter for Gw2ExecAdapter {
                     }
                 }
 
-                Job { req, steps }
+                Job {
+                    req,
+                    steps,
+                    queued_at: Instant::now(),
+                    last_waiting: None,
+                }
             };
 
             let handle_enqueue = |topic: &str, data: Gw2ExecQueue| -> Option<Job> {
@@ -245,7 +190,7 @@ impl Adapter for Gw2ExecAdapter {
                     return None;
                 }
                 debug!(logger, "exec: enqueuing job with {} steps", job.steps.len());
-                bus.action_notify_context_t(data.origin_ctx, GW2_EXEC_PROGRESS, ExecState::Queued);
+                notify_progress(&bus, &data.origin_ctx, ExecState::Queued);
                 Some(job)
             };
 
@@ -256,7 +201,21 @@ impl Adapter for Gw2ExecAdapter {
                             Ok(note) => {
                                 if let Some(t) = note.downcast(GW2_EXEC_QUEUE) {
                                     if let Some(job) = handle_enqueue(GW2_EXEC_QUEUE.name, t.clone()) {
-                                        queue.push_back(job);
+                                        match queue.back_mut() {
+                                            // A burst of dial ticks drains here before the idle
+                                            // tick runs, so they go out as one job.
+                                            Some(prev)
+                                                if job.req.coalesce
+                                                    && prev.req.coalesce
+                                                    && prev.req.origin_ctx == job.req.origin_ctx
+                                                    && prev.req.controls.len() + job.req.controls.len()
+                                                        <= MAX_COALESCED =>
+                                            {
+                                                prev.req.controls.extend(job.req.controls);
+                                                prev.steps.extend(job.steps);
+                                            }
+                                            _ => queue.push_back(job),
+                                        }
                                     }
                                 }
                             }
@@ -278,67 +237,51 @@ impl Adapter for Gw2ExecAdapter {
                             continue;
                         }
 
-                        // Drive UI animation pulse (~3.3 fps)
-                        if last_tick.elapsed() >= tick_every {
-                            last_tick = std::time::Instant::now();
-                            bus.publish_t(crate::topics::GW2_ANIMATION_TICK, ());
-                        }
-
-
-                        // Read latest airborne snapshot (produced by worker at ~25 Hz)
-                        let (_mv, in_air, landing_grace) = {
-                            let g = air_snapshot.lock().unwrap_or_else(|p| p.into_inner());
-                            (g.state, g.in_air, g.landed_recently)
-                        };
+                        // Read latest airborne snapshot (motion adapter, ~25 Hz)
+                        let air = motion.get();
+                        let in_air = air.in_air;
+                        let landing_grace = !in_air
+                            && air.landed_at.is_some_and(|t| t.elapsed() <= LANDING_GRACE);
+                        let in_water = air.water != WaterState::Dry;
 
                         // Fresh combat read
                         let in_combat = combat.in_combat_now(&logger);
 
-                        let front = queue.front().unwrap();
-
-                        let ok_combat = if in_combat {
-                            front.req.allow_in_combat
-                        } else {
-                            front.req.allow_out_of_combat
-                        };
+                        // Drop jobs whose TTL ran out while they were waiting
+                        queue.retain(|job| {
+                            let expired = job
+                                .req
+                                .ttl_ms
+                                .is_some_and(|ttl| job.queued_at.elapsed() >= Duration::from_millis(ttl));
+                            if expired {
+                                info!(logger, "exec: job for {} expired", job.req.origin_ctx);
+                                notify_progress(&bus, &job.req.origin_ctx, ExecState::Expired);
+                            }
+                            !expired
+                        });
+
+                        let mut front_reason = None;
+                        for (i, job) in queue.iter_mut().enumerate() {
+                            let gate =
+                                block_reason(&job.req, in_combat, in_air, landing_grace, in_water);
+                            if i == 0 {
+                                front_reason = gate;
+                            }
+                            // Jobs behind a blocked front wait for it even if they could run.
+                            let Some(reason) = gate.or((i > 0).then_some(BlockReason::Queue)) else {
+                                continue;
+                            };
+                            notify_waiting(&bus, job, reason);
+                        }
 
-                        // Block when airborne OR in landing grace, unless explicitly allowed.
-                        let air_blocked = in_air || landing_grace;
-                        let ok_air = if air_blocked {
-                            front.req.allow_gliding_or_falling
-                        } else {
-                            true
-                        };
-
-                        let can_run_now = ok_combat && ok_air;
-
-                        // debug!(
-                        //     logger,
-                        //     "exec: queue={}, allow_in_combat={}, allow_out_of_combat={}, allow_glide={}, combat={}, mv={:?}, in_air={}, landed_recently={}, run={}",
-                        //     queue.len(),
-                        //     front.req.allow_in_combat,
-                        //     front.req.allow_out_of_combat,
-                        //     front.req.allow_gliding_or_falling,
-                        //     combat.last_state,
-                        //     mv,
-                        //     in_air,
-                        //     landing_grace,
-                        //     can_run_now
-                        // );
-
-
-                        if !can_run_now {
+                        if front_reason.is_some() {
                             // stay queued; we'll re-check next loop
                             continue;
                         }
 
                         // Pop and run this job to completion
                         let job = queue.pop_front().unwrap();
-                        bus.action_notify_context_t(
-                            job.req.origin_ctx.clone(),
-                            GW2_EXEC_PROGRESS,
-                            ExecState::Started,
-                        );
+                        notify_progress(&bus, &job.req.origin_ctx, ExecState::Started);
 
                         for step in &job.steps {
                             if let Err(e) = synth.send_step(step) {
@@ -346,11 +289,7 @@ impl Adapter for Gw2ExecAdapter {
                             }
                         }
 
-                        bus.action_notify_context_t(
-                            job.req.origin_ctx.clone(),
-                            GW2_EXEC_PROGRESS,
-                            ExecState::Done,
-                        );
+                        notify_progress(&bus, &job.req.origin_ctx, ExecState::Done);
                     }
                 }
             }
@@ -361,3 +300,73 @@ impl Adapter for Gw2ExecAdapter {
         Ok(AdapterHandle::from_crossbeam(join, stop_tx))
     }
 }
+
+/// Why `req` can't run right now, or None when every gate is open.
+fn block_reason(
+    req: &Gw2ExecQueue,
+    in_combat: bool,
+    in_air: bool,
+    landing_grace: bool,
+    in_water: bool,
+) -> Option<BlockReason> {
+    if in_combat && !req.allow_in_combat {
+        return Some(BlockReason::Combat);
+    }
+    if !in_combat && !req.allow_out_of_combat {
+        return Some(BlockReason::OutOfCombat);
+    }
+    // Block when airborne OR in landing grace, unless explicitly allowed.
+    if !req.allow_gliding_or_falling {
+        if in_air {
+            return Some(BlockReason::Airborne);
+        }
+        if landing_grace {
+            return Some(BlockReason::LandingGrace);
+        }
+    }
+    if in_water && !req.allow_in_water {
+        return Some(BlockReason::InWater);
+    }
+    None
+}
+
+/// Tell the originating key, and broadcast it for the local API.
+fn notify_progress(bus: &Arc<dyn Bus>, origin_ctx: &str, state: ExecState) {
+    bus.action_notify_context_t(origin_ctx.to_string(), GW2_EXEC_PROGRESS, state);
+    bus.publish_t(
+        GW2_EXEC_ACTIVITY,
+        ExecActivity {
+            origin_ctx: origin_ctx.to_string(),
+            state,
+        },
+    );
+}
+
+/// Tells the job's key how long it has been waiting; at most once per displayed second.
+fn notify_waiting(bus: &Arc<dyn Bus>, job: &mut Job, reason: BlockReason) {
+    let waited = job.queued_at.elapsed();
+    let remaining = job
+        .req
+        .ttl_ms
+        .map(|ttl| Duration::from_millis(ttl).saturating_sub(waited));
+
+    let key = (
+        reason,
+        waited.as_secs(),
+        remaining.map(|r| r.as_secs_f32().ceil() as u64),
+    );
+    if job.last_waiting == Some(key) {
+        return;
+    }
+    job.last_waiting = Some(key);
+
+    bus.action_notify_context_t(
+        job.req.origin_ctx.clone(),
+        GW2_EXEC_WAITING,
+        Gw2ExecWaiting {
+            reason,
+            waited_ms: waited.as_millis() as u64,
+            remaining_ms: remaining.map(|r| r.as_millis() as u64),
+        },
+    );
+}
@@ -1,28 +1,53 @@
 use crossbeam_channel::{Receiver as CbReceiver, bounded, select};
 use reqwest::blocking::Client;
-use std::{collections::HashMap, sync::Arc, thread, time::Duration};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
 use streamdeck_lib::prelude::*;
 
 use crate::{
     gw2::{
-        enums::{CharacterData, TemplateNames},
+        enums::{ActiveTabs, CharacterData, TemplateNames},
         shared::TemplateStore,
     },
     topics::{
         GW2_API_CHARACTER_CHANGED, GW2_API_FETCHED, GW2_API_GET_CHARACTERS,
-        GW2_API_TEMPLATE_CHANGED, Gw2ApiCharacterChanged, Gw2ApiFetched, Gw2ApiTemplateChanged,
+        GW2_API_TEMPLATE_CHANGED, GW2_API_VERIFY_RESULT, GW2_API_VERIFY_TEMPLATE,
+        GW2_TEMPLATE_ACTIVE_CHANGED, Gw2ApiCharacterChanged, Gw2ApiFetched, Gw2ApiTemplateChanged,
+        Gw2ApiVerifyTemplate, Gw2TemplateActiveChanged, VerifyOutcome,
     },
 };
 
 const SCHEMA_VERSION: &str = "2024-07-20T01:00:00.000Z";
 
+const REFRESH_EVERY: Duration = Duration::from_secs(60);
+
+/// Delay before the first verification fetch; doubled after every miss.
+/// The API caches character data for up to ~5 minutes, so 6 attempts span ~5 min.
+const VERIFY_FIRST_DELAY: Duration = Duration::from_secs(5);
+const VERIFY_MAX_ATTEMPTS: u32 = 6;
+
+struct PendingVerify {
+    req: Gw2ApiVerifyTemplate,
+    /// Responses to requests sent before this say nothing about the swap.
+    queued_at: Instant,
+    attempt: u32,
+    due: Instant,
+}
+
 /// Publishes:
 /// - "gw2-api.fetched"            -> { total, added, removed, changed }
 /// - "gw2-api.character-changed"  -> { name, change: "added" | "removed" }
 /// - "gw2-api.template-changed"   -> { name, before: TemplateNames, after: TemplateNames }
+/// - "gw2.template.active-changed" -> { name, active: ActiveTabs } when the API reports a new active tab
 ///
 /// Listens:
 /// - "gw2.api.get_characters"     -> triggers a fetch now
+/// - "gw2-api.verify-template"    -> re-fetches with backoff until the requested tabs show up
+///   as active, then notifies the origin context with "gw2-api.verify-result"
 pub struct Gw2ApiAdapter {
     client: Client,
     // local snapshot to diff between runs
@@ -53,7 +78,7 @@ impl Adapter for Gw2ApiAdapter {
     }
 
     fn topics(&self) -> &'static [&'static str] {
-        &[GW2_API_GET_CHARACTERS.name]
+        &[GW2_API_GET_CHARACTERS.name, GW2_API_VERIFY_TEMPLATE.name]
     }
 
     fn start(
@@ -74,8 +99,11 @@ impl Adapter for Gw2ApiAdapter {
             ))?
             .clone();
 
+        let store_verify = template_store.clone();
+
         // Keep a local, mutable snapshot inside the worker thread
         let mut last_seen: HashMap<String, TemplateNames> = self.last_seen.clone();
+        let mut last_active: HashMap<String, ActiveTabs> = HashMap::new();
 
         let get_api_key = |cx: Context| {
             cx.globals()
@@ -87,12 +115,13 @@ impl Adapter for Gw2ApiAdapter {
             move |cx: &Context,
                   logger: &Arc<dyn ActionLog>,
                   bus: &Arc<dyn Bus>,
-                  last_seen: &mut HashMap<String, TemplateNames>| {
+                  last_seen: &mut HashMap<String, TemplateNames>,
+                  last_active: &mut HashMap<String, ActiveTabs>| {
                 let api_key = match get_api_key(cx.clone()) {
                     Some(key) => key,
                     None => {
                         warn!(logger, "API key not found in globals");
-                        return;
+                        return false;
                     }
                 };
 
@@ -100,7 +129,7 @@ impl Adapter for Gw2ApiAdapter {
                     Ok(chars) => chars,
                     Err(e) => {
                         error!(logger, "Failed to fetch characters: {}", e);
-                        return;
+                        return false;
                     }
                 };
 
@@ -168,7 +197,29 @@ impl Adapter for Gw2ApiAdapter {
                     }
                 }
 
-                // 2c) Replace local snapshot and emit summary.
+                // 2c) Active tabs. Only push values the API actually changed since the last
+                // fetch, so a stale response doesn't undo a swap we already applied locally.
+                let mut new_active: HashMap<String, ActiveTabs> = HashMap::new();
+                for c in &characters {
+                    let active = into_active_tabs(c);
+                    let api_changed = last_active.get(&c.name) != Some(&active);
+                    let unknown = template_store.get_active(&c.name).is_none();
+                    if (api_changed || unknown) && template_store.set_active(c.name.clone(), active)
+                    {
+                        bus.publish_t(
+                            GW2_TEMPLATE_ACTIVE_CHANGED,
+                            Gw2TemplateActiveChanged {
+                                name: c.name.clone(),
+                                active,
+                            },
+                        );
+                    }
+                    new_active.insert(c.name.clone(), active);
+                }
+                last_active.clear();
+                last_active.extend(new_active);
+
+                // 2d) Replace local snapshot and emit summary.
                 last_seen.clear();
                 last_seen.extend(new_map);
 
@@ -181,32 +232,130 @@ impl Adapter for Gw2ApiAdapter {
                         changed,
                     },
                 );
+                true
             };
 
         let bus_clone = bus.clone();
         let join = thread::spawn(move || {
             info!(logger, "GW2 API adapter started");
+            let mut pending: Vec<PendingVerify> = Vec::new();
+
+            // Settle verifications against what the API reported on the last fetch,
+            // which was sent at `fetched_at`.
+            let check_pending = |pending: &mut Vec<PendingVerify>,
+                                 last_active: &HashMap<String, ActiveTabs>,
+                                 fetched_at: Instant,
+                                 attempted: bool| {
+                let now = Instant::now();
+                pending.retain_mut(|p| {
+                    if fetched_at < p.queued_at {
+                        return true;
+                    }
+                    let actual = last_active.get(&p.req.name).copied();
+                    if actual.is_some_and(|a| tabs_match(&p.req.expected, &a)) {
+                        debug!(
+                            logger,
+                            "verify: {} confirmed {:?}", p.req.name, p.req.expected
+                        );
+                        bus_clone.action_notify_context_t(
+                            p.req.origin_ctx.clone(),
+                            GW2_API_VERIFY_RESULT,
+                            VerifyOutcome::Verified,
+                        );
+                        return false;
+                    }
+                    if !attempted || p.due > now {
+                        return true;
+                    }
+
+                    p.attempt += 1;
+                    if p.attempt < VERIFY_MAX_ATTEMPTS {
+                        p.due = now + VERIFY_FIRST_DELAY * 2u32.pow(p.attempt);
+                        return true;
+                    }
+
+                    warn!(
+                        logger,
+                        "verify: {} expected {:?}, API reports {:?}",
+                        p.req.name,
+                        p.req.expected,
+                        actual
+                    );
+                    // The optimistic local value was wrong; fall back to what the API says.
+                    if let Some(a) = actual {
+                        if store_verify.set_active(p.req.name.clone(), a) {
+                            bus_clone.publish_t(
+                                GW2_TEMPLATE_ACTIVE_CHANGED,
+                                Gw2TemplateActiveChanged {
+                                    name: p.req.name.clone(),
+                                    active: a,
+                                },
+                            );
+                        }
+                    }
+                    bus_clone.action_notify_context_t(
+                        p.req.origin_ctx.clone(),
+                        GW2_API_VERIFY_RESULT,
+                        VerifyOutcome::Mismatch {
+                            expected: p.req.expected,
+                            actual,
+                        },
+                    );
+                    false
+                });
+            };
 
             // ✅ Immediate fetch on start
             debug!(logger, "Initial fetch on start");
-            fetch_apply_and_emit(&cx, &logger, &bus_clone, &mut last_seen);
+            fetch_apply_and_emit(&cx, &logger, &bus_clone, &mut last_seen, &mut last_active);
+            let mut next_refresh = Instant::now() + REFRESH_EVERY;
             loop {
+                let next_due = pending
+                    .iter()
+                    .map(|p| p.due)
+                    .min()
+                    .map_or(next_refresh, |d| d.min(next_refresh));
+                let timeout = next_due.saturating_duration_since(Instant::now());
+
                 select! {
                     recv(inbox) -> msg => {
                         match msg {
                             Ok(note) => {
                                 if note.downcast(GW2_API_GET_CHARACTERS).is_some() {
                                     debug!(logger, "Trigger fetch: gw2.api.get_characters");
-                                    fetch_apply_and_emit(&cx, &logger, &bus_clone, &mut last_seen);
+                                    let fetched_at = Instant::now();
+                                    if fetch_apply_and_emit(&cx, &logger, &bus_clone, &mut last_seen, &mut last_active) {
+                                        check_pending(&mut pending, &last_active, fetched_at, false);
+                                    }
+                                    next_refresh = Instant::now() + REFRESH_EVERY;
+                                }
+                                if let Some(req) = note.downcast(GW2_API_VERIFY_TEMPLATE) {
+                                    debug!(logger, "verify: queued {:?}", req);
+                                    pending.retain(|p| p.req.origin_ctx != req.origin_ctx);
+                                    pending.push(PendingVerify {
+                                        req: req.clone(),
+                                        queued_at: Instant::now(),
+                                        attempt: 0,
+                                        due: Instant::now() + VERIFY_FIRST_DELAY,
+                                    });
                                 }
                             },
                             Err(e) => error!(logger, "Error receiving message: {}", e),
                         }
                     },
                     recv(stop_rx) -> _ => break,
-                    default(Duration::from_secs(60)) => {
-                        // periodic refresh
-                        fetch_apply_and_emit(&cx, &logger, &bus_clone, &mut last_seen);
+                    default(timeout) => {
+                        // periodic refresh, or a verification came due
+                        let fetched_at = Instant::now();
+                        if fetch_apply_and_emit(&cx, &logger, &bus_clone, &mut last_seen, &mut last_active) {
+                            check_pending(&mut pending, &last_active, fetched_at, true);
+                        } else {
+                            // nothing learned: retry the due ones later without using up an attempt
+                            for p in pending.iter_mut().filter(|p| p.due <= fetched_at) {
+                                p.due = fetched_at + VERIFY_FIRST_DELAY * 2u32.pow(p.attempt);
+                            }
+                        }
+                        next_refresh = Instant::now() + REFRESH_EVERY;
                     }
                 }
             }
@@ -247,3 +396,26 @@ fn into_template_names(c: &CharacterData) -> TemplateNames {
     }
     t
 }
+
+/// `expected` sides that are `None` match anything.
+fn tabs_match(expected: &ActiveTabs, actual: &ActiveTabs) -> bool {
+    expected.build.is_none_or(|b| actual.build == Some(b))
+        && expected
+            .equipment
+            .is_none_or(|e| actual.equipment == Some(e))
+}
+
+fn into_active_tabs(c: &CharacterData) -> ActiveTabs {
+    ActiveTabs {
+        build: c
+            .build_tabs
+            .iter()
+            .find(|bt| bt.is_active && (1..=9).contains(&bt.tab_index))
+            .map(|bt| bt.tab_index),
+        equipment: c
+            .equipment_tabs
+            .iter()
+            .find(|et| et.is_active && (1..=9).contains(&et.tab_index))
+            .map(|et| et.tab_index),
+    }
+}
@@ -0,0 +1,319 @@
+// src/gw2/markers.rs
+//! Reading TacO / Blish HUD marker packs: categories and POIs, from loose
+//! `.xml` files or zipped `.taco`/`.zip` packs.
+//!
+//! Only what the plugin points at is kept: each POI's category, map and
+//! position (`xpos`/`ypos`/`zpos`, Mumble coordinates, `ypos` is height).
+//! Trails, textures and behaviors are ignored.
+//!
+//! ```xml
+//! <OverlayData>
+//!   <MarkerCategory name="tekkit" DisplayName="Tekkit's">
+//!     <MarkerCategory name="chests" DisplayName="Chests"/>
+//!   </MarkerCategory>
+//!   <POIs>
+//!     <POI MapID="15" xpos="-30.1" ypos="22.4" zpos="100.7" type="tekkit.chests"/>
+//!   </POIs>
+//! </OverlayData>
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{Read, Seek},
+    path::Path,
+};
+
+use roxmltree::{Document, Node};
+
+use crate::gw2::positions::horizontal_distance;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Poi {
+    /// Dotted category path, e.g. `tekkit.chests`.
+    pub category: String,
+    pub map_id: u32,
+    pub pos: [f32; 3],
+}
+
+/// Everything loaded from a marker folder.
+#[derive(Debug, Clone, Default)]
+pub struct MarkerSet {
+    /// Display name per dotted category path.
+    pub categories: BTreeMap<String, String>,
+    pub pois: Vec<Poi>,
+}
+
+impl MarkerSet {
+    /// Category paths (sorted) that have POIs on `map_id`.
+    pub fn categories_on(&self, map_id: u32) -> Vec<&str> {
+        let mut cats: Vec<&str> = self
+            .pois
+            .iter()
+            .filter(|p| p.map_id == map_id)
+            .map(|p| p.category.as_str())
+            .collect();
+        cats.sort_unstable();
+        cats.dedup();
+        cats
+    }
+
+    /// Display name of a category, falling back to the last path segment.
+    pub fn display_name<'a>(&'a self, category: &'a str) -> &'a str {
+        self.categories
+            .get(category)
+            .map(String::as_str)
+            .unwrap_or_else(|| category.rsplit('.').next().unwrap_or(category))
+    }
+
+    /// Closest POI on `map_id` (horizontal distance, game units), optionally
+    /// limited to `category` and its subcategories.
+    pub fn nearest(
+        &self,
+        map_id: u32,
+        pos: [f32; 3],
+        category: Option<&str>,
+    ) -> Option<(&Poi, f32)> {
+        self.pois
+            .iter()
+            .filter(|p| p.map_id == map_id)
+            .filter(|p| category.is_none_or(|c| in_category(&p.category, c)))
+            .map(|p| (p, horizontal_distance(pos, p.pos)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    fn merge(&mut self, other: MarkerSet) {
+        self.categories.extend(other.categories);
+        self.pois.extend(other.pois);
+    }
+}
+
+/// `cat` is `parent` or one of its subcategories.
+fn in_category(cat: &str, parent: &str) -> bool {
+    cat.eq_ignore_ascii_case(parent)
+        || (cat.len() > parent.len()
+            && cat.as_bytes()[parent.len()] == b'.'
+            && cat[..parent.len()].eq_ignore_ascii_case(parent))
+}
+
+/// Load every pack in `dir` (loose XML in any subfolder, `.taco`/`.zip`
+/// archives). Broken files are reported and skipped.
+pub fn load_dir(dir: &Path) -> (MarkerSet, Vec<String>) {
+    let mut set = MarkerSet::default();
+    let mut errors = Vec::new();
+    load_dir_into(dir, &mut set, &mut errors);
+    (set, errors)
+}
+
+fn load_dir_into(dir: &Path, set: &mut MarkerSet, errors: &mut Vec<String>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(e) => {
+            errors.push(format!("{}: {e}", dir.display()));
+            return;
+        }
+    };
+    let mut paths: Vec<_> = entries.flatten().map(|e| e.path()).collect();
+    paths.sort();
+
+    for path in paths {
+        if path.is_dir() {
+            load_dir_into(&path, set, errors);
+            continue;
+        }
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        let loaded = match ext.as_deref() {
+            Some("xml") => fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|text| parse_overlay_xml(&text)),
+            Some("taco" | "zip") => File::open(&path)
+                .map_err(|e| e.to_string())
+                .and_then(load_zip),
+            _ => continue,
+        };
+        match loaded {
+            Ok(pack) => set.merge(pack),
+            Err(e) => errors.push(format!("{}: {e}", path.display())),
+        }
+    }
+}
+
+/// All XML files inside a zipped pack.
+fn load_zip(reader: impl Read + Seek) -> Result<MarkerSet, String> {
+    let mut archive = zip::ZipArchive::new(reader).map_err(|e| e.to_string())?;
+    let mut set = MarkerSet::default();
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(|e| e.to_string())?;
+        if entry.is_dir() || !entry.name().to_ascii_lowercase().ends_with(".xml") {
+            continue;
+        }
+        let name = entry.name().to_string();
+        let mut text = String::new();
+        entry
+            .read_to_string(&mut text)
+            .map_err(|e| format!("{name}: {e}"))?;
+        set.merge(parse_overlay_xml(&text).map_err(|e| format!("{name}: {e}"))?);
+    }
+    Ok(set)
+}
+
+/// Parse one `OverlayData` document.
+pub fn parse_overlay_xml(text: &str) -> Result<MarkerSet, String> {
+    // some packs start with a BOM
+    let doc = Document::parse(text.trim_start_matches('\u{feff}')).map_err(|e| e.to_string())?;
+    let root = doc.root_element();
+    if !root.tag_name().name().eq_ignore_ascii_case("OverlayData") {
+        return Err(format!("unexpected root <{}>", root.tag_name().name()));
+    }
+
+    let mut set = MarkerSet::default();
+    for node in root.children().filter(Node::is_element) {
+        let tag = node.tag_name().name();
+        if tag.eq_ignore_ascii_case("MarkerCategory") {
+            collect_categories(node, "", &mut set.categories);
+        } else if tag.eq_ignore_ascii_case("POIs") {
+            for poi in node.children().filter(|n| is_tag(n, "POI")) {
+                if let Some(p) = read_poi(poi) {
+                    set.pois.push(p);
+                }
+            }
+        }
+    }
+    Ok(set)
+}
+
+fn collect_categories(node: Node, parent: &str, out: &mut BTreeMap<String, String>) {
+    let Some(name) = attr(node, "name") else {
+        return;
+    };
+    let path = if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{parent}.{name}")
+    };
+    let display = attr(node, "DisplayName").unwrap_or(name).to_string();
+    for child in node.children().filter(|n| is_tag(n, "MarkerCategory")) {
+        collect_categories(child, &path, out);
+    }
+    out.insert(path, display);
+}
+
+/// POIs without a map or position can't be pointed at; skip them.
+fn read_poi(node: Node) -> Option<Poi> {
+    let num = |name| attr(node, name)?.trim().parse::<f32>().ok();
+    Some(Poi {
+        category: attr(node, "type").unwrap_or_default().to_string(),
+        map_id: attr(node, "MapID")?.trim().parse().ok()?,
+        pos: [num("xpos")?, num("ypos")?, num("zpos")?],
+    })
+}
+
+fn is_tag(node: &Node, name: &str) -> bool {
+    node.is_element() && node.tag_name().name().eq_ignore_ascii_case(name)
+}
+
+/// TacO treats attribute names case-insensitively; packs rely on it.
+fn attr<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.attributes()
+        .find(|a| a.name().eq_ignore_ascii_case(name))
+        .map(|a| a.value())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use zip::{ZipWriter, write::SimpleFileOptions};
+
+    use super::*;
+
+    const PACK: &str = r#"<OverlayData>
+  <MarkerCategory name="tekkit" DisplayName="Tekkit's">
+    <MarkerCategory name="chests" DisplayName="Chests">
+      <MarkerCategory name="hidden"/>
+    </MarkerCategory>
+  </MarkerCategory>
+  <POIs>
+    <POI MapID="15" xpos="-30.1" ypos="22.4" zpos="100.7" type="tekkit.chests"/>
+  </POIs>
+</OverlayData>"#;
+
+    #[test]
+    fn nested_categories() {
+        let set = parse_overlay_xml(PACK).unwrap();
+        let cats: Vec<(&str, &str)> = set
+            .categories
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+        assert_eq!(
+            cats,
+            [
+                ("tekkit", "Tekkit's"),
+                ("tekkit.chests", "Chests"),
+                ("tekkit.chests.hidden", "hidden"),
+            ]
+        );
+        assert_eq!(
+            set.pois,
+            [Poi {
+                category: "tekkit.chests".into(),
+                map_id: 15,
+                pos: [-30.1, 22.4, 100.7],
+            }]
+        );
+    }
+
+    #[test]
+    fn case_insensitive_with_bom() {
+        let xml = "\u{feff}<overlaydata>\
+            <markercategory Name=\"jp\" displayname=\"Jumping Puzzles\"/>\
+            <pois><poi mapid=\"28\" XPOS=\"1\" YPos=\"2\" zPos=\"3\" TYPE=\"jp\"/></pois>\
+            </overlaydata>";
+        let set = parse_overlay_xml(xml).unwrap();
+        assert_eq!(set.categories["jp"], "Jumping Puzzles");
+        assert_eq!(set.pois.len(), 1);
+        assert_eq!(set.pois[0].map_id, 28);
+        assert_eq!(set.pois[0].pos, [1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn skips_pois_without_map_or_position() {
+        let xml = r#"<OverlayData><POIs>
+            <POI xpos="1" ypos="2" zpos="3" type="a"/>
+            <POI MapID="15" xpos="1" zpos="3" type="b"/>
+            <POI MapID="15" xpos="1" ypos="two" zpos="3" type="c"/>
+            <POI MapID="15" xpos="1" ypos="2" zpos="3" type="d"/>
+        </POIs></OverlayData>"#;
+        let set = parse_overlay_xml(xml).unwrap();
+        let types: Vec<&str> = set.pois.iter().map(|p| p.category.as_str()).collect();
+        assert_eq!(types, ["d"]);
+    }
+
+    #[test]
+    fn rejects_other_documents() {
+        assert!(parse_overlay_xml("<Trails/>").is_err());
+        assert!(parse_overlay_xml("<OverlayData>").is_err());
+    }
+
+    #[test]
+    fn zipped_pack() {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        zip.add_directory("Data/", options).unwrap();
+        zip.start_file("Data/chests.XML", options).unwrap();
+        zip.write_all(PACK.as_bytes()).unwrap();
+        zip.start_file("Data/chest.png", options).unwrap();
+        zip.write_all(b"not xml").unwrap();
+        let mut file = zip.finish().unwrap();
+        file.set_position(0);
+
+        let set = load_zip(file).unwrap();
+        assert_eq!(set.categories.len(), 3);
+        assert_eq!(set.pois.len(), 1);
+        assert_eq!(set.pois[0].category, "tekkit.chests");
+    }
+}
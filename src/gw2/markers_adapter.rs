@@ -0,0 +1,106 @@
+// src/gw2/markers_adapter.rs
+use crossbeam_channel::{Receiver as CbReceiver, bounded, select};
+use std::{path::PathBuf, sync::Arc, thread};
+use streamdeck_lib::prelude::*;
+
+use crate::{
+    gw2::{markers::load_dir, shared::SharedMarkers},
+    topics::{MARKERS_DIR_SET, MARKERS_UPDATED},
+};
+
+/// Loads TacO / Blish HUD marker packs into `SharedMarkers`.
+///
+/// Publishes:
+/// - "markers.updated" -> POI count, after every (re)load
+///
+/// Listens:
+/// - "markers-dir.set" -> folder to load packs from
+pub struct MarkersAdapter;
+
+impl MarkersAdapter {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Adapter for MarkersAdapter {
+    fn name(&self) -> &'static str {
+        "gw2.markers_loader"
+    }
+
+    fn policy(&self) -> StartPolicy {
+        StartPolicy::OnAppLaunch
+    }
+
+    fn topics(&self) -> &'static [&'static str] {
+        &[MARKERS_DIR_SET.name]
+    }
+
+    fn start(
+        &self,
+        cx: &Context,
+        bus: Arc<dyn Bus>,
+        inbox: CbReceiver<Arc<ErasedTopic>>,
+    ) -> AdapterResult {
+        let (stop_tx, stop_rx) = bounded::<()>(1);
+
+        let initial_dir: Option<PathBuf> = cx
+            .globals()
+            .snapshot()
+            .get("markers_dir")
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.trim().is_empty())
+            .map(PathBuf::from);
+
+        let markers = cx.try_ext::<SharedMarkers>().ok_or(AdapterError::Init(
+            "SharedMarkers extension not found".into(),
+        ))?;
+
+        let join = thread::spawn(move || {
+            // Big packs take a moment; this runs on the adapter thread, not the plugin's.
+            let load = |dir: &PathBuf| {
+                bus.log(
+                    &format!("Loading marker packs from {}", dir.display()),
+                    Level::Info,
+                );
+                let (set, errors) = load_dir(dir);
+                for e in errors {
+                    bus.log(&format!("marker pack: {e}"), Level::Warn);
+                }
+                let count = set.pois.len();
+                bus.log(
+                    &format!(
+                        "Loaded {count} POIs in {} categories.",
+                        set.categories.len()
+                    ),
+                    Level::Info,
+                );
+                markers.replace(set);
+                bus.publish_t(MARKERS_UPDATED, count);
+            };
+
+            if let Some(dir) = &initial_dir {
+                load(dir);
+            }
+
+            loop {
+                select! {
+                    recv(inbox) -> msg => {
+                        let Ok(note) = msg else {
+                            break; // inbox closed
+                        };
+                        if let Some(dir) = note.downcast(MARKERS_DIR_SET) {
+                            load(&PathBuf::from(dir));
+                        }
+                    }
+                    recv(stop_rx) -> _ => {
+                        bus.log("Stopping marker loader...", Level::Debug);
+                        break;
+                    }
+                }
+            }
+        });
+
+        Ok(AdapterHandle::from_crossbeam(join, stop_tx))
+    }
+}
@@ -0,0 +1,200 @@
+#![cfg(windows)]
+
+use std::sync::Arc;
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::{Receiver as CbReceiver, bounded, select, tick};
+
+use streamdeck_lib::prelude::*;
+
+use crate::gw2::airborne::{AirClassifier, MotionSource, MoveEvent, classify::Conditions};
+use crate::gw2::enums::{FallRisk, InstanceId};
+use crate::gw2::mumble::MumbleLink;
+use crate::gw2::shared::{
+    AvatarPosition, CurrentMount, MotionSnapshot, SharedClassifier, SharedMotion, SharedUiState,
+};
+use crate::topics::{FallAlert, MOTION_EVENT, MOTION_FALL, MotionEvent};
+
+/// Mumble updates at ~25 Hz; sampling faster only repeats frames.
+const SAMPLE_EVERY: Duration = Duration::from_millis(40);
+
+/// Runs the airborne classifier on Mumble position samples and keeps the
+/// latest result, along with the raw avatar position, in the `SharedMotion`
+/// extension.
+///
+/// The classifier profile follows the current mount and combat state, which
+/// the Mumble adapter keeps in `CurrentMount` / `SharedUiState`.
+///
+/// Consumers (exec gating, speedometer, …) read the snapshot; nothing is
+/// published on the bus at sample rate. Only changes in fall risk are
+/// broadcast ("motion.fall"), since those need a prompt reaction, along with
+/// teleports, map/instance transitions, jumps, landings and dodges
+/// ("motion.event").
+pub struct MotionAdapter;
+
+impl MotionAdapter {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Adapter for MotionAdapter {
+    fn name(&self) -> &'static str {
+        "gw2.motion_adapter"
+    }
+
+    fn policy(&self) -> StartPolicy {
+        StartPolicy::OnAppLaunch
+    }
+
+    fn topics(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    fn start(
+        &self,
+        cx: &Context,
+        bus: Arc<dyn Bus>,
+        inbox: CbReceiver<Arc<ErasedTopic>>,
+    ) -> AdapterResult {
+        let (stop_tx, stop_rx) = bounded::<()>(1);
+        let logger = cx.log().clone();
+        let motion = cx.try_ext::<SharedMotion>().ok_or(AdapterError::Init(
+            "SharedMotion extension not found".into(),
+        ))?;
+        let classifier = cx.try_ext::<SharedClassifier>().ok_or(AdapterError::Init(
+            "SharedClassifier extension not found".into(),
+        ))?;
+        let mount = cx.try_ext::<CurrentMount>();
+        let ui_state = cx.try_ext::<SharedUiState>();
+
+        let join = thread::spawn(move || {
+            let ticker = tick(SAMPLE_EVERY);
+            let mut air = AirClassifier::new(Instant::now());
+            let mut link: Option<MumbleLink> = None;
+            let mut retry_at = Instant::now();
+            let mut last_risk = FallRisk::Safe;
+            let mut last_map: Option<u32> = None;
+            let mut last_instance: Option<InstanceId> = None;
+
+            info!(logger, "motion adapter started");
+
+            loop {
+                select! {
+                    recv(inbox) -> msg => {
+                        if msg.is_err() {
+                            break; // inbox closed
+                        }
+                    }
+
+                    recv(stop_rx) -> _ => {
+                        debug!(logger, "Stopping motion adapter...");
+                        break;
+                    }
+
+                    recv(ticker) -> _ => {
+                        // ensure link
+                        if link.is_none() {
+                            if Instant::now() < retry_at {
+                                continue;
+                            }
+                            match MumbleLink::new() {
+                                Ok(l) => {
+                                    info!(logger, "motion: MumbleLink mapped");
+                                    link = Some(l);
+                                }
+                                Err(e) => {
+                                    // back off a bit if mapping fails
+                                    warn!(logger, "motion: map failed: {e}");
+                                    retry_at = Instant::now() + Duration::from_secs(2);
+                                    continue;
+                                }
+                            }
+                        }
+
+                        if let Some(ref l) = link {
+                            // map_id 0: character select / loading screen
+                            let ctx = l.read_context().filter(|c| c.map_id() != 0);
+                            if let Some(ctx) = ctx {
+                                let (map_id, instance) = (ctx.map_id(), ctx.instance());
+                                let event = if last_map != Some(map_id) {
+                                    Some(MotionEvent::MapChanged { from: last_map, to: map_id })
+                                } else if last_instance != Some(instance) {
+                                    Some(MotionEvent::InstanceChanged { map_id, instance })
+                                } else {
+                                    None
+                                };
+                                last_map = Some(map_id);
+                                last_instance = Some(instance);
+
+                                if let Some(event) = event {
+                                    debug!(logger, "motion: {:?}", event);
+                                    air.reset(Instant::now());
+                                    bus.publish_t(MOTION_EVENT, event);
+                                }
+                            }
+
+                            let cond = Conditions {
+                                in_combat: ui_state.as_ref().is_some_and(|u| u.get().is_in_combat()),
+                                mount: mount.as_ref().and_then(|m| m.get()),
+                                // water state comes from the sample itself
+                                ..Conditions::default()
+                            };
+                            let cfg = classifier.get();
+                            let sample = l.read_motion();
+                            let state = match sample {
+                                Some(sample) => air.update_sample(Instant::now(), sample, cfg.profile_for(cond.mount), cond),
+                                None => air.state(),
+                            };
+                            if let Some(distance) = air.take_teleport() {
+                                debug!(logger, "motion: teleported {:.0} units", distance);
+                                bus.publish_t(MOTION_EVENT, MotionEvent::Teleported { distance });
+                            }
+                            for ev in air.take_events() {
+                                let event = match ev {
+                                    MoveEvent::Jumped => MotionEvent::Jumped,
+                                    MoveEvent::Landed { airtime, drop } => MotionEvent::Landed {
+                                        airtime_ms: airtime.as_millis() as u64,
+                                        drop,
+                                    },
+                                    MoveEvent::Dodged => MotionEvent::Dodged,
+                                };
+                                debug!(logger, "motion: {:?}", event);
+                                bus.publish_t(MOTION_EVENT, event);
+                            }
+
+                            let fall = air.fall();
+                            motion.set(MotionSnapshot {
+                                state,
+                                position: ctx.zip(sample).map(|(c, (pos, front))| AvatarPosition {
+                                    map_id: c.map_id(),
+                                    pos,
+                                    front,
+                                }),
+                                in_air: air.is_airborne(),
+                                landed_at: air.last_landing(),
+                                speed: air.speed(),
+                                water: air.water(),
+                                fall_risk: fall.risk(),
+                                fall_drop: fall.drop(),
+                            });
+
+                            if fall.risk() != last_risk {
+                                last_risk = fall.risk();
+                                debug!(logger, "motion: fall risk {:?} after {:.0} units", last_risk, fall.drop());
+                                bus.publish_t(MOTION_FALL, FallAlert { risk: last_risk, drop: fall.drop() });
+                            }
+                        }
+                    }
+                }
+            }
+
+            info!(logger, "motion adapter stopped");
+        });
+
+        Ok(AdapterHandle::from_crossbeam(join, stop_tx))
+    }
+}
@@ -1,42 +1,12 @@
 #![cfg(windows)]
 
 use bytemuck::{Pod, Zeroable};
+use std::net::{Ipv6Addr, SocketAddr};
 use std::slice;
 use windows::Win32::Foundation::*;
 use windows::Win32::System::Memory::*;
 
-bitflags::bitflags! {
-    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-    pub struct UiState: u32 {
-        const MAP_OPEN          = 1 << 0;
-        const COMPASS_TOP_RIGHT = 1 << 1;
-        const COMPASS_ROTATION  = 1 << 2;
-        const GAME_HAS_FOCUS    = 1 << 3;
-        const COMPETITIVE_MODE  = 1 << 4;
-        const TEXTBOX_HAS_FOCUS = 1 << 5;
-        const IN_COMBAT         = 1 << 6;
-    }
-}
-impl UiState {
-    #[inline]
-    pub fn is_in_combat(self) -> bool {
-        self.contains(Self::IN_COMBAT)
-    }
-}
-
-#[derive(Debug, Clone, serde::Deserialize)]
-pub struct Identity {
-    pub name: String,
-    pub profession: Option<u8>,
-    pub spec: Option<u16>,
-    pub race: Option<u8>,
-    pub map_id: Option<u32>,
-    pub world_id: Option<u32>,
-    pub team_color_id: Option<u8>,
-    pub commander: Option<bool>,
-    pub fov: Option<f32>,
-    pub uisz: Option<u8>,
-}
+use crate::gw2::enums::{Identity, InstanceId, MapKind, Mount, UiState};
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Zeroable, Pod)]
@@ -78,6 +48,44 @@ pub struct MumbleContext {
     mount_index: u8,
     _padding: [u8; 3],
 }
+impl MumbleContext {
+    pub fn map_id(&self) -> u32 {
+        self.map_id
+    }
+    /// Shard, instance and server; changes whenever we land in another copy of a map.
+    pub fn instance(&self) -> InstanceId {
+        InstanceId {
+            shard_id: self.shard_id,
+            instance: self.instance,
+            server: self.server_addr(),
+        }
+    }
+    /// `server_address` is a raw `sockaddr_in`/`sockaddr_in6`.
+    pub fn server_addr(&self) -> Option<SocketAddr> {
+        let b = &self.server_address;
+        let family = u16::from_le_bytes([b[0], b[1]]);
+        let port = u16::from_be_bytes([b[2], b[3]]);
+        match family {
+            2 => Some(SocketAddr::from(([b[4], b[5], b[6], b[7]], port))), // AF_INET
+            23 => {
+                // AF_INET6: flowinfo (4 bytes), then the address
+                let mut octets = [0u8; 16];
+                octets.copy_from_slice(&b[8..24]);
+                Some(SocketAddr::from((Ipv6Addr::from(octets), port)))
+            }
+            _ => None,
+        }
+    }
+    pub fn map_kind(&self) -> MapKind {
+        MapKind::from_mumble(self.map_type)
+    }
+    pub fn mount(&self) -> Option<Mount> {
+        Mount::from_mumble(self.mount_index)
+    }
+    pub fn ui_state(&self) -> UiState {
+        UiState::from_bits_truncate(self.ui_state)
+    }
+}
 
 const SHARED_MEM_SIZE: usize = std::mem::size_of::<LinkedMem>();
 
@@ -118,6 +126,16 @@ impl MumbleLink {
         }
     }
 
+    /// Just the game context block (map, mount, ui state, …).
+    pub fn read_context(&self) -> Option<MumbleContext> {
+        let lm = self.read_linked_mem()?;
+        bytemuck::try_from_bytes::<MumbleContext>(
+            &lm.context[..std::mem::size_of::<MumbleContext>()],
+        )
+        .ok()
+        .copied()
+    }
+
     pub fn read_linked_mem(&self) -> Option<LinkedMem> {
         unsafe {
             let bytes = slice::from_raw_parts(self.view_ptr.Value as *const u8, SHARED_MEM_SIZE);
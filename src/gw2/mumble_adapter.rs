@@ -1,19 +1,35 @@
 #![cfg(windows)]
 
 use std::sync::Arc;
-use std::{thread, time::Duration};
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
 
 use crossbeam_channel::{Receiver as CbReceiver, bounded, select, tick};
 
 use streamdeck_lib::prelude::*;
 
+use crate::gw2::enums::{Identity, MapKind, Mount, UiState};
 use crate::gw2::mumble::MumbleLink;
-use crate::gw2::shared::ActiveChar;
-use crate::topics::MUMBLE_ACTIVE_CHARACTER;
+use crate::gw2::shared::{ActiveChar, CurrentMount, SharedIdentity, SharedUiState};
+use crate::topics::{
+    MUMBLE_ACTIVE_CHARACTER, MUMBLE_IDENTITY, MUMBLE_IN_GAME, MUMBLE_MAP_KIND, MUMBLE_MOUNT,
+    MUMBLE_UI_STATE,
+};
+
+/// The game bumps `ui_tick` every frame while in a map; this long without a
+/// bump is character select (or a long loading screen).
+const IN_GAME_STALE: Duration = Duration::from_secs(5);
 
 /// Publishes:
 /// - "mumble.combat"           -> bool
 /// - "mumble.active-character" -> String  (only emitted in SLOW mode)
+/// - "mumble.identity"         -> Option<Identity> whenever the parsed identity changes
+/// - "mumble.mount"            -> Option<Mount> on change (context polled every 200ms)
+/// - "mumble.ui-state"         -> UiState on change (same poll)
+/// - "mumble.map-kind"         -> MapKind (PvE/PvP/WvW) on change (same poll)
+/// - "mumble.in-game"          -> bool on change, false at character select (same poll)
 ///
 /// Listens:
 /// - "mumble.fast"             -> ~16ms polling, combat only
@@ -51,15 +67,42 @@ impl Adapter for MumbleAdapter {
             .try_ext::<ActiveChar>()
             .ok_or(AdapterError::Init("ActiveChar extension not found".into()))?
             .clone();
+        let identity_ext = cx
+            .try_ext::<SharedIdentity>()
+            .ok_or(AdapterError::Init(
+                "SharedIdentity extension not found".into(),
+            ))?
+            .clone();
+        let mount_ext = cx
+            .try_ext::<CurrentMount>()
+            .ok_or(AdapterError::Init(
+                "CurrentMount extension not found".into(),
+            ))?
+            .clone();
+
+        let ui_ext = cx
+            .try_ext::<SharedUiState>()
+            .ok_or(AdapterError::Init(
+                "SharedUiState extension not found".into(),
+            ))?
+            .clone();
 
         let join = thread::spawn(move || {
             // Tickers
             let ticker = tick(Duration::from_secs(10));
+            let context_ticker = tick(Duration::from_millis(200));
             // mapping
             let mut link: Option<MumbleLink> = None;
 
             // de-dupe
             let mut last_name: Option<String> = None;
+            let mut last_identity: Option<Identity> = None;
+            let mut last_mount: Option<Mount> = None;
+            let mut last_ui: Option<UiState> = None;
+            let mut last_map_kind: Option<MapKind> = None;
+            let mut last_in_game: Option<bool> = None;
+            let mut last_tick: Option<u32> = None;
+            let mut tick_changed_at = Instant::now();
 
             info!(logger, "🎧 Mumble adapter started (slow)");
 
@@ -77,6 +120,45 @@ impl Adapter for MumbleAdapter {
                         break;
                     }
 
+                    recv(context_ticker) -> _ => {
+                        // Mapping is (re)established by the slow ticker below.
+                        let Some(l) = link.as_ref() else {
+                            continue;
+                        };
+                        if let Some((_, tick)) = l.read_ui() {
+                            let now = Instant::now();
+                            if last_tick != Some(tick) {
+                                last_tick = Some(tick);
+                                tick_changed_at = now;
+                            }
+                            let in_game = now.duration_since(tick_changed_at) < IN_GAME_STALE;
+                            if last_in_game != Some(in_game) {
+                                last_in_game = Some(in_game);
+                                bus.publish_t(MUMBLE_IN_GAME, in_game);
+                            }
+                        }
+                        let Some(ctx) = l.read_context() else {
+                            continue;
+                        };
+                        let map_kind = ctx.map_kind();
+                        if last_map_kind != Some(map_kind) {
+                            last_map_kind = Some(map_kind);
+                            bus.publish_t(MUMBLE_MAP_KIND, map_kind);
+                        }
+                        let mount = ctx.mount();
+                        if mount != last_mount {
+                            last_mount = mount;
+                            mount_ext.set(mount);
+                            bus.publish_t(MUMBLE_MOUNT, mount);
+                        }
+                        let ui = ctx.ui_state();
+                        if last_ui != Some(ui) {
+                            last_ui = Some(ui);
+                            ui_ext.set(ui);
+                            bus.publish_t(MUMBLE_UI_STATE, ui);
+                        }
+                    }
+
                     recv(ticker) -> _ => {
                         // Ensure mapping
                         if link.is_none() {
@@ -85,6 +167,7 @@ impl Adapter for MumbleAdapter {
                                     info!(logger, "✅ MumbleLink mapped");
                                     link = Some(l);
                                     last_name = None; // force re-emit
+                                    last_identity = None;
 
                                 }
                                 Err(e) => {
@@ -98,6 +181,12 @@ impl Adapter for MumbleAdapter {
                         if let Some(l) = link.as_ref() {
                             if let Some((_, ident)) = l.read_full(true) {
                                 if let Some(id) = ident {
+                                    if last_identity.as_ref() != Some(&id) {
+                                        last_identity = Some(id.clone());
+                                        identity_ext.set(Some(id.clone()));
+                                        bus.publish_t(MUMBLE_IDENTITY, Some(id.clone()));
+                                    }
+
                                     let name = id.name.trim();
                                     if !name.is_empty() {
                                         if last_name.as_deref() != Some(name) {
@@ -109,6 +198,9 @@ impl Adapter for MumbleAdapter {
                                         last_name = None;
                                         active_char_ext.set(None);
                                         bus.publish_t(MUMBLE_ACTIVE_CHARACTER, None);
+                                        last_identity = None;
+                                        identity_ext.set(None);
+                                        bus.publish_t(MUMBLE_IDENTITY, None);
                                     }
                                 }
                             } else {
@@ -0,0 +1,163 @@
+// src/gw2/positions.rs
+//! Named positions saved from the Mumble avatar position, and the geometry to
+//! point back at them.
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::gw2::storage::{data_dir, read_json_or_default, write_json};
+
+const UNITS_PER_METER: f32 = 39.37;
+const POSITIONS_FILE: &str = "positions.json";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedPosition {
+    pub name: String,
+    pub map_id: u32,
+    /// Mumble avatar position: meters, height is the second value.
+    pub pos: [f32; 3],
+}
+
+impl SavedPosition {
+    /// Horizontal distance in game units.
+    pub fn distance_from(&self, pos: [f32; 3]) -> f32 {
+        horizontal_distance(pos, self.pos)
+    }
+
+    /// Height above `pos` in game units (negative: below).
+    pub fn height_from(&self, pos: [f32; 3]) -> f32 {
+        (self.pos[1] - pos[1]) * UNITS_PER_METER
+    }
+
+    /// Degrees clockwise from the avatar's facing (`front`) to this position,
+    /// in -180..=180. None when standing on it or the facing is unknown.
+    pub fn bearing_from(&self, pos: [f32; 3], front: [f32; 3]) -> Option<f32> {
+        bearing(pos, front, self.pos)
+    }
+}
+
+/// Horizontal distance in game units between two Mumble positions (meters).
+pub fn horizontal_distance(from: [f32; 3], to: [f32; 3]) -> f32 {
+    let (dx, dz) = (to[0] - from[0], to[2] - from[2]);
+    (dx * dx + dz * dz).sqrt() * UNITS_PER_METER
+}
+
+/// Straight-line distance between two positions, in their own units.
+pub fn dist(a: [f32; 3], b: [f32; 3]) -> f32 {
+    let d = [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+    (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt()
+}
+
+/// Degrees clockwise from `front` (facing at `from`) toward `to`, in -180..=180.
+/// None when `to` is straight above/below or the facing is unknown.
+pub fn bearing(from: [f32; 3], front: [f32; 3], to: [f32; 3]) -> Option<f32> {
+    let (dx, dz) = (to[0] - from[0], to[2] - from[2]);
+    let (fx, fz) = (front[0], front[2]);
+    if dx.hypot(dz) < 1e-3 || fx.hypot(fz) < 1e-3 {
+        return None;
+    }
+    // x grows east and z north, so the cross product sign is flipped
+    Some((fz * dx - fx * dz).atan2(fx * dx + fz * dz).to_degrees())
+}
+
+/// Saved positions, in the order they were saved, in `<data dir>/positions.json`.
+#[derive(Debug, Clone)]
+pub struct PositionStore {
+    path: PathBuf,
+}
+
+impl PositionStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn open_default() -> Result<Self, String> {
+        data_dir().map(|dir| Self::new(dir.join(POSITIONS_FILE)))
+    }
+
+    /// Empty when nothing was saved yet; an error when the file is unreadable.
+    pub fn load(&self) -> Result<Vec<SavedPosition>, String> {
+        read_json_or_default(&self.path)
+    }
+
+    /// By name, or the most recently saved one when `name` is None.
+    pub fn find(&self, name: Option<&str>) -> Result<Option<SavedPosition>, String> {
+        let all = self.load()?;
+        Ok(match name {
+            Some(name) => all.into_iter().find(|p| p.name == name),
+            None => all.into_iter().last(),
+        })
+    }
+
+    /// Save `position`, replacing one of the same name. Refuses to write when the
+    /// existing file can't be read, which would lose everything saved in it.
+    pub fn save(&self, position: SavedPosition) -> Result<(), String> {
+        let mut all = self.load()?;
+        all.retain(|p| p.name != position.name);
+        all.push(position);
+        write_json(&self.path, &all)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NORTH: [f32; 3] = [0.0, 0.0, 1.0];
+    const EAST: [f32; 3] = [1.0, 0.0, 0.0];
+    const HERE: [f32; 3] = [10.0, 5.0, 20.0];
+
+    fn offset(dx: f32, dy: f32, dz: f32) -> [f32; 3] {
+        [HERE[0] + dx, HERE[1] + dy, HERE[2] + dz]
+    }
+
+    fn assert_deg(actual: Option<f32>, expected: f32) {
+        let actual = actual.expect("no bearing");
+        assert!((actual - expected).abs() < 1e-3, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn bearing_ahead_is_zero() {
+        assert_deg(bearing(HERE, NORTH, offset(0.0, 0.0, 30.0)), 0.0);
+        // height doesn't matter
+        assert_deg(bearing(HERE, NORTH, offset(0.0, -40.0, 30.0)), 0.0);
+    }
+
+    #[test]
+    fn bearing_right_is_positive() {
+        // facing north, east is on the right
+        assert_deg(bearing(HERE, NORTH, offset(30.0, 0.0, 0.0)), 90.0);
+        assert_deg(bearing(HERE, NORTH, offset(-30.0, 0.0, 0.0)), -90.0);
+        // facing east, south is on the right
+        assert_deg(bearing(HERE, EAST, offset(0.0, 0.0, -30.0)), 90.0);
+        assert_deg(bearing(HERE, NORTH, offset(30.0, 0.0, 30.0)), 45.0);
+    }
+
+    #[test]
+    fn bearing_behind_is_half_turn() {
+        let b = bearing(HERE, NORTH, offset(0.0, 0.0, -30.0)).unwrap();
+        assert!((b.abs() - 180.0).abs() < 1e-3, "{b}");
+    }
+
+    #[test]
+    fn no_bearing_on_the_spot() {
+        assert_eq!(bearing(HERE, NORTH, HERE), None);
+        // straight above
+        assert_eq!(bearing(HERE, NORTH, offset(0.0, 50.0, 0.0)), None);
+        // facing straight up or down
+        assert_eq!(bearing(HERE, [0.0, 1.0, 0.0], offset(30.0, 0.0, 0.0)), None);
+    }
+
+    #[test]
+    fn saved_position_distance_and_height() {
+        let saved = SavedPosition {
+            name: "Vista".into(),
+            map_id: 15,
+            pos: offset(3.0, 2.0, 4.0),
+        };
+        assert!((saved.distance_from(HERE) - 5.0 * UNITS_PER_METER).abs() < 1e-3);
+        assert!((saved.height_from(HERE) - 2.0 * UNITS_PER_METER).abs() < 1e-3);
+        assert_deg(saved.bearing_from(HERE, NORTH), 36.869_9);
+    }
+}
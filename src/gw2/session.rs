@@ -0,0 +1,281 @@
+// src/gw2/session.rs
+//! Statistics for one game session (launch to exit), per character and map,
+//! accumulated from motion snapshots.
+//!
+//! Distances are in meters (Mumble units), times in milliseconds. Deaths by
+//! falling are an estimate: a fall that reached `FallRisk::Lethal` and ended
+//! on the ground rather than on a glider, mount or in water.
+
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::gw2::{
+    airborne::{Speed, classify::Movement},
+    enums::FallRisk,
+    positions::dist,
+    storage::{data_dir, write_json},
+};
+
+const SESSIONS_DIR: &str = "sessions";
+/// Longer between two samples (loading screen, stalled game) isn't counted.
+const MAX_GAP: Duration = Duration::from_secs(1);
+/// Faster than this between two samples is a waypoint or portal, not travel.
+const MAX_SPEED_M_S: f32 = 150.0;
+
+/// How distance was covered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MoveClass {
+    OnFoot,
+    Gliding,
+    Falling,
+    Mounted,
+    Flying,
+    Swimming,
+}
+
+impl MoveClass {
+    /// None for movement the classifier couldn't place.
+    pub fn of(movement: Movement) -> Option<Self> {
+        Some(match movement {
+            Movement::Idle
+            | Movement::Walk
+            | Movement::RunForward
+            | Movement::Strafe
+            | Movement::Backpedal => MoveClass::OnFoot,
+            Movement::GlideForward | Movement::GlideNeutral | Movement::GlideBack => {
+                MoveClass::Gliding
+            }
+            Movement::Falling | Movement::FallingTerminal => MoveClass::Falling,
+            Movement::MountedRun => MoveClass::Mounted,
+            Movement::Flying | Movement::Diving => MoveClass::Flying,
+            Movement::Swimming => MoveClass::Swimming,
+            Movement::Other => return None,
+        })
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MoveClass::OnFoot => "On foot",
+            MoveClass::Gliding => "Gliding",
+            MoveClass::Falling => "Falling",
+            MoveClass::Mounted => "Mounted",
+            MoveClass::Flying => "Flying",
+            MoveClass::Swimming => "Swimming",
+        }
+    }
+}
+
+/// Counters for one character, on one map or in total.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Tally {
+    /// Time in game (not in loading screens or character select).
+    pub time_ms: u64,
+    pub combat_ms: u64,
+    pub glide_ms: u64,
+    pub distance_m: BTreeMap<MoveClass, f32>,
+    /// Arrivals from another map; the first map of a session doesn't count.
+    pub map_changes: u32,
+    pub fall_deaths: u32,
+    /// Fastest speeds seen, in game units/s: ground speed and including height.
+    pub max_speed_horizontal: f32,
+    pub max_speed_total: f32,
+}
+
+impl Tally {
+    pub fn distance(&self, class: Option<MoveClass>) -> f32 {
+        match class {
+            Some(class) => self.distance_m.get(&class).copied().unwrap_or_default(),
+            None => self.distance_m.values().sum(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CharacterStats {
+    pub total: Tally,
+    pub maps: BTreeMap<u32, Tally>,
+}
+
+/// Everything counted since the game started; this is the exported summary.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionStats {
+    pub started: DateTime<Local>,
+    pub ended: Option<DateTime<Local>>,
+    pub characters: BTreeMap<String, CharacterStats>,
+}
+
+impl SessionStats {
+    pub fn new(started: DateTime<Local>) -> Self {
+        Self {
+            started,
+            ended: None,
+            characters: BTreeMap::new(),
+        }
+    }
+
+    /// A character's totals, or one map of them.
+    pub fn tally(&self, character: &str, map_id: Option<u32>) -> Option<&Tally> {
+        let stats = self.characters.get(character)?;
+        match map_id {
+            Some(map_id) => stats.maps.get(&map_id),
+            None => Some(&stats.total),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.characters.values().all(|c| c.total.time_ms == 0)
+    }
+}
+
+/// One motion snapshot, attributed to a character.
+#[derive(Debug, Clone, Copy)]
+pub struct SessionSample<'a> {
+    pub character: &'a str,
+    pub map_id: u32,
+    pub pos: [f32; 3],
+    pub movement: Movement,
+    pub speed: Option<Speed>,
+    pub in_combat: bool,
+    pub fall_risk: FallRisk,
+}
+
+struct LastSample {
+    at: Instant,
+    pos: [f32; 3],
+    fall_risk: FallRisk,
+}
+
+/// Turns a stream of samples into `SessionStats`.
+pub struct SessionTracker {
+    stats: SessionStats,
+    last: Option<LastSample>,
+    /// Character and map of the last sample, kept across loading screens.
+    location: Option<(String, u32)>,
+}
+
+impl SessionTracker {
+    pub fn new(started: DateTime<Local>) -> Self {
+        Self {
+            stats: SessionStats::new(started),
+            last: None,
+            location: None,
+        }
+    }
+
+    pub fn stats(&self) -> &SessionStats {
+        &self.stats
+    }
+
+    /// Feed one sample; None while not in a map (or no character is known).
+    pub fn update(&mut self, now: Instant, sample: Option<SessionSample>) {
+        let Some(s) = sample else {
+            self.last = None;
+            return;
+        };
+
+        let location = (s.character.to_string(), s.map_id);
+        match self.location.replace(location) {
+            Some((character, map_id)) if character == s.character && map_id == s.map_id => {}
+            Some((character, _)) if character == s.character => {
+                self.bump(s.character, s.map_id, |t| t.map_changes += 1);
+                self.last = None;
+            }
+            _ => self.last = None,
+        }
+
+        let last = self.last.replace(LastSample {
+            at: now,
+            pos: s.pos,
+            fall_risk: s.fall_risk,
+        });
+        let Some(last) = last else {
+            // first sample on this map: nothing to measure against yet
+            self.bump(s.character, s.map_id, |_| {});
+            return;
+        };
+
+        let class = MoveClass::of(s.movement);
+        let fell_to_death = last.fall_risk == FallRisk::Lethal
+            && s.fall_risk != FallRisk::Lethal
+            && matches!(class, Some(MoveClass::OnFoot | MoveClass::Falling) | None);
+
+        let dt = now.saturating_duration_since(last.at);
+        let counted = (dt <= MAX_GAP).then_some(dt.as_millis() as u64);
+        let step = dist(last.pos, s.pos);
+        let travelled = (step <= MAX_SPEED_M_S * dt.as_secs_f32().max(0.04)).then_some(step);
+
+        self.bump(s.character, s.map_id, |t| {
+            if let Some(ms) = counted {
+                t.time_ms += ms;
+                if s.in_combat {
+                    t.combat_ms += ms;
+                }
+                if class == Some(MoveClass::Gliding) {
+                    t.glide_ms += ms;
+                }
+            }
+            if let (Some(class), Some(m)) = (class, travelled)
+                && m > 0.0
+            {
+                *t.distance_m.entry(class).or_default() += m;
+            }
+            if fell_to_death {
+                t.fall_deaths += 1;
+            }
+            if let (Some(speed), Some(_)) = (s.speed, travelled) {
+                t.max_speed_horizontal = t.max_speed_horizontal.max(speed.horizontal);
+                t.max_speed_total = t.max_speed_total.max(speed.magnitude);
+            }
+        });
+    }
+
+    pub fn finish(mut self, ended: DateTime<Local>) -> SessionStats {
+        self.stats.ended = Some(ended);
+        self.stats
+    }
+
+    /// Apply `f` to the character's map tally and to its total.
+    fn bump(&mut self, character: &str, map_id: u32, f: impl Fn(&mut Tally)) {
+        let stats = self
+            .stats
+            .characters
+            .entry(character.to_string())
+            .or_default();
+        f(stats.maps.entry(map_id).or_default());
+        f(&mut stats.total);
+    }
+}
+
+/// Writes `<data dir>/sessions/session_<start time>.json`; returns the path.
+pub fn write_summary(stats: &SessionStats) -> Result<PathBuf, String> {
+    let name = format!("session_{}.json", stats.started.format("%Y-%m-%d_%H%M%S"));
+    let path = data_dir()?.join(SESSIONS_DIR).join(name);
+    write_json(&path, stats)?;
+    Ok(path)
+}
+
+/// `1h 05m` / `12m 30s` / `45s`.
+pub fn format_duration(ms: u64) -> String {
+    let s = ms / 1000;
+    match (s / 3600, s / 60 % 60, s % 60) {
+        (0, 0, sec) => format!("{sec}s"),
+        (0, min, sec) => format!("{min}m {sec:02}s"),
+        (h, min, _) => format!("{h}h {min:02}m"),
+    }
+}
+
+/// `850 m` / `12.4 km`.
+pub fn format_distance(m: f32) -> String {
+    if m < 1000.0 {
+        format!("{m:.0} m")
+    } else {
+        format!("{:.1} km", m / 1000.0)
+    }
+}
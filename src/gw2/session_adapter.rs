@@ -0,0 +1,137 @@
+// src/gw2/session_adapter.rs
+use crossbeam_channel::{Receiver as CbReceiver, bounded, select, tick};
+use std::{
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+use streamdeck_lib::prelude::*;
+
+use crate::{
+    gw2::{
+        session::{SessionSample, SessionStats, SessionTracker, write_summary},
+        shared::{ActiveChar, SharedMotion, SharedSession, SharedUiState},
+    },
+    topics::SESSION_EXPORT,
+};
+
+/// Motion snapshots change at ~25 Hz; distance over 100ms steps is close enough.
+const SAMPLE_EVERY: Duration = Duration::from_millis(100);
+/// How often keys get fresh numbers.
+const SHARE_EVERY: Duration = Duration::from_secs(1);
+
+/// Accumulates session statistics from the motion pipeline.
+///
+/// Runs while the game does: a new session starts at launch, and the summary
+/// is written to the plugin's data directory when the game exits (the
+/// adapter stops).
+///
+/// Listens:
+/// - "session.export" -> write the summary so far
+pub struct SessionAdapter;
+
+impl SessionAdapter {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Adapter for SessionAdapter {
+    fn name(&self) -> &'static str {
+        "gw2.session_adapter"
+    }
+
+    fn policy(&self) -> StartPolicy {
+        StartPolicy::OnAppLaunch
+    }
+
+    fn topics(&self) -> &'static [&'static str] {
+        &[SESSION_EXPORT.name]
+    }
+
+    fn start(
+        &self,
+        cx: &Context,
+        _bus: Arc<dyn Bus>,
+        inbox: CbReceiver<Arc<ErasedTopic>>,
+    ) -> AdapterResult {
+        let (stop_tx, stop_rx) = bounded::<()>(1);
+        let logger = cx.log().clone();
+        let motion = cx.try_ext::<SharedMotion>().ok_or(AdapterError::Init(
+            "SharedMotion extension not found".into(),
+        ))?;
+        let active_char = cx
+            .try_ext::<ActiveChar>()
+            .ok_or(AdapterError::Init("ActiveChar extension not found".into()))?;
+        let shared = cx.try_ext::<SharedSession>().ok_or(AdapterError::Init(
+            "SharedSession extension not found".into(),
+        ))?;
+        let ui_state = cx.try_ext::<SharedUiState>();
+
+        let join = thread::spawn(move || {
+            let save = |stats: &SessionStats| {
+                if stats.is_empty() {
+                    debug!(logger, "session: nothing to write");
+                    return;
+                }
+                match write_summary(stats) {
+                    Ok(path) => info!(logger, "session: summary written to {}", path.display()),
+                    Err(e) => error!(logger, "session: writing summary failed: {e}"),
+                }
+            };
+
+            let sampler = tick(SAMPLE_EVERY);
+            let sharer = tick(SHARE_EVERY);
+            let mut tracker = SessionTracker::new(chrono::Local::now());
+            shared.set(tracker.stats().clone());
+
+            info!(logger, "session adapter started");
+
+            loop {
+                select! {
+                    recv(inbox) -> msg => {
+                        let Ok(note) = msg else {
+                            break; // inbox closed
+                        };
+                        if note.downcast(SESSION_EXPORT).is_some() {
+                            save(tracker.stats());
+                        }
+                    }
+
+                    recv(stop_rx) -> _ => {
+                        debug!(logger, "Stopping session adapter...");
+                        break;
+                    }
+
+                    recv(sampler) -> _ => {
+                        let snapshot = motion.get();
+                        let character = active_char.get();
+                        let sample = snapshot.position.zip(character.as_deref()).map(|(p, character)| {
+                            SessionSample {
+                                character,
+                                map_id: p.map_id,
+                                pos: p.pos,
+                                movement: snapshot.state,
+                                speed: snapshot.speed,
+                                in_combat: ui_state.as_ref().is_some_and(|u| u.get().is_in_combat()),
+                                fall_risk: snapshot.fall_risk,
+                            }
+                        });
+                        tracker.update(Instant::now(), sample);
+                    }
+
+                    recv(sharer) -> _ => {
+                        shared.set(tracker.stats().clone());
+                    }
+                }
+            }
+
+            let stats = tracker.finish(chrono::Local::now());
+            save(&stats);
+            shared.set(stats);
+            info!(logger, "session adapter stopped");
+        });
+
+        Ok(AdapterHandle::from_crossbeam(join, stop_tx))
+    }
+}
@@ -1,10 +1,23 @@
 // src/gw2/shared.rs
-use std::sync::{Arc, RwLock};
+use std::{
+    sync::{Arc, RwLock},
+    time::Instant,
+};
 
 use dashmap::DashMap;
 use streamdeck_lib::prelude::{GlobalSettings, SdClient};
 
-use crate::gw2::{binds::BindingSet, enums::TemplateNames};
+use crate::gw2::{
+    airborne::{
+        Speed,
+        classify::{ClassifierConfig, Movement, WaterState},
+    },
+    binds::BindingSet,
+    course::Run,
+    enums::{ActiveTabs, FallRisk, Identity, Mount, TemplateNames, UiState},
+    markers::MarkerSet,
+    session::SessionStats,
+};
 
 /// Arc<RwLock<…>> so SettingsAction can update at runtime and mappers read it.
 #[derive(Clone)]
@@ -54,9 +67,12 @@ impl SharedBindings {
     }
 }
 
-/// Cloneable, thread-safe map: character -> names
+/// Cloneable, thread-safe maps: character -> names, character -> active tabs
 #[derive(Clone, Default)]
-pub struct TemplateStore(Arc<DashMap<String, TemplateNames>>);
+pub struct TemplateStore {
+    names: Arc<DashMap<String, TemplateNames>>,
+    active: Arc<DashMap<String, ActiveTabs>>,
+}
 
 impl TemplateStore {
     pub fn new() -> Self {
@@ -65,16 +81,17 @@ impl TemplateStore {
 
     pub fn get(&self, character: &str) -> Option<TemplateNames> {
         // clone so actions can own it briefly without a borrow guard
-        self.0.get(character).map(|r| r.clone())
+        self.names.get(character).map(|r| r.clone())
     }
 
     pub fn insert(&self, character: String, names: TemplateNames) {
-        self.0.insert(character, names);
+        self.names.insert(character, names);
     }
 
     /// Remove a character’s templates. Returns true if an entry existed.
     pub fn remove(&self, character: &str) -> bool {
-        self.0.remove(character).is_some()
+        self.active.remove(character);
+        self.names.remove(character).is_some()
     }
 
     /// Retain only entries that satisfy the given predicate.
@@ -83,7 +100,18 @@ impl TemplateStore {
     where
         F: FnMut(&String, &mut TemplateNames) -> bool,
     {
-        self.0.retain(f);
+        self.names.retain(f);
+        self.active.retain(|name, _| self.names.contains_key(name));
+    }
+
+    /// Active build/equipment tabs for a character, if known.
+    pub fn get_active(&self, character: &str) -> Option<ActiveTabs> {
+        self.active.get(character).map(|r| *r)
+    }
+
+    /// Replace the active tabs; returns true if the value changed.
+    pub fn set_active(&self, character: String, active: ActiveTabs) -> bool {
+        self.active.insert(character, active) != Some(active)
     }
 }
 
@@ -99,3 +127,193 @@ impl ActiveChar {
         }
     }
 }
+
+/// Last identity parsed from Mumble (profession, spec, map, commander, …).
+#[derive(Clone, Default)]
+pub struct SharedIdentity(Arc<RwLock<Option<Identity>>>);
+impl SharedIdentity {
+    pub fn get(&self) -> Option<Identity> {
+        self.0.read().ok().and_then(|g| g.clone())
+    }
+    pub fn set(&self, v: Option<Identity>) {
+        if let Ok(mut w) = self.0.write() {
+            *w = v;
+        }
+    }
+}
+
+/// Mount the player is on right now (from the Mumble context, polled fast).
+#[derive(Clone, Default)]
+pub struct CurrentMount(Arc<RwLock<Option<Mount>>>);
+impl CurrentMount {
+    pub fn get(&self) -> Option<Mount> {
+        self.0.read().ok().and_then(|g| *g)
+    }
+    pub fn set(&self, v: Option<Mount>) {
+        if let Ok(mut w) = self.0.write() {
+            *w = v;
+        }
+    }
+}
+
+/// Latest Mumble `ui_state` bits (map open, combat, focus, …).
+#[derive(Clone)]
+pub struct SharedUiState(Arc<RwLock<UiState>>);
+impl Default for SharedUiState {
+    fn default() -> Self {
+        Self(Arc::new(RwLock::new(UiState::empty())))
+    }
+}
+impl SharedUiState {
+    pub fn get(&self) -> UiState {
+        self.0.read().map(|g| *g).unwrap_or(UiState::empty())
+    }
+    pub fn set(&self, v: UiState) {
+        if let Ok(mut w) = self.0.write() {
+            *w = v;
+        }
+    }
+}
+
+/// Where the avatar is and which way it faces, straight from Mumble.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AvatarPosition {
+    pub map_id: u32,
+    /// Meters, height is the second value.
+    pub pos: [f32; 3],
+    pub front: [f32; 3],
+}
+
+/// Latest output of the motion adapter's classifier (~25 Hz).
+#[derive(Debug, Clone, Copy)]
+pub struct MotionSnapshot {
+    pub state: Movement,
+    /// None while not in a map (character select, loading screen).
+    pub position: Option<AvatarPosition>,
+    pub in_air: bool,
+    /// Last detected landing; the exec adapter holds inputs back shortly after.
+    pub landed_at: Option<Instant>,
+    pub speed: Option<Speed>,
+    pub water: WaterState,
+    pub fall_risk: FallRisk,
+    /// Units dropped so far in the current fall.
+    pub fall_drop: f32,
+}
+impl Default for MotionSnapshot {
+    fn default() -> Self {
+        Self {
+            state: Movement::Idle,
+            position: None,
+            in_air: false,
+            landed_at: None,
+            speed: None,
+            water: WaterState::Dry,
+            fall_risk: FallRisk::Safe,
+            fall_drop: 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct SharedMotion(Arc<RwLock<MotionSnapshot>>);
+impl SharedMotion {
+    pub fn get(&self) -> MotionSnapshot {
+        self.0.read().map(|g| *g).unwrap_or_default()
+    }
+    pub fn set(&self, v: MotionSnapshot) {
+        if let Ok(mut w) = self.0.write() {
+            *w = v;
+        }
+    }
+}
+
+/// Split timer state kept by the course adapter, for keys that show a live time.
+#[derive(Debug, Clone, Default)]
+pub struct CourseStatus {
+    /// Course of the current or last run.
+    pub course: Option<String>,
+    pub checkpoints: usize,
+    pub run: Option<Run>,
+    /// Delta against the personal best at the last checkpoint reached.
+    pub last_delta_ms: Option<i64>,
+    /// Time of the last finished run and whether it was a personal best.
+    pub finished: Option<(u64, bool)>,
+    pub best_ms: Option<u64>,
+}
+
+#[derive(Clone, Default)]
+pub struct SharedCourse(Arc<RwLock<CourseStatus>>);
+impl SharedCourse {
+    pub fn get(&self) -> CourseStatus {
+        self.0.read().map(|g| g.clone()).unwrap_or_default()
+    }
+    pub fn set(&self, v: CourseStatus) {
+        if let Ok(mut w) = self.0.write() {
+            *w = v;
+        }
+    }
+}
+
+/// Marker packs loaded from the `markers_dir` global setting, plus the
+/// category the POI keys are cycled to (None: all).
+#[derive(Clone, Default)]
+pub struct SharedMarkers {
+    set: Arc<RwLock<Arc<MarkerSet>>>,
+    selected: Arc<RwLock<Option<String>>>,
+}
+impl SharedMarkers {
+    pub fn get(&self) -> Arc<MarkerSet> {
+        self.set.read().map(|g| g.clone()).unwrap_or_default()
+    }
+    pub fn replace(&self, set: MarkerSet) {
+        if let Ok(mut w) = self.set.write() {
+            *w = Arc::new(set);
+        }
+    }
+    pub fn selected(&self) -> Option<String> {
+        self.selected.read().ok().and_then(|g| g.clone())
+    }
+    pub fn set_selected(&self, category: Option<String>) {
+        if let Ok(mut w) = self.selected.write() {
+            *w = category;
+        }
+    }
+}
+
+/// Statistics of the running game session, refreshed by the session adapter
+/// about once a second. None until the game is started.
+#[derive(Clone, Default)]
+pub struct SharedSession(Arc<RwLock<Option<Arc<SessionStats>>>>);
+impl SharedSession {
+    pub fn get(&self) -> Option<Arc<SessionStats>> {
+        self.0.read().ok().and_then(|g| g.clone())
+    }
+    pub fn set(&self, v: SessionStats) {
+        if let Ok(mut w) = self.0.write() {
+            *w = Some(Arc::new(v));
+        }
+    }
+}
+
+/// Classifier thresholds from the `classifier` global setting (defaults when unset).
+#[derive(Clone, Default)]
+pub struct SharedClassifier(Arc<RwLock<Arc<ClassifierConfig>>>);
+impl SharedClassifier {
+    pub fn get(&self) -> Arc<ClassifierConfig> {
+        self.0.read().map(|g| g.clone()).unwrap_or_default()
+    }
+
+    pub fn replace_from_globals(
+        &self,
+        globals: &serde_json::Map<String, serde_json::Value>,
+    ) -> Result<(), String> {
+        let cfg = match globals.get("classifier") {
+            None | Some(serde_json::Value::Null) => ClassifierConfig::default(),
+            Some(v) => serde_json::from_value::<ClassifierConfig>(v.clone())
+                .map_err(|e| format!("Failed to parse classifier config: {e}"))?,
+        };
+        let mut w = self.0.write().map_err(|e| e.to_string())?;
+        *w = Arc::new(cfg);
+        Ok(())
+    }
+}
@@ -0,0 +1,50 @@
+// src/gw2/storage.rs
+//! Plugin data files (courses, saved positions, …) in the per-user data directory.
+
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use directories::ProjectDirs;
+use serde::{Serialize, de::DeserializeOwned};
+
+/// `%APPDATA%\veelume\gw2\data` on Windows.
+pub fn data_dir() -> Result<PathBuf, String> {
+    ProjectDirs::from("icu", "veelume", "gw2")
+        .map(|dirs| dirs.data_dir().to_path_buf())
+        .ok_or("no home directory to keep plugin data in".into())
+}
+
+/// Pretty-printed, creating parent directories as needed.
+pub fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+    }
+    let json = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// Parsed JSON file, or `T::default()` when it doesn't exist yet. Any other failure
+/// is an error, so callers don't overwrite a file they couldn't read.
+pub fn read_json_or_default<T: DeserializeOwned + Default>(path: &Path) -> Result<T, String> {
+    match fs::read_to_string(path) {
+        Ok(text) => serde_json::from_str(&text).map_err(|e| format!("{}: {e}", path.display())),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(format!("{}: {e}", path.display())),
+    }
+}
+
+/// Names are free text (courses, trail packs, …); keep file names portable.
+pub fn file_stem(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
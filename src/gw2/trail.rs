@@ -0,0 +1,162 @@
+// src/gw2/trail.rs
+//! Recording the avatar's path as TacO / Blish HUD trails.
+//!
+//! A `.trl` file is little-endian binary: `i32` version (0), `i32` map id, then
+//! `f32` x/y/z triples in Mumble avatar coordinates (meters, y up). Trails are
+//! referenced from a marker pack XML with `<Trail trailData="…"/>`.
+
+use std::{
+    fmt::Write as _,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use crate::gw2::{positions::dist, storage::file_stem};
+
+const TRL_VERSION: i32 = 0;
+/// Farther than this between two samples is a waypoint/portal; the trail is split there.
+const MAX_STEP_M: f32 = 30.0;
+
+/// A continuous stretch of trail on one map.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrailSegment {
+    pub map_id: u32,
+    pub points: Vec<[f32; 3]>,
+}
+
+/// Collects positions, keeping one point per `min_step_m` travelled.
+///
+/// A new segment starts on map change and after teleports, so no trail line
+/// is drawn across the gap.
+#[derive(Debug, Clone)]
+pub struct TrailRecorder {
+    min_step_m: f32,
+    segments: Vec<TrailSegment>,
+    /// Last sample seen, kept or not; teleports are judged against it.
+    last: Option<[f32; 3]>,
+}
+
+impl TrailRecorder {
+    pub fn new(min_step_m: f32) -> Self {
+        Self {
+            min_step_m,
+            segments: Vec::new(),
+            last: None,
+        }
+    }
+
+    pub fn push(&mut self, map_id: u32, pos: [f32; 3]) {
+        let last = self.last.replace(pos);
+        let continues = self.segments.last().is_some_and(|s| s.map_id == map_id)
+            && last.is_some_and(|l| dist(l, pos) <= MAX_STEP_M);
+        if !continues {
+            self.segments.push(TrailSegment {
+                map_id,
+                points: vec![pos],
+            });
+            return;
+        }
+
+        let Some(seg) = self.segments.last_mut() else {
+            return;
+        };
+        if seg
+            .points
+            .last()
+            .is_none_or(|p| dist(*p, pos) >= self.min_step_m)
+        {
+            seg.points.push(pos);
+        }
+    }
+
+    pub fn point_count(&self) -> usize {
+        self.segments.iter().map(|s| s.points.len()).sum()
+    }
+
+    /// Segments worth writing: a single point draws nothing.
+    pub fn finish(mut self) -> Vec<TrailSegment> {
+        // end where we stopped, not at the last kept point
+        if let (Some(last), Some(seg)) = (self.last, self.segments.last_mut())
+            && seg.points.last() != Some(&last)
+        {
+            seg.points.push(last);
+        }
+        self.segments
+            .into_iter()
+            .filter(|s| s.points.len() >= 2)
+            .collect()
+    }
+}
+
+/// `.trl` file contents for one segment.
+pub fn trl_bytes(segment: &TrailSegment) -> Vec<u8> {
+    let mut out = Vec::with_capacity(8 + segment.points.len() * 12);
+    out.extend_from_slice(&TRL_VERSION.to_le_bytes());
+    out.extend_from_slice(&(segment.map_id as i32).to_le_bytes());
+    for p in &segment.points {
+        for v in p {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+    }
+    out
+}
+
+/// Writes `<dir>/<name>/<name>.xml` and one `.trl` per segment under
+/// `<dir>/<name>/trails/`, ready to drop into a TacO `POIs` or Blish HUD
+/// `markers` folder. Returns the XML path.
+///
+/// Existing packs are never touched: when `<name>` is taken, the pack (folder,
+/// files and category) is named `<name>_2`, `<name>_3`, … instead.
+pub fn write_pack(dir: &Path, name: &str, segments: &[TrailSegment]) -> Result<PathBuf, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+    let base = file_stem(name);
+    let mut n = 1;
+    let (stem, root) = loop {
+        let stem = if n == 1 {
+            base.clone()
+        } else {
+            format!("{base}_{n}")
+        };
+        let root = dir.join(&stem);
+        match fs::create_dir(&root) {
+            Ok(()) => break (stem, root),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => n += 1,
+            Err(e) => return Err(format!("{}: {e}", root.display())),
+        }
+    };
+    let trails = root.join("trails");
+    fs::create_dir(&trails).map_err(|e| format!("{}: {e}", trails.display()))?;
+
+    let mut xml = String::new();
+    let _ = writeln!(xml, r#"<?xml version="1.0" encoding="utf-8"?>"#);
+    let _ = writeln!(xml, "<OverlayData>");
+    let _ = writeln!(
+        xml,
+        r#"  <MarkerCategory name="{stem}" DisplayName="{}"/>"#,
+        xml_escape(name)
+    );
+    let _ = writeln!(xml, "  <POIs>");
+    for (i, seg) in segments.iter().enumerate() {
+        let file = format!("{stem}_{}_{i}.trl", seg.map_id);
+        let path = trails.join(&file);
+        fs::write(&path, trl_bytes(seg)).map_err(|e| format!("{}: {e}", path.display()))?;
+        let _ = writeln!(
+            xml,
+            r#"    <Trail type="{stem}" trailData="{stem}/trails/{file}"/>"#
+        );
+    }
+    let _ = writeln!(xml, "  </POIs>");
+    let _ = writeln!(xml, "</OverlayData>");
+
+    let path = root.join(format!("{stem}.xml"));
+    fs::write(&path, xml).map_err(|e| format!("{}: {e}", path.display()))?;
+    Ok(path)
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
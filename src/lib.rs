@@ -0,0 +1,51 @@
+//! Guild Wars 2 Stream Deck plugin internals.
+//!
+//! The plugin binary (`main.rs`) wires the actions and adapters together;
+//! `bin/glide_logger` reuses the Mumble and motion modules to record traces.
+
+pub mod actions {
+    pub mod compass;
+    pub mod dial_control;
+    pub mod fall_warning;
+    pub mod mount;
+    pub mod nearest_poi;
+    pub mod poi_category;
+    pub mod save_position;
+    pub mod session_stats;
+    pub mod set_template;
+    pub mod settings;
+    pub mod speedometer;
+    pub mod split_timer;
+    pub mod squad_marker;
+    pub mod template_cycler;
+    pub mod title_template;
+    pub mod trail_recorder;
+}
+pub mod animation;
+pub mod api;
+pub mod gw2 {
+    pub mod airborne;
+    pub mod bindings_adapter;
+    pub mod binds;
+    pub mod course;
+    pub mod course_adapter;
+    pub mod enums;
+    pub mod exec_adapter;
+    pub mod gw2_api_adapter;
+    pub mod markers;
+    pub mod markers_adapter;
+    pub mod motion_adapter;
+    pub mod mumble;
+    pub mod mumble_adapter;
+    pub mod positions;
+    pub mod session;
+    pub mod session_adapter;
+    pub mod shared;
+    pub mod storage;
+    pub mod trail;
+}
+pub mod obs;
+pub mod render;
+pub mod topics;
+
+pub const PLUGIN_ID: &str = "icu.veelume.gw2";
@@ -1,32 +1,28 @@
-use crate::gw2::bindings_adapter::Gw2BindingsAdapter;
-use crate::gw2::exec_adapter::Gw2ExecAdapter;
-use crate::gw2::gw2_api_adapter::Gw2ApiAdapter;
-use crate::gw2::mumble_adapter::MumbleAdapter;
-use crate::gw2::shared::SharedBindings;
-use crate::gw2::shared::{ActiveChar, TemplateStore};
 use constcat::concat;
 use std::{process::exit, sync::Arc};
+#[cfg(windows)]
+use streamdeck_gw2::gw2::{
+    course_adapter::CourseAdapter, exec_adapter::Gw2ExecAdapter, motion_adapter::MotionAdapter,
+    mumble_adapter::MumbleAdapter, session_adapter::SessionAdapter,
+};
+use streamdeck_gw2::{
+    PLUGIN_ID, actions,
+    animation::AnimationAdapter,
+    api::ApiAdapter,
+    gw2::{
+        bindings_adapter::Gw2BindingsAdapter,
+        gw2_api_adapter::Gw2ApiAdapter,
+        markers_adapter::MarkersAdapter,
+        shared::{
+            ActiveChar, CurrentMount, SharedBindings, SharedClassifier, SharedCourse,
+            SharedIdentity, SharedMarkers, SharedMotion, SharedSession, SharedUiState,
+            TemplateStore,
+        },
+    },
+    obs::ObsAdapter,
+};
 use streamdeck_lib::prelude::*;
 
-mod actions {
-    pub mod set_template;
-    pub mod settings;
-}
-mod gw2 {
-    pub mod airborne;
-    pub mod bindings_adapter;
-    pub mod binds;
-    pub mod enums;
-    pub mod exec_adapter;
-    pub mod gw2_api_adapter;
-    pub mod mumble;
-    pub mod mumble_adapter;
-    pub mod shared;
-}
-mod topics;
-
-const PLUGIN_ID: &str = "icu.veelume.gw2";
-
 fn main() {
     let logger: Arc<dyn ActionLog> = match FileLogger::from_appdata(PLUGIN_ID) {
         Ok(logger) => Arc::new(logger),
@@ -54,26 +50,112 @@ fn main() {
                     info!(cx.log(), "Bindings updated from globals.");
                 }
             }
+            if let Some(classifier) = cx.try_ext::<SharedClassifier>() {
+                if let Err(e) = classifier.replace_from_globals(settings) {
+                    error!(cx.log(), "Failed to replace classifier config: {}", e);
+                }
+            }
         }
     });
 
     let shared_binds = SharedBindings::default();
     let template_store = TemplateStore::default();
     let active_char = ActiveChar::default();
+    let identity = SharedIdentity::default();
+    let mount = CurrentMount::default();
+    let ui_state = SharedUiState::default();
+    let motion = SharedMotion::default();
+    let classifier = SharedClassifier::default();
+    let course = SharedCourse::default();
+    let markers = SharedMarkers::default();
+    let session = SharedSession::default();
 
-    let plugin = match PluginBuilder::new()
+    let builder = PluginBuilder::new()
         .set_hooks(hooks)
         .add_adapter(Gw2BindingsAdapter::new())
         .add_adapter(Gw2ApiAdapter::new())
+        .add_adapter(MarkersAdapter::new());
+
+    // Mumble Link and input synthesis only exist on Windows.
+    #[cfg(windows)]
+    let builder = builder
         .add_adapter(MumbleAdapter::new())
-        .add_adapter(Gw2ExecAdapter::new())
+        .add_adapter(MotionAdapter::new())
+        .add_adapter(SessionAdapter::new())
+        .add_adapter(CourseAdapter::new())
+        .add_adapter(Gw2ExecAdapter::new());
+
+    let plugin = match builder
+        .add_adapter(AnimationAdapter::new())
+        .add_adapter(ApiAdapter::new())
+        .add_adapter(ObsAdapter::new())
         .add_extension(Arc::new(shared_binds))
         .add_extension(Arc::new(template_store))
         .add_extension(Arc::new(active_char))
+        .add_extension(Arc::new(identity))
+        .add_extension(Arc::new(mount))
+        .add_extension(Arc::new(ui_state))
+        .add_extension(Arc::new(motion))
+        .add_extension(Arc::new(classifier))
+        .add_extension(Arc::new(course))
+        .add_extension(Arc::new(markers))
+        .add_extension(Arc::new(session))
         .add_action(ActionFactory::new(
             concat!(PLUGIN_ID, ".set-template"),
             actions::set_template::SetTemplateAction::default,
         ))
+        .add_action(ActionFactory::new(
+            concat!(PLUGIN_ID, ".template-cycler"),
+            actions::template_cycler::TemplateCyclerAction::default,
+        ))
+        .add_action(ActionFactory::new(
+            concat!(PLUGIN_ID, ".dial-control"),
+            actions::dial_control::DialControlAction::default,
+        ))
+        .add_action(ActionFactory::new(
+            concat!(PLUGIN_ID, ".mount"),
+            actions::mount::MountAction::default,
+        ))
+        .add_action(ActionFactory::new(
+            concat!(PLUGIN_ID, ".squad-marker"),
+            actions::squad_marker::SquadMarkerAction::default,
+        ))
+        .add_action(ActionFactory::new(
+            concat!(PLUGIN_ID, ".speedometer"),
+            actions::speedometer::SpeedometerAction::default,
+        ))
+        .add_action(ActionFactory::new(
+            concat!(PLUGIN_ID, ".fall-warning"),
+            actions::fall_warning::FallWarningAction::default,
+        ))
+        .add_action(ActionFactory::new(
+            concat!(PLUGIN_ID, ".split-timer"),
+            actions::split_timer::SplitTimerAction::default,
+        ))
+        .add_action(ActionFactory::new(
+            concat!(PLUGIN_ID, ".save-position"),
+            actions::save_position::SavePositionAction::default,
+        ))
+        .add_action(ActionFactory::new(
+            concat!(PLUGIN_ID, ".compass"),
+            actions::compass::CompassAction::default,
+        ))
+        .add_action(ActionFactory::new(
+            concat!(PLUGIN_ID, ".trail-recorder"),
+            actions::trail_recorder::TrailRecorderAction::default,
+        ))
+        .add_action(ActionFactory::new(
+            concat!(PLUGIN_ID, ".nearest-poi"),
+            actions::nearest_poi::NearestPoiAction::default,
+        ))
+        .add_action(ActionFactory::new(
+            concat!(PLUGIN_ID, ".poi-category"),
+            actions::poi_category::PoiCategoryAction::default,
+        ))
+        .add_action(ActionFactory::new(
+            concat!(PLUGIN_ID, ".session-stats"),
+            actions::session_stats::SessionStatsAction::default,
+        ))
         .add_action(ActionFactory::new(
             concat!(PLUGIN_ID, ".settings"),
             actions::settings::SettingsAction::default,
@@ -0,0 +1,463 @@
+// src/obs.rs
+//! OBS scene automation over obs-websocket v5: switch scenes and show, hide
+//! or toggle sources when the game state changes.
+//!
+//! Rules come from the `obs_rules` global setting, a JSON list; a rule
+//! without `source` switches the program scene, one with `source` changes
+//! that source in `scene` (toggling it when `visible` is left out):
+//!
+//! ```json
+//! [
+//!   { "on": "combat_start", "scene": "Combat" },
+//!   { "on": "combat_end", "scene": "Gameplay" },
+//!   { "on": "map_opened", "scene": "Gameplay", "source": "Map Blur", "visible": true },
+//!   { "on": "map_closed", "scene": "Gameplay", "source": "Map Blur", "visible": false },
+//!   { "on": "enter_wvw", "scene": "WvW" },
+//!   { "on": "character_select", "scene": "Be Right Back" }
+//! ]
+//! ```
+
+use std::{
+    net::{TcpStream, ToSocketAddrs},
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use crossbeam_channel::{Receiver as CbReceiver, bounded, select};
+use serde_json::{Map, Value, json};
+use sha2::{Digest, Sha256};
+use streamdeck_lib::prelude::*;
+use tungstenite::{Message, WebSocket};
+
+use crate::{
+    gw2::{
+        enums::{MapKind, UiState},
+        shared::SharedUiState,
+    },
+    topics::{
+        MUMBLE_IN_GAME, MUMBLE_MAP_KIND, MUMBLE_UI_STATE, OBS_CONFIG_SET, ObsAction, ObsConfig,
+        ObsRule, Trigger,
+    },
+};
+
+pub const DEFAULT_URL: &str = "ws://127.0.0.1:4455";
+const RPC_VERSION: u64 = 1;
+const IO_TIMEOUT: Duration = Duration::from_secs(2);
+/// Wait this long before trying to reach OBS again after a failure.
+const RETRY_AFTER: Duration = Duration::from_secs(5);
+
+pub fn parse_rules(text: &str) -> Result<Vec<ObsRule>, String> {
+    if text.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    serde_json::from_str(text).map_err(|e| format!("OBS rules: {e}"))
+}
+
+/// Read the OBS settings out of the global settings.
+pub fn config_from_globals(globals: &Map<String, Value>) -> Result<ObsConfig, String> {
+    let text = |key| {
+        globals
+            .get(key)
+            .and_then(|v: &Value| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+    };
+    Ok(ObsConfig {
+        url: text("obs_url").unwrap_or(DEFAULT_URL).to_string(),
+        password: text("obs_password").map(str::to_string),
+        rules: parse_rules(text("obs_rules").unwrap_or_default())?,
+    })
+}
+
+/// One piece of game state, as the Mumble adapter reports it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateChange {
+    UiState(UiState),
+    MapKind(MapKind),
+    InGame(bool),
+}
+
+/// Game state as far as it's known; None until first reported.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct GameState {
+    in_game: Option<bool>,
+    in_combat: Option<bool>,
+    map_open: Option<bool>,
+    map_kind: Option<MapKind>,
+}
+
+/// Turns state changes into the actions of the rules they trigger.
+///
+/// A trigger fires when its part of the state changes, including the first
+/// report, so OBS follows the game from the start.
+#[derive(Debug, Default)]
+pub struct ObsAutomation {
+    rules: Vec<ObsRule>,
+    state: GameState,
+}
+
+impl ObsAutomation {
+    pub fn new(rules: Vec<ObsRule>) -> Self {
+        Self {
+            rules,
+            state: GameState::default(),
+        }
+    }
+
+    pub fn set_rules(&mut self, rules: Vec<ObsRule>) {
+        self.rules = rules;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Actions to run, in rule order.
+    pub fn apply(&mut self, change: StateChange) -> Vec<ObsAction> {
+        let prev = self.state;
+        match change {
+            StateChange::UiState(ui) => {
+                self.state.in_combat = Some(ui.is_in_combat());
+                self.state.map_open = Some(ui.is_map_open());
+            }
+            StateChange::MapKind(kind) => self.state.map_kind = Some(kind),
+            StateChange::InGame(in_game) => self.state.in_game = Some(in_game),
+        }
+
+        let fired = triggers(&prev, &self.state);
+        self.rules
+            .iter()
+            .filter(|r| fired.contains(&r.on))
+            .map(|r| r.action.clone())
+            .collect()
+    }
+}
+
+fn triggers(prev: &GameState, next: &GameState) -> Vec<Trigger> {
+    let mut fired = Vec::new();
+    let mut edge = |before: Option<bool>, after: Option<bool>, on: Trigger, off: Trigger| {
+        if before != after
+            && let Some(now) = after
+        {
+            fired.push(if now { on } else { off });
+        }
+    };
+    edge(
+        prev.in_combat,
+        next.in_combat,
+        Trigger::CombatStart,
+        Trigger::CombatEnd,
+    );
+    edge(
+        prev.map_open,
+        next.map_open,
+        Trigger::MapOpened,
+        Trigger::MapClosed,
+    );
+    edge(
+        prev.in_game,
+        next.in_game,
+        Trigger::InGame,
+        Trigger::CharacterSelect,
+    );
+    if prev.map_kind != next.map_kind
+        && let Some(kind) = next.map_kind
+    {
+        fired.push(match kind {
+            MapKind::Pve => Trigger::EnterPve,
+            MapKind::Pvp => Trigger::EnterPvp,
+            MapKind::Wvw => Trigger::EnterWvw,
+        });
+    }
+    fired
+}
+
+/// `authentication` for the Identify message: base64(sha256(base64(sha256(password + salt)) + challenge)).
+pub fn auth_response(password: &str, salt: &str, challenge: &str) -> String {
+    let secret = BASE64.encode(Sha256::digest(format!("{password}{salt}")));
+    BASE64.encode(Sha256::digest(format!("{secret}{challenge}")))
+}
+
+/// Blocking obs-websocket v5 client; enough for requests, ignores events.
+pub struct ObsClient {
+    ws: WebSocket<TcpStream>,
+    next_id: u64,
+}
+
+impl ObsClient {
+    /// Connect and identify, authenticating with `password` if OBS asks for it.
+    pub fn connect(url: &str, password: Option<&str>) -> Result<Self, String> {
+        let host = url
+            .strip_prefix("ws://")
+            .ok_or_else(|| format!("{url}: only ws:// URLs are supported"))?
+            .split('/')
+            .next()
+            .unwrap_or_default();
+        let addr = host
+            .to_socket_addrs()
+            .map_err(|e| format!("{host}: {e}"))?
+            .next()
+            .ok_or_else(|| format!("{host}: no address"))?;
+        let stream =
+            TcpStream::connect_timeout(&addr, IO_TIMEOUT).map_err(|e| format!("{host}: {e}"))?;
+        stream
+            .set_read_timeout(Some(IO_TIMEOUT))
+            .map_err(|e| e.to_string())?;
+        stream
+            .set_write_timeout(Some(IO_TIMEOUT))
+            .map_err(|e| e.to_string())?;
+        let (ws, _) = tungstenite::client::client(url, stream).map_err(|e| e.to_string())?;
+
+        let mut client = Self { ws, next_id: 1 };
+        let hello = client.read_op(0)?;
+        let mut identify = json!({ "rpcVersion": RPC_VERSION, "eventSubscriptions": 0 });
+        if let Some(auth) = hello.get("authentication") {
+            let password = password.ok_or("OBS asks for a password")?;
+            let field = |name| auth.get(name).and_then(Value::as_str).unwrap_or_default();
+            identify["authentication"] =
+                json!(auth_response(password, field("salt"), field("challenge")));
+        }
+        client.send(1, identify)?;
+        client.read_op(2)?;
+        Ok(client)
+    }
+
+    /// Send a request and wait for its response data (Null when there is none).
+    pub fn request(&mut self, request_type: &str, data: Value) -> Result<Value, String> {
+        let id = self.next_id.to_string();
+        self.next_id += 1;
+        self.send(
+            6,
+            json!({ "requestType": request_type, "requestId": id, "requestData": data }),
+        )?;
+
+        loop {
+            let d = self.read_op(7)?;
+            if d.get("requestId").and_then(Value::as_str) != Some(id.as_str()) {
+                continue;
+            }
+            let status = &d["requestStatus"];
+            if status["result"].as_bool() == Some(true) {
+                return Ok(d.get("responseData").cloned().unwrap_or(Value::Null));
+            }
+            let reason = status["comment"]
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("code {}", status["code"]));
+            return Err(format!("{request_type} failed: {reason}"));
+        }
+    }
+
+    pub fn switch_scene(&mut self, scene: &str) -> Result<(), String> {
+        self.request("SetCurrentProgramScene", json!({ "sceneName": scene }))
+            .map(|_| ())
+    }
+
+    /// Show or hide `source` in `scene`; None flips its current visibility.
+    pub fn set_source_visible(
+        &mut self,
+        scene: &str,
+        source: &str,
+        visible: Option<bool>,
+    ) -> Result<(), String> {
+        let item = self.request(
+            "GetSceneItemId",
+            json!({ "sceneName": scene, "sourceName": source }),
+        )?;
+        let item_id = item["sceneItemId"]
+            .as_i64()
+            .ok_or_else(|| format!("no scene item id for '{source}'"))?;
+        let visible = match visible {
+            Some(v) => v,
+            None => {
+                let current = self.request(
+                    "GetSceneItemEnabled",
+                    json!({ "sceneName": scene, "sceneItemId": item_id }),
+                )?;
+                !current["sceneItemEnabled"].as_bool().unwrap_or(false)
+            }
+        };
+        self.request(
+            "SetSceneItemEnabled",
+            json!({ "sceneName": scene, "sceneItemId": item_id, "sceneItemEnabled": visible }),
+        )
+        .map(|_| ())
+    }
+
+    pub fn run(&mut self, action: &ObsAction) -> Result<(), String> {
+        match action {
+            ObsAction::Scene { scene } => self.switch_scene(scene),
+            ObsAction::Source {
+                scene,
+                source,
+                visible,
+            } => self.set_source_visible(scene, source, *visible),
+        }
+    }
+
+    pub fn close(mut self) {
+        let _ = self.ws.close(None);
+        let _ = self.ws.flush();
+    }
+
+    fn send(&mut self, op: u64, d: Value) -> Result<(), String> {
+        self.ws
+            .send(Message::text(json!({ "op": op, "d": d }).to_string()))
+            .map_err(|e| e.to_string())
+    }
+
+    /// Next message with opcode `op` (events and other replies are skipped).
+    fn read_op(&mut self, op: u64) -> Result<Value, String> {
+        loop {
+            let text = match self.ws.read().map_err(|e| e.to_string())? {
+                Message::Text(text) => text,
+                Message::Close(frame) => {
+                    // 4009: authentication failed, 4010: unsupported RPC version, …
+                    return Err(frame.map_or("OBS closed the connection".into(), |f| {
+                        format!("OBS closed the connection: {} ({})", f.reason, f.code)
+                    }));
+                }
+                _ => continue,
+            };
+            let msg: Value = serde_json::from_str(&text).map_err(|e| e.to_string())?;
+            if msg["op"].as_u64() == Some(op) {
+                return Ok(msg["d"].clone());
+            }
+        }
+    }
+}
+
+/// Runs the OBS rules on Mumble state changes.
+///
+/// Connects to OBS when a rule first fires and keeps the connection; after a
+/// failure the next attempt waits a few seconds, and actions in between are
+/// dropped rather than replayed late.
+///
+/// Listens:
+/// - "obs.config-set"  -> new URL, password or rules
+/// - "mumble.ui-state" -> combat start/end, map opened/closed
+/// - "mumble.map-kind" -> entering PvE, PvP or WvW maps
+/// - "mumble.in-game"  -> character select / back in game
+pub struct ObsAdapter;
+
+impl ObsAdapter {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Adapter for ObsAdapter {
+    fn name(&self) -> &'static str {
+        "obs_client"
+    }
+
+    fn policy(&self) -> StartPolicy {
+        StartPolicy::OnAppLaunch
+    }
+
+    fn topics(&self) -> &'static [&'static str] {
+        &[
+            OBS_CONFIG_SET.name,
+            MUMBLE_UI_STATE.name,
+            MUMBLE_MAP_KIND.name,
+            MUMBLE_IN_GAME.name,
+        ]
+    }
+
+    fn start(
+        &self,
+        cx: &Context,
+        _bus: Arc<dyn Bus>,
+        inbox: CbReceiver<Arc<ErasedTopic>>,
+    ) -> AdapterResult {
+        let (stop_tx, stop_rx) = bounded::<()>(1);
+        let logger = cx.log().clone();
+        let mut config = config_from_globals(&cx.globals().snapshot()).unwrap_or_else(|e| {
+            warn!(logger, "obs: {e}");
+            ObsConfig::default()
+        });
+        let ui_state = cx.try_ext::<SharedUiState>();
+
+        let join = thread::spawn(move || {
+            let mut automation = ObsAutomation::new(config.rules.clone());
+            let mut client: Option<ObsClient> = None;
+            let mut retry_at = Instant::now();
+
+            // the first ui-state report may have gone out before we subscribed
+            let mut pending = match &ui_state {
+                Some(ui) if !automation.is_empty() => {
+                    automation.apply(StateChange::UiState(ui.get()))
+                }
+                _ => Vec::new(),
+            };
+
+            loop {
+                if !pending.is_empty() {
+                    if client.is_none() && Instant::now() >= retry_at {
+                        match ObsClient::connect(&config.url, config.password.as_deref()) {
+                            Ok(c) => {
+                                info!(logger, "obs: connected to {}", config.url);
+                                client = Some(c);
+                            }
+                            Err(e) => {
+                                warn!(logger, "obs: {e}");
+                                retry_at = Instant::now() + RETRY_AFTER;
+                            }
+                        }
+                    }
+                    for action in pending.drain(..) {
+                        let Some(c) = client.as_mut() else {
+                            debug!(logger, "obs: not connected, dropping {:?}", action);
+                            continue;
+                        };
+                        if let Err(e) = c.run(&action) {
+                            // reconnect next time; a dropped connection looks the same
+                            warn!(logger, "obs: {e}");
+                            client = None;
+                            retry_at = Instant::now() + RETRY_AFTER;
+                        }
+                    }
+                }
+
+                select! {
+                    recv(inbox) -> msg => {
+                        let Ok(note) = msg else {
+                            break; // inbox closed
+                        };
+                        let change = if let Some(new) = note.downcast(OBS_CONFIG_SET) {
+                            config = new.clone();
+                            automation.set_rules(config.rules.clone());
+                            if let Some(c) = client.take() {
+                                c.close();
+                            }
+                            retry_at = Instant::now();
+                            None
+                        } else if let Some(ui) = note.downcast(MUMBLE_UI_STATE) {
+                            Some(StateChange::UiState(*ui))
+                        } else if let Some(kind) = note.downcast(MUMBLE_MAP_KIND) {
+                            Some(StateChange::MapKind(*kind))
+                        } else {
+                            note.downcast(MUMBLE_IN_GAME).map(|g| StateChange::InGame(*g))
+                        };
+                        if let Some(change) = change {
+                            pending = automation.apply(change);
+                        }
+                    }
+
+                    recv(stop_rx) -> _ => {
+                        debug!(logger, "Stopping OBS adapter...");
+                        break;
+                    }
+                }
+            }
+
+            if let Some(c) = client.take() {
+                c.close();
+            }
+        });
+
+        Ok(AdapterHandle::from_crossbeam(join, stop_tx))
+    }
+}
@@ -0,0 +1,460 @@
+//! SVG key images rendered from state.
+//!
+//! Everything here is pure string building (no Stream Deck or Windows APIs), so the
+//! output is deterministic for a given input. Actions pass the result of
+//! [`KeyImage::to_data_uri`] straight to `set_image`.
+
+use std::fmt::Write as _;
+
+use crate::gw2::enums::{Identity, SquadMarker, elite_spec_name, profession_name};
+
+/// Key canvas size in SVG user units (Stream Deck keys are 72x72 / 144x144 px).
+const SIZE: f32 = 72.0;
+
+const BACKGROUND: &str = "#1B1B1F";
+const NEUTRAL_ACCENT: &str = "#C9A24A"; // gold, matches the static template icons
+const TEXT: &str = "#F2F2F2";
+const ACTIVE: &str = "#5FD07A";
+const ACTIVE_DARK: &str = "#1E5B2B";
+const QUEUED: &str = "#66CCFF";
+const RUNNING: &str = "#FFB347";
+const DISABLED: &str = "#5A5A60";
+
+/// Characters per line for template names; three lines fit under the header.
+const NAME_WRAP: usize = 10;
+const NAME_LINES: usize = 3;
+
+/// State overlay drawn on top of the base image.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Overlay {
+    #[default]
+    None,
+    /// Configured slots are the active ones: green frame + corner dot.
+    Active,
+    /// Waiting in the exec queue: blue bars top and bottom.
+    Queued,
+    /// Currently being executed: amber bars top and bottom.
+    Running,
+}
+
+/// Profession color pair: `accent` for frames and header, `dark` for the header text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    pub accent: &'static str,
+    pub dark: &'static str,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            accent: NEUTRAL_ACCENT,
+            dark: "#3A2A08",
+        }
+    }
+}
+
+impl Palette {
+    /// Red pair for alerts (fall warning, …).
+    pub const WARNING: Palette = Palette {
+        accent: "#FF5A4E",
+        dark: "#4A0F0B",
+    };
+    /// Green pair for good news (ahead of a personal best, …).
+    pub const AHEAD: Palette = Palette {
+        accent: "#7BD88F",
+        dark: "#15401F",
+    };
+}
+
+/// Colors for a Mumble profession id (the in-game class colors).
+pub fn profession_palette(profession: Option<u8>) -> Palette {
+    let (accent, dark) = match profession {
+        Some(1) => ("#72C1D9", "#163A45"), // Guardian
+        Some(2) => ("#FFD166", "#4A3608"), // Warrior
+        Some(3) => ("#D09C59", "#43290B"), // Engineer
+        Some(4) => ("#8CDC82", "#1E4419"), // Ranger
+        Some(5) => ("#C08F95", "#3E2428"), // Thief
+        Some(6) => ("#F68A87", "#4D1B1A"), // Elementalist
+        Some(7) => ("#B679D5", "#35194A"), // Mesmer
+        Some(8) => ("#52A76F", "#123A20"), // Necromancer
+        Some(9) => ("#D16E5A", "#451A12"), // Revenant
+        _ => return Palette::default(),
+    };
+    Palette { accent, dark }
+}
+
+/// Everything that can appear on a rendered key.
+#[derive(Debug, Clone, Default)]
+pub struct KeyImage {
+    pub palette: Palette,
+    /// Short header text, e.g. "B3 · E2".
+    pub header: Option<String>,
+    /// Main text, word-wrapped to three lines.
+    pub name: Option<String>,
+    /// Small footer text, e.g. the elite spec.
+    pub footer: Option<String>,
+    pub overlay: Overlay,
+    /// 0.0..=1.0; draws a ring around the key when set.
+    pub progress: Option<f32>,
+}
+
+impl KeyImage {
+    /// Palette and footer (elite spec or profession name) from the Mumble identity.
+    pub fn with_identity(mut self, identity: Option<&Identity>) -> Self {
+        let Some(id) = identity else {
+            return self;
+        };
+        self.palette = profession_palette(id.profession);
+        self.footer = id
+            .spec
+            .and_then(elite_spec_name)
+            .or_else(|| id.profession.and_then(profession_name))
+            .map(str::to_string);
+        self
+    }
+
+    pub fn to_svg(&self) -> String {
+        let p = self.palette;
+        let mut s = svg_open();
+        let _ = write!(
+            s,
+            r#"<rect x="1" y="1" width="70" height="70" rx="8" fill="{BACKGROUND}" stroke="{}" stroke-width="2"/>"#,
+            p.accent
+        );
+
+        if let Some(header) = self.header.as_deref().filter(|h| !h.is_empty()) {
+            let _ = write!(
+                s,
+                r#"<path d="M1 9a8 8 0 0 1 8-8h54a8 8 0 0 1 8 8v9H1z" fill="{}"/>"#,
+                p.accent
+            );
+            push_text(&mut s, 36.0, 14.0, 11.0, p.dark, true, header);
+        }
+
+        if let Some(name) = self.name.as_deref().filter(|n| !n.trim().is_empty()) {
+            let lines = name_lines(name);
+            let line_h = 12.0;
+            let top = if self.header.is_some() { 24.0 } else { 14.0 };
+            let space = if self.footer.is_some() { 60.0 } else { 70.0 } - top;
+            let first = top + (space - line_h * lines.len() as f32) / 2.0 + line_h - 2.0;
+            for (i, line) in lines.iter().enumerate() {
+                push_text(
+                    &mut s,
+                    36.0,
+                    first + line_h * i as f32,
+                    11.0,
+                    TEXT,
+                    false,
+                    line,
+                );
+            }
+        }
+
+        if let Some(footer) = self.footer.as_deref().filter(|f| !f.is_empty()) {
+            push_text(&mut s, 36.0, 67.0, 8.0, p.accent, false, footer);
+        }
+
+        match self.overlay {
+            Overlay::None => {}
+            Overlay::Active => {
+                let _ = write!(
+                    s,
+                    r#"<rect x="2.5" y="2.5" width="67" height="67" rx="7" fill="none" stroke="{ACTIVE}" stroke-width="3"/><circle cx="63" cy="9" r="4" fill="{ACTIVE}" stroke="{ACTIVE_DARK}" stroke-width="1"/>"#
+                );
+            }
+            Overlay::Queued => push_bars(&mut s, QUEUED),
+            Overlay::Running => push_bars(&mut s, RUNNING),
+        }
+
+        if let Some(progress) = self.progress {
+            push_ring(&mut s, progress, p.accent);
+        }
+
+        s.push_str("</svg>");
+        s
+    }
+
+    /// `data:` URI accepted by `set_image`.
+    pub fn to_data_uri(&self) -> String {
+        data_uri(&self.to_svg())
+    }
+}
+
+/// Arrow toward a target, relative to where the avatar faces (up = ahead).
+#[derive(Debug, Clone, Default)]
+pub struct CompassImage {
+    pub palette: Palette,
+    pub header: Option<String>,
+    /// Degrees clockwise from straight ahead; a dot instead of the arrow when None.
+    pub bearing: Option<f32>,
+    pub footer: Option<String>,
+}
+
+impl CompassImage {
+    pub fn to_svg(&self) -> String {
+        let p = self.palette;
+        let mut s = svg_open();
+        let _ = write!(
+            s,
+            r#"<rect x="1" y="1" width="70" height="70" rx="8" fill="{BACKGROUND}" stroke="{}" stroke-width="2"/>"#,
+            p.accent
+        );
+        if let Some(header) = self.header.as_deref().filter(|h| !h.is_empty()) {
+            let _ = write!(
+                s,
+                r#"<path d="M1 9a8 8 0 0 1 8-8h54a8 8 0 0 1 8 8v9H1z" fill="{}"/>"#,
+                p.accent
+            );
+            push_text(&mut s, 36.0, 14.0, 11.0, p.dark, true, header);
+        }
+
+        let _ = write!(
+            s,
+            r#"<circle cx="36" cy="39" r="17" fill="none" stroke="{}" stroke-opacity="0.35" stroke-width="1.5"/>"#,
+            p.accent
+        );
+        match self.bearing {
+            Some(deg) => {
+                let _ = write!(
+                    s,
+                    r#"<path d="M36 24l9 20l-9-5l-9 5z" fill="{TEXT}" transform="rotate({deg:.0} 36 39)"/>"#
+                );
+            }
+            None => {
+                let _ = write!(s, r#"<circle cx="36" cy="39" r="4" fill="{TEXT}"/>"#);
+            }
+        }
+
+        if let Some(footer) = self.footer.as_deref().filter(|f| !f.is_empty()) {
+            push_text(&mut s, 36.0, 67.0, 8.0, p.accent, false, footer);
+        }
+
+        s.push_str("</svg>");
+        s
+    }
+
+    /// `data:` URI accepted by `set_image`.
+    pub fn to_data_uri(&self) -> String {
+        data_uri(&self.to_svg())
+    }
+}
+
+/// Squad marker icon: the marker shape in its in-game color, on a ground
+/// ellipse for location markers or inside a ring for object markers.
+#[derive(Debug, Clone, Copy)]
+pub struct MarkerImage {
+    pub marker: SquadMarker,
+    pub object: bool,
+    /// Drawn greyed out with "Not commander" when false.
+    pub enabled: bool,
+}
+
+impl MarkerImage {
+    pub fn to_svg(&self) -> String {
+        let color = if self.enabled {
+            marker_color(self.marker)
+        } else {
+            DISABLED
+        };
+        let mut s = svg_open();
+        let _ = write!(
+            s,
+            r#"<rect x="1" y="1" width="70" height="70" rx="8" fill="{BACKGROUND}"/>"#
+        );
+        if self.object {
+            let _ = write!(
+                s,
+                r#"<circle cx="36" cy="32" r="23" fill="none" stroke="{color}" stroke-opacity="0.6" stroke-width="2"/>"#
+            );
+        } else {
+            let _ = write!(
+                s,
+                r#"<ellipse cx="36" cy="50" rx="20" ry="5" fill="{color}" fill-opacity="0.35"/>"#
+            );
+        }
+        push_marker_shape(&mut s, self.marker, color);
+
+        let footer = match (self.enabled, self.object) {
+            (false, _) => "Not commander",
+            (true, true) => "Object",
+            (true, false) => "Location",
+        };
+        push_text(&mut s, 36.0, 67.0, 8.0, TEXT, false, footer);
+
+        s.push_str("</svg>");
+        s
+    }
+
+    /// `data:` URI accepted by `set_image`.
+    pub fn to_data_uri(&self) -> String {
+        data_uri(&self.to_svg())
+    }
+}
+
+fn marker_color(marker: SquadMarker) -> &'static str {
+    match marker {
+        SquadMarker::Arrow => "#F5C542",
+        SquadMarker::Circle => "#5FD07A",
+        SquadMarker::Heart => "#E06FD8",
+        SquadMarker::Square => "#4A90E2",
+        SquadMarker::Star => "#4FD1E0",
+        SquadMarker::Spiral => "#B07CF0",
+        SquadMarker::Triangle => "#F39C3D",
+        SquadMarker::X => "#E5484D",
+    }
+}
+
+/// Shape centered on (36, 32), roughly 30 units across.
+fn push_marker_shape(s: &mut String, marker: SquadMarker, color: &str) {
+    let _ = match marker {
+        SquadMarker::Arrow => write!(
+            s,
+            r#"<polygon points="36,48 21,31 30,31 30,16 42,16 42,31 51,31" fill="{color}"/>"#
+        ),
+        SquadMarker::Circle => write!(s, r#"<circle cx="36" cy="32" r="14" fill="{color}"/>"#),
+        SquadMarker::Heart => write!(
+            s,
+            r#"<path d="M36 47 L22 33 A8 8 0 0 1 36 22 A8 8 0 0 1 50 33 Z" fill="{color}"/>"#
+        ),
+        SquadMarker::Square => write!(
+            s,
+            r#"<rect x="23" y="19" width="26" height="26" rx="2" fill="{color}"/>"#
+        ),
+        SquadMarker::Star => {
+            let points: Vec<String> = (0..10)
+                .map(|i| {
+                    let r = if i % 2 == 0 { 16.0 } else { 7.0 };
+                    let a = std::f32::consts::PI / 5.0 * i as f32 - std::f32::consts::FRAC_PI_2;
+                    format!("{:.1},{:.1}", 36.0 + r * a.cos(), 33.0 + r * a.sin())
+                })
+                .collect();
+            write!(
+                s,
+                r#"<polygon points="{}" fill="{color}"/>"#,
+                points.join(" ")
+            )
+        }
+        SquadMarker::Spiral => write!(
+            s,
+            r#"<path d="M36 32 a3 3 0 0 1 3 3 a6 6 0 0 1 -6 6 a9 9 0 0 1 -9 -9 a12 12 0 0 1 12 -12 a14 14 0 0 1 14 14" fill="none" stroke="{color}" stroke-width="3.5" stroke-linecap="round"/>"#
+        ),
+        SquadMarker::Triangle => {
+            write!(s, r#"<polygon points="36,17 51,45 21,45" fill="{color}"/>"#)
+        }
+        SquadMarker::X => write!(
+            s,
+            r#"<path d="M24 20 L48 44 M48 20 L24 44" stroke="{color}" stroke-width="7" stroke-linecap="round"/>"#
+        ),
+    };
+}
+
+// ── helpers ──────────────────────────────────────────────────────────────────
+
+/// Buffer for a key image, with the `<svg>` element already opened.
+fn svg_open() -> String {
+    let mut s = String::with_capacity(1024);
+    let _ = write!(
+        s,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="144" height="144" viewBox="0 0 {SIZE} {SIZE}">"#
+    );
+    s
+}
+
+fn data_uri(svg: &str) -> String {
+    format!(
+        "data:image/svg+xml;charset=utf8,{}",
+        urlencoding::encode(svg)
+    )
+}
+
+pub(crate) fn wrap_title_or_fallback(name: &str, fallback: &str, max_len: usize) -> String {
+    if name.is_empty() {
+        return fallback.to_string();
+    }
+    wrap_title(name, max_len)
+}
+
+/// Word-wraps `title` at `max_len` chars per line; long words are kept whole.
+pub(crate) fn wrap_title(title: &str, max_len: usize) -> String {
+    let mut out = String::new();
+    let mut cur_len = 0usize;
+
+    for (i, word) in title.split_whitespace().enumerate() {
+        let need = if i == 0 { word.len() } else { 1 + word.len() };
+        if cur_len > 0 && cur_len + need > max_len {
+            out.push('\n');
+            out.push_str(word);
+            cur_len = word.len();
+        } else {
+            if i != 0 && cur_len > 0 {
+                out.push(' ');
+                cur_len += 1;
+            }
+            out.push_str(word);
+            cur_len += word.len();
+        }
+    }
+
+    if out.is_empty() {
+        // title had no whitespace or was empty — still clamp hard to avoid long single-line strings
+        let mut s = String::new();
+        let _ = write!(&mut s, "{title}");
+        return s;
+    }
+
+    out
+}
+
+fn name_lines(name: &str) -> Vec<String> {
+    let wrapped = wrap_title(name.trim(), NAME_WRAP);
+    let mut lines: Vec<String> = wrapped.lines().map(str::to_string).collect();
+    if lines.len() > NAME_LINES {
+        lines.truncate(NAME_LINES);
+        if let Some(last) = lines.last_mut() {
+            last.push('…');
+        }
+    }
+    lines
+}
+
+fn push_text(s: &mut String, x: f32, y: f32, size: f32, fill: &str, bold: bool, text: &str) {
+    let weight = if bold { "700" } else { "400" };
+    let _ = write!(
+        s,
+        r#"<text x="{x:.1}" y="{y:.1}" font-family="Segoe UI, Arial, sans-serif" font-size="{size:.1}" font-weight="{weight}" fill="{fill}" text-anchor="middle">{}</text>"#,
+        escape_xml(text)
+    );
+}
+
+fn push_bars(s: &mut String, color: &str) {
+    let _ = write!(
+        s,
+        r#"<g fill="{color}" fill-opacity="0.8"><rect x="6" y="3" width="60" height="3" rx="1.5"/><rect x="6" y="66" width="60" height="3" rx="1.5"/></g>"#
+    );
+}
+
+/// Clockwise ring starting at 12 o'clock.
+fn push_ring(s: &mut String, progress: f32, color: &str) {
+    let r = 33.0_f32;
+    let circumference = 2.0 * std::f32::consts::PI * r;
+    let filled = circumference * progress.clamp(0.0, 1.0);
+    let _ = write!(
+        s,
+        r#"<circle cx="36" cy="36" r="{r:.1}" fill="none" stroke="{color}" stroke-opacity="0.25" stroke-width="3"/><circle cx="36" cy="36" r="{r:.1}" fill="none" stroke="{color}" stroke-width="3" stroke-linecap="round" stroke-dasharray="{filled:.2} {circumference:.2}" transform="rotate(-90 36 36)"/>"#
+    );
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
@@ -1,9 +1,24 @@
+use serde::{Deserialize, Serialize};
 use streamdeck_lib::prelude::*;
 
-use crate::gw2::enums::{CharacterChange, KeyControl, TemplateNames};
+use crate::gw2::enums::{
+    ActiveTabs, CharacterChange, FallRisk, Identity, InstanceId, KeyControl, MapKind, Mount,
+    RunEvent, TemplateNames, UiState,
+};
 
 pub const MUMBLE_ACTIVE_CHARACTER: TopicId<Option<String>> =
     TopicId::new("mumble.active-character");
+/// Full Mumble identity; emitted whenever any field of it changes.
+pub const MUMBLE_IDENTITY: TopicId<Option<Identity>> = TopicId::new("mumble.identity");
+/// Mount the player is on (None when on foot); emitted on change.
+pub const MUMBLE_MOUNT: TopicId<Option<Mount>> = TopicId::new("mumble.mount");
+/// Mumble `ui_state` bits; emitted on change.
+pub const MUMBLE_UI_STATE: TopicId<UiState> = TopicId::new("mumble.ui-state");
+/// Game mode of the current map; emitted on change.
+pub const MUMBLE_MAP_KIND: TopicId<MapKind> = TopicId::new("mumble.map-kind");
+/// False while Mumble stops updating: character select or a long loading
+/// screen. Emitted on change.
+pub const MUMBLE_IN_GAME: TopicId<bool> = TopicId::new("mumble.in-game");
 
 pub const GW2_API_GET_CHARACTERS: TopicId<()> = TopicId::new("gw2-api.get-characters");
 pub const GW2_API_TEMPLATE_CHANGED: TopicId<Gw2ApiTemplateChanged> =
@@ -30,13 +45,255 @@ pub struct Gw2ApiCharacterChanged {
     pub change: CharacterChange,
 }
 
-pub const GW2_ANIMATION_TICK: TopicId<()> = TopicId::new("gw2.animation.tick");
+/// Active build/equipment tabs of a character changed, either from an API refresh
+/// or optimistically after a swap finished executing.
+pub const GW2_TEMPLATE_ACTIVE_CHANGED: TopicId<Gw2TemplateActiveChanged> =
+    TopicId::new("gw2.template.active-changed");
+#[derive(Debug, Clone)]
+pub struct Gw2TemplateActiveChanged {
+    pub name: String,
+    pub active: ActiveTabs,
+}
+
+/// Ask the API adapter to confirm that `expected` became the active tabs of `name`.
+/// Only the `Some` sides of `expected` are compared.
+pub const GW2_API_VERIFY_TEMPLATE: TopicId<Gw2ApiVerifyTemplate> =
+    TopicId::new("gw2-api.verify-template");
+#[derive(Debug, Clone)]
+pub struct Gw2ApiVerifyTemplate {
+    pub name: String,
+    pub expected: ActiveTabs,
+    pub origin_ctx: String,
+}
+/// Sent to the originating context once a verification settles.
+pub const GW2_API_VERIFY_RESULT: TopicId<VerifyOutcome> = TopicId::new("gw2-api.verify-result");
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyOutcome {
+    Verified,
+    Mismatch {
+        expected: ActiveTabs,
+        actual: Option<ActiveTabs>,
+    },
+}
+
+/// Start (or replace) the animation `name` on context `ctx`.
+pub const ANIMATION_PLAY: TopicId<AnimationPlay> = TopicId::new("animation.play");
+#[derive(Debug, Clone)]
+pub struct AnimationPlay {
+    pub ctx: String,
+    pub name: String,
+    /// Image per frame: a plugin-relative path or a `data:` URI.
+    pub frames: Vec<String>,
+    pub frame_ms: u64,
+    pub mode: AnimationMode,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationMode {
+    Loop,
+    /// Play through once, then send `ANIMATION_DONE`.
+    Once,
+}
+/// Stop animation `name` on `ctx` (every animation on `ctx` when `name` is None).
+pub const ANIMATION_STOP: TopicId<AnimationStop> = TopicId::new("animation.stop");
+#[derive(Debug, Clone)]
+pub struct AnimationStop {
+    pub ctx: String,
+    pub name: Option<String>,
+}
+/// Sent to the animated context for every frame it should show.
+pub const ANIMATION_FRAME: TopicId<AnimationFrame> = TopicId::new("animation.frame");
+#[derive(Debug, Clone)]
+pub struct AnimationFrame {
+    pub name: String,
+    pub index: usize,
+    pub image: String,
+}
+/// Sent to the animated context after a `Once` animation showed its last frame.
+pub const ANIMATION_DONE: TopicId<String> = TopicId::new("animation.done");
+
+/// Start (or replace) a repeating timer `name` on context `ctx`.
+pub const TIMER_START: TopicId<TimerStart> = TopicId::new("timer.start");
+#[derive(Debug, Clone)]
+pub struct TimerStart {
+    pub ctx: String,
+    pub name: String,
+    pub every_ms: u64,
+}
+/// Stop timer `name` on `ctx` (every timer on `ctx` when `name` is None).
+pub const TIMER_STOP: TopicId<TimerStop> = TopicId::new("timer.stop");
+#[derive(Debug, Clone)]
+pub struct TimerStop {
+    pub ctx: String,
+    pub name: Option<String>,
+}
+/// Sent to the context each time its timer `name` fires.
+pub const TIMER_TICK: TopicId<String> = TopicId::new("timer.tick");
+
+/// Broadcast by the motion adapter whenever the risk of the current fall changes.
+pub const MOTION_FALL: TopicId<FallAlert> = TopicId::new("motion.fall");
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct FallAlert {
+    pub risk: FallRisk,
+    /// Units dropped so far.
+    pub drop: f32,
+}
+
+/// Broadcast by the motion adapter on position jumps and map/instance transitions.
+/// The classifier starts over after each of these.
+pub const MOTION_EVENT: TopicId<MotionEvent> = TopicId::new("motion.event");
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(tag = "type")]
+pub enum MotionEvent {
+    /// Moved farther between two samples than anything can travel (units).
+    Teleported {
+        distance: f32,
+    },
+    MapChanged {
+        from: Option<u32>,
+        to: u32,
+    },
+    /// Same map, different shard/instance/server (district change, new instance).
+    InstanceChanged {
+        map_id: u32,
+        instance: InstanceId,
+    },
+    Jumped,
+    /// Back on the ground after a jump, fall or glide; `drop` in units from the
+    /// highest point of the flight.
+    Landed {
+        airtime_ms: u64,
+        drop: f32,
+    },
+    Dodged,
+}
+
+/// Folder to load marker packs from; the markers adapter reloads on receipt.
+pub const MARKERS_DIR_SET: TopicId<String> = TopicId::new("markers-dir.set");
+/// Marker packs were (re)loaded; carries the number of POIs.
+pub const MARKERS_UPDATED: TopicId<usize> = TopicId::new("markers.updated");
+/// The POI category selection was cycled.
+pub const MARKERS_CATEGORY_CHANGED: TopicId<Option<String>> =
+    TopicId::new("markers.category-changed");
+
+/// A position was saved (name); compass keys reload their target.
+pub const POSITION_SAVED: TopicId<String> = TopicId::new("position.saved");
+
+/// Local API settings changed; the API adapter restarts its server.
+pub const API_CONFIG_SET: TopicId<ApiConfig> = TopicId::new("api.config-set");
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApiConfig {
+    /// Listen port on 127.0.0.1; None keeps the server off.
+    pub port: Option<u16>,
+    /// Required by `POST /exec`; None disables that endpoint.
+    pub token: Option<String>,
+}
+
+/// OBS settings changed; the OBS adapter reconnects and uses the new rules.
+pub const OBS_CONFIG_SET: TopicId<ObsConfig> = TopicId::new("obs.config-set");
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ObsConfig {
+    /// obs-websocket address, e.g. `ws://127.0.0.1:4455`.
+    pub url: String,
+    pub password: Option<String>,
+    /// No rules keeps the adapter from connecting at all.
+    pub rules: Vec<ObsRule>,
+}
+
+/// A change in game state a rule can react to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Trigger {
+    CombatStart,
+    CombatEnd,
+    MapOpened,
+    MapClosed,
+    CharacterSelect,
+    InGame,
+    EnterPve,
+    EnterPvp,
+    EnterWvw,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum ObsAction {
+    /// Show, hide or (without `visible`) toggle `source` in `scene`.
+    Source {
+        scene: String,
+        source: String,
+        #[serde(default)]
+        visible: Option<bool>,
+    },
+    /// Switch the program scene.
+    Scene { scene: String },
+}
+
+/// One entry of the `obs_rules` setting; see `obs` for the format.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ObsRule {
+    pub on: Trigger,
+    #[serde(flatten)]
+    pub action: ObsAction,
+}
+
+/// Write the session summary now instead of only at game exit.
+pub const SESSION_EXPORT: TopicId<()> = TopicId::new("session.export");
+
+/// Split timer progress (start, checkpoint, finish) on a course; see `gw2::course`.
+pub const COURSE_EVENT: TopicId<CourseEvent> = TopicId::new("course.event");
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CourseEvent {
+    pub course: String,
+    pub event: RunEvent,
+}
+/// Abandon the current run.
+pub const COURSE_RESET: TopicId<()> = TopicId::new("course.reset");
+/// Course files changed on disk (e.g. after an import); reload them.
+pub const COURSES_RELOAD: TopicId<()> = TopicId::new("course.reload");
+
 pub const GW2_EXEC_PROGRESS: TopicId<ExecState> = TopicId::new("gw2.exec_progress");
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
 pub enum ExecState {
     Queued,
     Started,
     Done,
+    /// Dropped from the queue because its `ttl_ms` ran out before the gates opened.
+    Expired,
+}
+
+/// Every `GW2_EXEC_PROGRESS` notice, broadcast for listeners other than the
+/// originating key (the local API).
+pub const GW2_EXEC_ACTIVITY: TopicId<ExecActivity> = TopicId::new("gw2.exec_activity");
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecActivity {
+    pub origin_ctx: String,
+    pub state: ExecState,
+}
+
+/// Sent to the originating context while its job is held back by the exec gates;
+/// re-sent whenever the reason or a displayed second changes.
+pub const GW2_EXEC_WAITING: TopicId<Gw2ExecWaiting> = TopicId::new("gw2.exec_waiting");
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gw2ExecWaiting {
+    pub reason: BlockReason,
+    pub waited_ms: u64,
+    /// Time left before the job expires; None without a TTL.
+    pub remaining_ms: Option<u64>,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockReason {
+    /// In combat and the job doesn't allow it.
+    Combat,
+    /// Out of combat and the job doesn't allow it.
+    OutOfCombat,
+    /// Gliding, falling or jumping.
+    Airborne,
+    /// Just landed; the game ignores some inputs for a moment.
+    LandingGrace,
+    /// Swimming or underwater and the job doesn't allow it.
+    InWater,
+    /// Runnable, but behind a blocked job.
+    Queue,
 }
 
 pub const GW2_EXEC_QUEUE: TopicId<Gw2ExecQueue> = TopicId::new("gw2-exec.queue");
@@ -46,7 +303,14 @@ pub struct Gw2ExecQueue {
     pub allow_in_combat: bool,
     pub allow_out_of_combat: bool,
     pub allow_gliding_or_falling: bool,
+    /// Run while swimming or underwater (templates often fail to swap there).
+    pub allow_in_water: bool,
     pub inter_control_ms: Option<u64>,
+    /// Give up (`ExecState::Expired`) if the job is still blocked after this long.
+    pub ttl_ms: Option<u64>,
+    /// Merge into this context's previous job while that one is still waiting,
+    /// instead of queueing a new one (dial ticks).
+    pub coalesce: bool,
     pub origin_ctx: String,
 }
 
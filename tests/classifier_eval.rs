@@ -0,0 +1,573 @@
+//! Offline evaluation of the motion classifier.
+//!
+//! Replays labeled traces through `AirClassifier` with the trace's own clock and
+//! scores the result per trace: accuracy, confusion matrix, how long the
+//! classifier takes to follow a label change, and how often it flickers within
+//! a constant label. The test fails when a trace scores worse than in
+//! `tests/classifier_baseline.json`.
+//!
+//! Traces come from two places:
+//! - synthetic ones: generated below from ideal speed profiles, plus hand-written
+//!   CSV in `tests/synthetic/` for uneven timing and unlabeled stretches;
+//! - recordings in `tests/traces/*.csv` or `*.jsonl`, as written by
+//!   `glide_logger --csv/--jsonl` with Numpad labels.
+//!
+//! After an intended classifier change, refresh the baseline with
+//! `UPDATE_CLASSIFIER_BASELINE=1 cargo test --test classifier_eval`.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use streamdeck_gw2::gw2::{
+    airborne::{
+        AirClassifier, MoveEvent,
+        classify::{ClassifierConfig, Conditions, Movement},
+    },
+    enums::Mount,
+};
+
+const UNITS_PER_METER: f32 = 39.37;
+const SAMPLE_MS: u64 = 40;
+/// Accuracy may drop this much below the baseline before we call it a regression.
+const ACCURACY_SLACK: f64 = 0.005;
+/// Worst transition latency may grow by two samples before we call it a regression.
+const LATENCY_SLACK_MS: u64 = 2 * SAMPLE_MS;
+
+// ── traces ───────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+struct Sample {
+    t_ms: u64,
+    /// Meters, (x,z,y) as Mumble reports it.
+    pos: [f32; 3],
+    front: [f32; 3],
+    cond: Conditions,
+    label: Option<Movement>,
+}
+
+struct Trace {
+    name: String,
+    samples: Vec<Sample>,
+}
+
+/// One stretch of constant motion; velocities in units/s, facing in the xy plane.
+struct Segment {
+    label: Movement,
+    ms: u64,
+    vel: [f32; 2],
+    vz: (f32, f32),
+    facing: [f32; 2],
+    mount: Option<Mount>,
+}
+
+fn seg(label: Movement, ms: u64, vel: [f32; 2], vz: f32) -> Segment {
+    Segment {
+        label,
+        ms,
+        vel,
+        vz: (vz, vz),
+        facing: [0.0, 1.0],
+        mount: None,
+    }
+}
+
+/// Integrates segments into 25 Hz samples. A small deterministic jitter keeps
+/// the classifier honest about noise.
+fn synthesize(name: &str, start_height_m: f32, segments: &[Segment]) -> Trace {
+    let mut samples = Vec::new();
+    let mut pos = [0.0f32, start_height_m, 0.0];
+    let mut t_ms = 0u64;
+    let mut rng = 0x2545_F491u32;
+    let mut jitter = || {
+        rng ^= rng << 13;
+        rng ^= rng >> 17;
+        rng ^= rng << 5;
+        (rng % 1000) as f32 / 1000.0 * 6.0 - 3.0 // ±3 units/s
+    };
+
+    for s in segments {
+        let n = s.ms / SAMPLE_MS;
+        for i in 0..n {
+            let f = i as f32 / n.max(1) as f32;
+            let vz = s.vz.0 + (s.vz.1 - s.vz.0) * f;
+            let (vx, vy) = if s.vel == [0.0, 0.0] {
+                (0.0, 0.0)
+            } else {
+                (s.vel[0] + jitter(), s.vel[1] + jitter())
+            };
+            let dt = SAMPLE_MS as f32 / 1000.0;
+            pos[0] += vx * dt / UNITS_PER_METER;
+            pos[2] += vy * dt / UNITS_PER_METER;
+            pos[1] += vz * dt / UNITS_PER_METER;
+            t_ms += SAMPLE_MS;
+            samples.push(Sample {
+                t_ms,
+                pos,
+                front: [s.facing[0], 0.0, s.facing[1]],
+                cond: Conditions {
+                    mount: s.mount,
+                    ..Conditions::default()
+                },
+                label: Some(s.label),
+            });
+        }
+    }
+    Trace {
+        name: name.into(),
+        samples,
+    }
+}
+
+fn synthetic_traces() -> Vec<Trace> {
+    use Movement::*;
+    let fwd = |h: f32| [0.0, h];
+    let facing_x = [1.0, 0.0];
+
+    let ground = synthesize(
+        "synthetic_ground",
+        10.0,
+        &[
+            seg(Idle, 1000, [0.0, 0.0], 0.0),
+            seg(Walk, 2000, fwd(80.0), 0.0),
+            seg(RunForward, 3000, fwd(294.0), 0.0),
+            Segment {
+                facing: facing_x,
+                ..seg(Strafe, 2000, fwd(180.0), 0.0)
+            },
+            seg(Backpedal, 2000, fwd(-105.0), 0.0),
+            seg(RunForward, 2000, fwd(294.0), 0.0),
+            seg(Idle, 1000, [0.0, 0.0], 0.0),
+        ],
+    );
+
+    let air = synthesize(
+        "synthetic_glide_fall",
+        400.0,
+        &[
+            seg(RunForward, 1000, fwd(294.0), 0.0),
+            seg(GlideNeutral, 3000, fwd(294.0), -113.0),
+            seg(GlideForward, 2000, fwd(390.0), -113.0),
+            Segment {
+                vz: (-300.0, -880.0),
+                ..seg(Falling, 1500, fwd(50.0), 0.0)
+            },
+            seg(FallingTerminal, 1000, fwd(50.0), -1000.0),
+            seg(Idle, 1000, [0.0, 0.0], 0.0),
+        ],
+    );
+
+    let raptor = |s: Segment| Segment {
+        mount: Some(Mount::Raptor),
+        ..s
+    };
+    let griffon = |s: Segment| Segment {
+        mount: Some(Mount::Griffon),
+        ..s
+    };
+    let mounted = synthesize(
+        "synthetic_mounted",
+        400.0,
+        &[
+            raptor(seg(Idle, 1000, [0.0, 0.0], 0.0)),
+            raptor(seg(MountedRun, 3000, fwd(700.0), 0.0)),
+            griffon(seg(MountedRun, 1000, fwd(500.0), 0.0)),
+            griffon(seg(Flying, 2000, fwd(500.0), 250.0)),
+            griffon(seg(Diving, 2000, fwd(800.0), -900.0)),
+            griffon(seg(Flying, 2000, fwd(600.0), -150.0)),
+        ],
+    );
+
+    let swim = synthesize(
+        "synthetic_swim",
+        -1.0,
+        &[
+            seg(Swimming, 3000, fwd(200.0), 0.0),
+            seg(Swimming, 2000, fwd(150.0), -120.0),
+            seg(Swimming, 3000, fwd(200.0), 0.0),
+        ],
+    );
+
+    let handmade = Trace {
+        name: "synthetic_handmade_glide_raptor".into(),
+        samples: parse_csv(include_str!("synthetic/handmade_glide_raptor.csv"))
+            .unwrap_or_else(|e| panic!("handmade_glide_raptor.csv: {e}")),
+    };
+
+    vec![ground, air, mounted, swim, handmade]
+}
+
+/// Recorded traces in `tests/traces/`; unlabeled samples are replayed but not scored.
+fn recorded_traces() -> Vec<Trace> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/traces");
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let text = fs::read_to_string(&path).ok()?;
+            let samples = match path.extension()?.to_str()? {
+                "csv" => parse_csv(&text),
+                "jsonl" => parse_jsonl(&text),
+                _ => return None,
+            }
+            .unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+            Some(Trace {
+                name: path.file_stem()?.to_string_lossy().into_owned(),
+                samples,
+            })
+        })
+        .collect()
+}
+
+fn cond_from(mount: &str, in_combat: bool) -> Conditions {
+    Conditions {
+        in_combat,
+        mount: Mount::from_key(mount),
+        ..Conditions::default()
+    }
+}
+
+fn label_from(s: &str) -> Result<Option<Movement>, String> {
+    if s.trim().is_empty() {
+        return Ok(None);
+    }
+    Movement::from_name(s)
+        .map(Some)
+        .ok_or(format!("unknown label {s:?}"))
+}
+
+fn parse_csv(text: &str) -> Result<Vec<Sample>, String> {
+    let mut lines = text.lines();
+    let header: Vec<&str> = lines.next().ok_or("empty file")?.split(',').collect();
+    let col = |name: &str| {
+        header
+            .iter()
+            .position(|h| *h == name)
+            .ok_or(format!("missing column {name}"))
+    };
+    let pos_cols = [col("pos_x")?, col("pos_z")?, col("pos_y")?];
+    let front_cols = [col("front_x")?, col("front_z")?, col("front_y")?];
+    let (t, mount, combat, label) = (
+        col("t_ms")?,
+        col("mount")?,
+        col("in_combat")?,
+        col("label")?,
+    );
+
+    lines
+        .filter(|l| !l.trim().is_empty())
+        .map(|line| {
+            let f: Vec<&str> = line.split(',').collect();
+            let num = |i: usize| -> Result<f32, String> {
+                f.get(i)
+                    .ok_or(format!("short row: {line}"))?
+                    .parse::<f32>()
+                    .map_err(|e| format!("{e}: {line}"))
+            };
+            Ok(Sample {
+                t_ms: num(t)? as u64,
+                pos: [num(pos_cols[0])?, num(pos_cols[1])?, num(pos_cols[2])?],
+                front: [
+                    num(front_cols[0])?,
+                    num(front_cols[1])?,
+                    num(front_cols[2])?,
+                ],
+                cond: cond_from(
+                    f.get(mount).copied().unwrap_or(""),
+                    f.get(combat) == Some(&"1"),
+                ),
+                label: label_from(f.get(label).copied().unwrap_or(""))?,
+            })
+        })
+        .collect()
+}
+
+fn parse_jsonl(text: &str) -> Result<Vec<Sample>, String> {
+    #[derive(Deserialize)]
+    struct Row {
+        t_ms: u64,
+        pos: [f32; 3],
+        front: [f32; 3],
+        #[serde(default)]
+        mount: String,
+        #[serde(default)]
+        in_combat: bool,
+        label: Option<String>,
+    }
+    text.lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|line| {
+            let r: Row = serde_json::from_str(line).map_err(|e| format!("{e}: {line}"))?;
+            Ok(Sample {
+                t_ms: r.t_ms,
+                pos: r.pos,
+                front: r.front,
+                cond: cond_from(&r.mount, r.in_combat),
+                label: label_from(r.label.as_deref().unwrap_or(""))?,
+            })
+        })
+        .collect()
+}
+
+// ── scoring ──────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Score {
+    accuracy: f64,
+    /// Label changes the classifier never caught up with.
+    missed_transitions: usize,
+    /// Prediction changes within a constant label, after the first match.
+    flicker: usize,
+    /// Worst delay between a label change and a matching prediction.
+    max_latency_ms: u64,
+}
+
+struct Report {
+    score: Score,
+    confusion: BTreeMap<(String, String), usize>,
+    mean_latency_ms: f64,
+}
+
+fn replay(trace: &Trace) -> Vec<Movement> {
+    let config = ClassifierConfig::default();
+    let mut air = AirClassifier::new(Instant::now());
+    let t0 = Instant::now();
+    trace
+        .samples
+        .iter()
+        .map(|s| {
+            let now = t0 + Duration::from_millis(s.t_ms);
+            air.update_sample(
+                now,
+                (s.pos, s.front),
+                config.profile_for(s.cond.mount),
+                s.cond,
+            )
+        })
+        .collect()
+}
+
+fn evaluate(trace: &Trace) -> Report {
+    let predicted = replay(trace);
+    let mut confusion = BTreeMap::new();
+    let (mut total, mut correct) = (0usize, 0usize);
+    let mut latencies = Vec::new();
+    let (mut missed, mut flicker) = (0usize, 0usize);
+
+    // walk constant-label segments
+    let mut i = 0;
+    while i < trace.samples.len() {
+        let label = trace.samples[i].label;
+        let mut j = i;
+        while j < trace.samples.len() && trace.samples[j].label == label {
+            j += 1;
+        }
+        if let Some(label) = label {
+            let seg = i..j;
+            for k in seg.clone() {
+                total += 1;
+                correct += (predicted[k] == label) as usize;
+                *confusion
+                    .entry((format!("{label:?}"), format!("{:?}", predicted[k])))
+                    .or_default() += 1;
+            }
+            match seg.clone().find(|&k| predicted[k] == label) {
+                Some(first) => {
+                    latencies.push(trace.samples[first].t_ms - trace.samples[i].t_ms);
+                    flicker += (first + 1..j)
+                        .filter(|&k| predicted[k] != predicted[k - 1])
+                        .count();
+                }
+                None => missed += 1,
+            }
+        }
+        i = j;
+    }
+
+    Report {
+        score: Score {
+            accuracy: if total == 0 {
+                1.0
+            } else {
+                correct as f64 / total as f64
+            },
+            missed_transitions: missed,
+            flicker,
+            max_latency_ms: latencies.iter().copied().max().unwrap_or(0),
+        },
+        confusion,
+        mean_latency_ms: if latencies.is_empty() {
+            0.0
+        } else {
+            latencies.iter().sum::<u64>() as f64 / latencies.len() as f64
+        },
+    }
+}
+
+fn print_report(name: &str, r: &Report) {
+    let s = &r.score;
+    println!(
+        "── {name}: accuracy {:.3}, missed {}, flicker {}, latency mean {:.0} ms / max {} ms",
+        s.accuracy, s.missed_transitions, s.flicker, r.mean_latency_ms, s.max_latency_ms
+    );
+    for ((label, pred), n) in &r.confusion {
+        let mark = if label == pred { ' ' } else { '!' };
+        println!("   {mark} {label:>16} -> {pred:<16} {n:>5}");
+    }
+}
+
+// ── baseline ─────────────────────────────────────────────────────────────────
+
+fn baseline_path() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/classifier_baseline.json")
+}
+
+#[test]
+fn classifier_does_not_regress() {
+    let traces: Vec<Trace> = synthetic_traces()
+        .into_iter()
+        .chain(recorded_traces())
+        .collect();
+
+    let scores: BTreeMap<String, Score> = traces
+        .iter()
+        .map(|t| {
+            let report = evaluate(t);
+            print_report(&t.name, &report);
+            (t.name.clone(), report.score)
+        })
+        .collect();
+
+    if std::env::var_os("UPDATE_CLASSIFIER_BASELINE").is_some() {
+        let json = serde_json::to_string_pretty(&scores).unwrap();
+        fs::write(baseline_path(), json + "\n").unwrap();
+        return;
+    }
+
+    let baseline: BTreeMap<String, Score> = serde_json::from_str(
+        &fs::read_to_string(baseline_path()).expect("tests/classifier_baseline.json missing"),
+    )
+    .expect("tests/classifier_baseline.json is not valid");
+
+    let mut failures = Vec::new();
+    for (name, now) in &scores {
+        let Some(base) = baseline.get(name) else {
+            failures.push(format!(
+                "{name}: no baseline (run with UPDATE_CLASSIFIER_BASELINE=1)"
+            ));
+            continue;
+        };
+        if now.accuracy + ACCURACY_SLACK < base.accuracy {
+            failures.push(format!(
+                "{name}: accuracy {:.3} < baseline {:.3}",
+                now.accuracy, base.accuracy
+            ));
+        }
+        if now.missed_transitions > base.missed_transitions {
+            failures.push(format!(
+                "{name}: missed transitions {} > baseline {}",
+                now.missed_transitions, base.missed_transitions
+            ));
+        }
+        if now.flicker > base.flicker {
+            failures.push(format!(
+                "{name}: flicker {} > baseline {}",
+                now.flicker, base.flicker
+            ));
+        }
+        if now.max_latency_ms > base.max_latency_ms + LATENCY_SLACK_MS {
+            failures.push(format!(
+                "{name}: max latency {} ms > baseline {} ms",
+                now.max_latency_ms, base.max_latency_ms
+            ));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn csv_traces_round_trip() {
+    // Same columns glide_logger writes; keeps the recorded-trace reader honest.
+    let header = "t_ms,pos_x,pos_z,pos_y,front_x,front_z,front_y,map_id,mount,in_combat,h,vz,movement,water,label";
+    let text = format!(
+        "{header}\n\
+         40,1.5,-2,3,0,0,1,15,raptor,1,294.00,0.00,MountedRun,Dry,MountedRun\n\
+         80,1.6,-2,3,0,0,1,15,,0,0.00,0.00,Idle,Dry,\n"
+    );
+    let samples = parse_csv(&text).unwrap();
+
+    assert_eq!(samples.len(), 2);
+    assert_eq!(samples[0].pos, [1.5, -2.0, 3.0]);
+    assert_eq!(samples[0].cond.mount, Some(Mount::Raptor));
+    assert!(samples[0].cond.in_combat);
+    assert_eq!(samples[0].label, Some(Movement::MountedRun));
+    assert_eq!(samples[1].cond.mount, None);
+    assert_eq!(samples[1].label, None);
+}
+
+#[test]
+fn jump_land_and_dodge_events() {
+    use Movement::*;
+    let fwd = |h: f32| [0.0, h];
+    let rising = Segment {
+        vz: (400.0, 0.0),
+        ..seg(Other, 400, fwd(294.0), 0.0)
+    };
+    let descending = Segment {
+        vz: (0.0, -400.0),
+        ..seg(Other, 400, fwd(294.0), 0.0)
+    };
+    let trace = synthesize(
+        "jump_and_dodge",
+        10.0,
+        &[
+            seg(Idle, 1000, [0.0, 0.0], 0.0),
+            seg(RunForward, 1000, fwd(294.0), 0.0),
+            rising,
+            descending,
+            seg(RunForward, 1000, fwd(294.0), 0.0),
+            seg(Other, 600, fwd(550.0), 0.0),
+            seg(RunForward, 1000, fwd(294.0), 0.0),
+        ],
+    );
+
+    let config = ClassifierConfig::default();
+    let mut air = AirClassifier::new(Instant::now());
+    let t0 = Instant::now();
+    let mut events = Vec::new();
+    for s in &trace.samples {
+        let now = t0 + Duration::from_millis(s.t_ms);
+        air.update_sample(
+            now,
+            (s.pos, s.front),
+            config.profile_for(s.cond.mount),
+            s.cond,
+        );
+        events.extend(air.take_events());
+    }
+
+    let [
+        MoveEvent::Jumped,
+        MoveEvent::Landed { airtime, drop },
+        MoveEvent::Dodged,
+    ] = events[..]
+    else {
+        panic!("unexpected events: {events:?}");
+    };
+    // 800 ms up and down, ~80 units high
+    assert!(
+        (600..=1000).contains(&airtime.as_millis()),
+        "airtime {airtime:?}"
+    );
+    assert!((40.0..=120.0).contains(&drop), "drop {drop}");
+}
@@ -0,0 +1,267 @@
+//! OBS automation against a local mock obs-websocket v5 server.
+//!
+//! The mock speaks just enough of the protocol: Hello with an authentication
+//! challenge, Identify checked against its own password, and answers for the
+//! scene and scene item requests the client sends. Every request it gets is
+//! forwarded to the test, which checks what OBS would have been asked to do.
+
+use std::{
+    net::TcpListener,
+    sync::mpsc::{Receiver, channel},
+    thread,
+    time::Duration,
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use tungstenite::Message;
+
+use streamdeck_gw2::{
+    gw2::enums::{MapKind, UiState},
+    obs::{ObsAutomation, ObsClient, StateChange, parse_rules},
+    topics::{ObsAction, Trigger},
+};
+
+const PASSWORD: &str = "hunter2";
+const SALT: &str = "lM1GncleQOaCu9lT1yeUZhFYnqhsLLP1G5lAGo3ixaI=";
+const CHALLENGE: &str = "+IxH4CnCiqpX1rM9scsNynZzbOe4KhDeYcTNS3PDaeY=";
+/// Scene item id the mock hands out for any source.
+const ITEM_ID: i64 = 7;
+
+/// Starts a mock server for one connection; returns its URL and the requests it receives.
+fn mock_obs() -> (String, Receiver<Value>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    let (tx, rx) = channel();
+
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut ws = tungstenite::accept(stream).unwrap();
+        let send = |ws: &mut tungstenite::WebSocket<_>, op: u64, d: Value| {
+            ws.send(Message::text(json!({ "op": op, "d": d }).to_string()))
+                .unwrap();
+        };
+
+        send(
+            &mut ws,
+            0,
+            json!({
+                "obsWebSocketVersion": "5.5.0",
+                "rpcVersion": 1,
+                "authentication": { "challenge": CHALLENGE, "salt": SALT },
+            }),
+        );
+
+        // computed here independently of `obs::auth_response`
+        let secret = BASE64.encode(Sha256::digest(format!("{PASSWORD}{SALT}")));
+        let expected = BASE64.encode(Sha256::digest(format!("{secret}{CHALLENGE}")));
+        let mut enabled = false;
+
+        while let Ok(msg) = ws.read() {
+            let Message::Text(text) = msg else {
+                continue;
+            };
+            let msg: Value = serde_json::from_str(&text).unwrap();
+            let d = &msg["d"];
+            match msg["op"].as_u64() {
+                Some(1) => {
+                    if d["authentication"].as_str() != Some(expected.as_str()) {
+                        let _ = ws.close(Some(tungstenite::protocol::CloseFrame {
+                            code: 4009.into(),
+                            reason: "Authentication failed.".into(),
+                        }));
+                        let _ = ws.flush();
+                        return;
+                    }
+                    send(&mut ws, 2, json!({ "negotiatedRpcVersion": 1 }));
+                }
+                Some(6) => {
+                    let request_type = d["requestType"].as_str().unwrap_or_default();
+                    let data = &d["requestData"];
+                    let (ok, response) = match request_type {
+                        "SetCurrentProgramScene" => (data["sceneName"] != "Missing", json!(null)),
+                        "GetSceneItemId" => (true, json!({ "sceneItemId": ITEM_ID })),
+                        "GetSceneItemEnabled" => (true, json!({ "sceneItemEnabled": enabled })),
+                        "SetSceneItemEnabled" => {
+                            enabled = data["sceneItemEnabled"].as_bool().unwrap();
+                            (true, json!(null))
+                        }
+                        _ => (false, json!(null)),
+                    };
+                    // an event in between, which the client must skip
+                    send(
+                        &mut ws,
+                        5,
+                        json!({ "eventType": "Noise", "eventIntent": 0 }),
+                    );
+                    let mut reply = json!({
+                        "requestType": request_type,
+                        "requestId": d["requestId"],
+                        "requestStatus": if ok {
+                            json!({ "result": true, "code": 100 })
+                        } else {
+                            json!({ "result": false, "code": 600, "comment": "No source was found." })
+                        },
+                    });
+                    if !response.is_null() {
+                        reply["responseData"] = response;
+                    }
+                    send(&mut ws, 7, reply);
+                    let _ = tx.send(json!({ "type": request_type, "data": data }));
+                }
+                _ => {}
+            }
+        }
+    });
+
+    (url, rx)
+}
+
+fn next_request(rx: &Receiver<Value>) -> Value {
+    rx.recv_timeout(Duration::from_secs(2))
+        .expect("mock got no request")
+}
+
+#[test]
+fn combat_rule_switches_scene() {
+    let rules = parse_rules(
+        r#"[
+            { "on": "combat_start", "scene": "Combat" },
+            { "on": "combat_end", "scene": "Gameplay" }
+        ]"#,
+    )
+    .unwrap();
+    let mut automation = ObsAutomation::new(rules);
+    let actions = automation.apply(StateChange::UiState(UiState::IN_COMBAT));
+    assert_eq!(
+        actions,
+        vec![ObsAction::Scene {
+            scene: "Combat".into()
+        }]
+    );
+
+    let (url, rx) = mock_obs();
+    let mut client = ObsClient::connect(&url, Some(PASSWORD)).unwrap();
+    for action in &actions {
+        client.run(action).unwrap();
+    }
+    assert_eq!(
+        next_request(&rx),
+        json!({ "type": "SetCurrentProgramScene", "data": { "sceneName": "Combat" } })
+    );
+}
+
+#[test]
+fn source_is_toggled_and_set() {
+    let (url, rx) = mock_obs();
+    let mut client = ObsClient::connect(&url, Some(PASSWORD)).unwrap();
+
+    client.set_source_visible("Game", "Map Blur", None).unwrap();
+    assert_eq!(next_request(&rx)["type"], "GetSceneItemId");
+    assert_eq!(next_request(&rx)["type"], "GetSceneItemEnabled");
+    let set = next_request(&rx);
+    assert_eq!(set["type"], "SetSceneItemEnabled");
+    assert_eq!(
+        set["data"],
+        json!({ "sceneName": "Game", "sceneItemId": ITEM_ID, "sceneItemEnabled": true })
+    );
+
+    client
+        .set_source_visible("Game", "Map Blur", Some(false))
+        .unwrap();
+    assert_eq!(next_request(&rx)["type"], "GetSceneItemId");
+    let set = next_request(&rx);
+    assert_eq!(set["type"], "SetSceneItemEnabled");
+    assert_eq!(set["data"]["sceneItemEnabled"], false);
+}
+
+#[test]
+fn failed_request_reports_obs_comment() {
+    let (url, _rx) = mock_obs();
+    let mut client = ObsClient::connect(&url, Some(PASSWORD)).unwrap();
+    let err = client.switch_scene("Missing").unwrap_err();
+    assert!(err.contains("No source was found"), "{err}");
+    // the connection is still usable
+    client.switch_scene("Gameplay").unwrap();
+}
+
+#[test]
+fn wrong_password_is_rejected() {
+    let (url, _rx) = mock_obs();
+    let err = ObsClient::connect(&url, Some("wrong")).err().unwrap();
+    assert!(err.contains("4009"), "{err}");
+
+    let (url, _rx) = mock_obs();
+    assert!(ObsClient::connect(&url, None).is_err());
+}
+
+#[test]
+fn rules_fire_on_changes_only() {
+    let rules = parse_rules(
+        r#"[
+            { "on": "map_opened", "scene": "Game", "source": "Map Blur", "visible": true },
+            { "on": "map_closed", "scene": "Game", "source": "Map Blur" },
+            { "on": "enter_wvw", "scene": "WvW" },
+            { "on": "character_select", "scene": "BRB" },
+            { "on": "in_game", "scene": "Game" }
+        ]"#,
+    )
+    .unwrap();
+    assert_eq!(rules[0].on, Trigger::MapOpened);
+    assert_eq!(
+        rules[1].action,
+        ObsAction::Source {
+            scene: "Game".into(),
+            source: "Map Blur".into(),
+            visible: None
+        }
+    );
+
+    let mut automation = ObsAutomation::new(rules);
+    let scene = |s: &str| ObsAction::Scene { scene: s.into() };
+
+    assert_eq!(
+        automation.apply(StateChange::InGame(true)),
+        vec![scene("Game")]
+    );
+    assert!(automation.apply(StateChange::InGame(true)).is_empty());
+
+    let opened = automation.apply(StateChange::UiState(UiState::MAP_OPEN));
+    assert!(matches!(
+        opened.as_slice(),
+        [ObsAction::Source {
+            visible: Some(true),
+            ..
+        }]
+    ));
+    // combat bit alone changes nothing the rules care about
+    assert!(
+        automation
+            .apply(StateChange::UiState(UiState::MAP_OPEN | UiState::IN_COMBAT))
+            .is_empty()
+    );
+
+    assert_eq!(
+        automation.apply(StateChange::MapKind(MapKind::Wvw)),
+        vec![scene("WvW")]
+    );
+    assert!(
+        automation
+            .apply(StateChange::MapKind(MapKind::Wvw))
+            .is_empty()
+    );
+    assert!(
+        automation
+            .apply(StateChange::MapKind(MapKind::Pve))
+            .is_empty()
+    );
+
+    assert_eq!(
+        automation.apply(StateChange::InGame(false)),
+        vec![scene("BRB")]
+    );
+
+    assert!(parse_rules(r#"[{ "on": "lunch_break", "scene": "x" }]"#).is_err());
+    assert!(parse_rules("").unwrap().is_empty());
+}
@@ -0,0 +1,146 @@
+//! Snapshot tests for the SVG key renderer.
+//!
+//! Renders representative key states and compares them byte for byte with the
+//! files in `tests/snapshots/`. Open a snapshot in a browser to see the key.
+//!
+//! After an intended rendering change, refresh the snapshots with
+//! `UPDATE_RENDER_SNAPSHOTS=1 cargo test --test render_snapshots`.
+
+use std::{fs, path::PathBuf};
+
+use streamdeck_gw2::{
+    gw2::enums::{Identity, SquadMarker},
+    render::{CompassImage, KeyImage, MarkerImage, Overlay, Palette},
+};
+
+fn snapshot_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("snapshots")
+        .join(format!("{name}.svg"))
+}
+
+fn assert_snapshot(name: &str, svg: &str) {
+    let path = snapshot_path(name);
+    if std::env::var_os("UPDATE_RENDER_SNAPSHOTS").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, svg).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("{name}: no snapshot (run with UPDATE_RENDER_SNAPSHOTS=1)"));
+    assert_eq!(svg, expected, "{name}: rendering changed");
+}
+
+fn revenant_herald() -> Identity {
+    Identity {
+        name: "Rytlock Brimstone".into(),
+        profession: Some(9),
+        spec: Some(52),
+        race: None,
+        map_id: None,
+        world_id: None,
+        team_color_id: None,
+        commander: None,
+        fov: None,
+        uisz: None,
+    }
+}
+
+#[test]
+fn template_key_idle() {
+    let image = KeyImage {
+        header: Some("B2 · E3".into()),
+        name: Some("Power Herald".into()),
+        ..KeyImage::default()
+    }
+    .with_identity(Some(&revenant_herald()));
+    assert_snapshot("template_idle", &image.to_svg());
+}
+
+#[test]
+fn template_key_active() {
+    let image = KeyImage {
+        header: Some("B2 · E3".into()),
+        name: Some("Power Herald".into()),
+        overlay: Overlay::Active,
+        ..KeyImage::default()
+    }
+    .with_identity(Some(&revenant_herald()));
+    assert_snapshot("template_active", &image.to_svg());
+}
+
+#[test]
+fn template_key_queued_and_running() {
+    let queued = KeyImage {
+        header: Some("B1".into()),
+        name: Some("Condi".into()),
+        overlay: Overlay::Queued,
+        progress: Some(0.25),
+        ..KeyImage::default()
+    };
+    assert_snapshot("template_queued", &queued.to_svg());
+
+    let running = KeyImage {
+        overlay: Overlay::Running,
+        ..queued
+    };
+    assert_snapshot("template_running", &running.to_svg());
+}
+
+#[test]
+fn long_name_is_wrapped_and_cut() {
+    let image = KeyImage {
+        name: Some("Extremely Long Template Name That Never Ends".into()),
+        footer: Some("Untamed".into()),
+        ..KeyImage::default()
+    };
+    assert_snapshot("template_long_name", &image.to_svg());
+}
+
+#[test]
+fn text_is_escaped() {
+    let image = KeyImage {
+        palette: Palette::WARNING,
+        header: Some("<Fall>".into()),
+        name: Some("Tom & \"Jerry\"".into()),
+        ..KeyImage::default()
+    };
+    let svg = image.to_svg();
+    assert!(svg.contains("&lt;Fall&gt;"));
+    assert_snapshot("warning_escaped", &svg);
+}
+
+#[test]
+fn compass() {
+    let pointing = CompassImage {
+        header: Some("Vista".into()),
+        bearing: Some(135.0),
+        footer: Some("120 m".into()),
+        ..CompassImage::default()
+    };
+    assert_snapshot("compass_bearing", &pointing.to_svg());
+
+    let here = CompassImage {
+        bearing: None,
+        ..pointing
+    };
+    assert_snapshot("compass_here", &here.to_svg());
+}
+
+#[test]
+fn squad_markers() {
+    let location = MarkerImage {
+        marker: SquadMarker::Star,
+        object: false,
+        enabled: true,
+    };
+    assert_snapshot("marker_star_location", &location.to_svg());
+
+    let disabled = MarkerImage {
+        marker: SquadMarker::Heart,
+        object: true,
+        enabled: false,
+    };
+    assert_snapshot("marker_heart_disabled", &disabled.to_svg());
+}